@@ -1,7 +1,9 @@
 use crate::credential_store;
+use crate::http;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
 
 const AI_KEY_PREFIX: &str = "ai_key_";
 
@@ -61,8 +63,9 @@ pub async fn save_ai_api_key(provider: String, api_key: String) -> Result<(), St
     .map_err(|e| format!("Task failed: {}", e))?
 }
 
-#[tauri::command]
-pub async fn get_ai_api_key(provider: String) -> Result<String, String> {
+/// Keys never leave the backend; requests that need one go through
+/// `ai_proxy_request`.
+async fn get_ai_api_key(provider: String) -> Result<String, String> {
     if let Some(cached) = get_cached_ai_key(&provider) {
         return Ok(cached);
     }
@@ -148,3 +151,92 @@ pub async fn clear_all_ai_keys() -> Result<(), String> {
     .await
     .map_err(|e| format!("Task failed: {}", e))?
 }
+
+/// How each AI provider expects its API key. Requests are only forwarded to the
+/// provider's own API so a compromised webview can't send a key elsewhere.
+fn provider_api(provider: &str) -> Option<(&'static str, &'static str)> {
+    match provider {
+        "openai" => Some(("https://api.openai.com/", "authorization")),
+        "groq" => Some(("https://api.groq.com/", "authorization")),
+        "anthropic" => Some(("https://api.anthropic.com/", "x-api-key")),
+        "google" => Some(("https://generativelanguage.googleapis.com/", "x-goog-api-key")),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AIProxyRequest {
+    pub url: String,
+    pub method: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AIProxyResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// Sends a request from the AI SDK to the provider's API with the stored key
+/// added, so the webview only ever sees the response.
+#[tauri::command]
+pub async fn ai_proxy_request(
+    app: AppHandle,
+    provider: String,
+    request: AIProxyRequest,
+) -> Result<AIProxyResponse, String> {
+    let (base_url, key_header) =
+        provider_api(&provider).ok_or_else(|| format!("Unknown AI provider: {}", provider))?;
+    if !request.url.starts_with(base_url) {
+        return Err(format!("Refusing to send the {} key to {}", provider, request.url));
+    }
+    let api_key = get_ai_api_key(provider.clone()).await?;
+
+    let method = reqwest::Method::from_bytes(request.method.to_uppercase().as_bytes())
+        .map_err(|e| format!("Invalid method: {}", e))?;
+    let mut builder = http::client(&app).request(method, &request.url);
+    for (name, value) in &request.headers {
+        // Drop the SDK's placeholder credentials.
+        let lower = name.to_lowercase();
+        if lower == "authorization" || lower == "x-api-key" || lower == "x-goog-api-key" {
+            continue;
+        }
+        builder = builder.header(name, value);
+    }
+    builder = if key_header == "authorization" {
+        builder.bearer_auth(&api_key)
+    } else {
+        builder.header(key_header, &api_key)
+    };
+    if let Some(body) = request.body {
+        builder = builder.body(body);
+    }
+
+    let response = http::send(builder)
+        .await
+        .map_err(|e| format!("AI request failed: {}", e))?;
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.to_string(), value.to_string()))
+        })
+        .collect();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read AI response: {}", e))?;
+
+    Ok(AIProxyResponse {
+        status,
+        headers,
+        body,
+    })
+}
@@ -0,0 +1,503 @@
+//! Disk cache for album art. Covers are downloaded once per URL, scaled to
+//! the sizes the layouts show and served to the webview through the
+//! `minify-art://` protocol, so they appear right away and also offline.
+//! The cache is bounded by `album_art_cache_mb`; the least recently used
+//! files go first.
+
+use crate::http;
+use crate::player::PlayerState;
+use crate::settings::{self, CachedTrack};
+use crate::spotify_api::{self, Image, Priority};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{async_runtime as rt, AppHandle, Manager, UriSchemeResponder};
+use tokio::fs;
+
+pub const SCHEME: &str = "minify-art";
+
+/// Edge lengths in pixels that requested sizes are rounded up to, so each
+/// cover is stored in a few sizes only.
+const SIZES: [u32; 4] = [64, 128, 256, 384];
+const JPEG_QUALITY: u8 = 85;
+
+struct Entry {
+    bytes: u64,
+    last_used: SystemTime,
+}
+
+/// What is on disk, loaded on first use and kept up to date afterwards.
+struct Index {
+    entries: HashMap<String, Entry>,
+    total_bytes: u64,
+}
+
+lazy_static::lazy_static! {
+    static ref INDEX: Mutex<Option<Index>> = Mutex::new(None);
+    /// One download or resize per file at a time, so a prefetch and the
+    /// webview asking for the same cover share the work.
+    static ref IN_FLIGHT: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>> =
+        Mutex::new(HashMap::new());
+    /// Sizes the webview has asked for, which prefetching produces too.
+    static ref SIZES_IN_USE: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+}
+
+fn cache_dir(app: &AppHandle) -> PathBuf {
+    app.path()
+        .app_cache_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join("album-art")
+}
+
+fn snap_size(size: u32) -> u32 {
+    SIZES
+        .into_iter()
+        .find(|&candidate| candidate >= size)
+        .unwrap_or(SIZES[SIZES.len() - 1])
+}
+
+/// File name for a URL at a size, or as downloaded for `None`.
+fn file_name(url: &str, size: Option<u32>) -> String {
+    let hash = hex::encode(Sha256::digest(url.as_bytes()));
+    match size {
+        Some(size) => format!("{}-{}", hash, size),
+        None => format!("{}-full", hash),
+    }
+}
+
+fn content_type(bytes: &[u8]) -> &'static str {
+    image::guess_format(bytes)
+        .map(|format| format.to_mime_type())
+        .unwrap_or("application/octet-stream")
+}
+
+fn with_index<T>(dir: &Path, f: impl FnOnce(&mut Index) -> T) -> Option<T> {
+    let mut guard = INDEX.lock().ok()?;
+    let index = guard.get_or_insert_with(|| scan(dir));
+    Some(f(index))
+}
+
+fn scan(dir: &Path) -> Index {
+    let mut index = Index {
+        entries: HashMap::new(),
+        total_bytes: 0,
+    };
+    let Ok(files) = std::fs::read_dir(dir) else {
+        return index;
+    };
+    for file in files.flatten() {
+        let Ok(metadata) = file.metadata() else {
+            continue;
+        };
+        let name = file.file_name().to_string_lossy().into_owned();
+        if !metadata.is_file() || name.ends_with(".tmp") {
+            continue;
+        }
+        index.total_bytes += metadata.len();
+        index.entries.insert(
+            name,
+            Entry {
+                bytes: metadata.len(),
+                last_used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            },
+        );
+    }
+    index
+}
+
+async fn read_cached(dir: &Path, name: &str) -> Option<Vec<u8>> {
+    let known = with_index(dir, |index| match index.entries.get_mut(name) {
+        Some(entry) => {
+            entry.last_used = SystemTime::now();
+            true
+        }
+        None => false,
+    })?;
+    if !known {
+        return None;
+    }
+    let path = dir.join(name);
+    match fs::read(&path).await {
+        Ok(bytes) => {
+            // The modification time keeps the order of use across restarts.
+            let _ = std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(SystemTime::now()));
+            Some(bytes)
+        }
+        Err(_) => {
+            forget(dir, name);
+            None
+        }
+    }
+}
+
+fn forget(dir: &Path, name: &str) {
+    with_index(dir, |index| {
+        if let Some(entry) = index.entries.remove(name) {
+            index.total_bytes = index.total_bytes.saturating_sub(entry.bytes);
+        }
+    });
+}
+
+async fn store(app: &AppHandle, dir: &Path, name: &str, bytes: &[u8]) -> Result<(), String> {
+    fs::create_dir_all(dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(name);
+    let temp_path = dir.join(format!("{}.tmp", name));
+    fs::write(&temp_path, bytes)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+    fs::rename(&temp_path, &path)
+        .await
+        .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+
+    let limit = settings::read_settings(app.clone())
+        .album_art_cache_mb
+        .saturating_mul(1024 * 1024);
+    let evicted = with_index(dir, |index| {
+        let entry = Entry {
+            bytes: bytes.len() as u64,
+            last_used: SystemTime::now(),
+        };
+        index.total_bytes += entry.bytes;
+        if let Some(old) = index.entries.insert(name.to_string(), entry) {
+            index.total_bytes = index.total_bytes.saturating_sub(old.bytes);
+        }
+        evict(index, limit)
+    })
+    .unwrap_or_default();
+    for name in evicted {
+        let _ = fs::remove_file(dir.join(name)).await;
+    }
+    Ok(())
+}
+
+/// Drops the least recently used entries until the cache fits in `limit`
+/// bytes and returns their file names.
+fn evict(index: &mut Index, limit: u64) -> Vec<String> {
+    if index.total_bytes <= limit {
+        return Vec::new();
+    }
+    let mut by_age: Vec<(SystemTime, String)> = index
+        .entries
+        .iter()
+        .map(|(name, entry)| (entry.last_used, name.clone()))
+        .collect();
+    by_age.sort();
+
+    let mut evicted = Vec::new();
+    for (_, name) in by_age {
+        if index.total_bytes <= limit {
+            break;
+        }
+        if let Some(entry) = index.entries.remove(&name) {
+            index.total_bytes = index.total_bytes.saturating_sub(entry.bytes);
+            evicted.push(name);
+        }
+    }
+    evicted
+}
+
+fn in_flight(name: &str) -> Arc<tokio::sync::Mutex<()>> {
+    IN_FLIGHT
+        .lock()
+        .map(|mut in_flight| in_flight.entry(name.to_string()).or_default().clone())
+        .unwrap_or_default()
+}
+
+fn done(name: &str, lock: Arc<tokio::sync::Mutex<()>>) {
+    if let Ok(mut in_flight) = IN_FLIGHT.lock() {
+        // The map and this task hold the last references; nobody waits.
+        if Arc::strong_count(&lock) == 2 {
+            in_flight.remove(name);
+        }
+    }
+}
+
+async fn download(app: &AppHandle, url: &str) -> Result<Vec<u8>, String> {
+    let response = http::send(http::client(app).get(url))
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to download album art: {}", e))?;
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download album art: {}", e))?;
+    Ok(bytes.to_vec())
+}
+
+/// Scales the image down to fit `size`. Smaller images and formats that
+/// cannot be decoded are returned unchanged.
+fn resize(original: &[u8], size: u32) -> Vec<u8> {
+    let Ok(decoded) = image::load_from_memory(original) else {
+        return original.to_vec();
+    };
+    if decoded.width().max(decoded.height()) <= size {
+        return original.to_vec();
+    }
+    let scaled = decoded.resize(size, size, FilterType::Lanczos3).into_rgb8();
+    let mut encoded = Vec::new();
+    match JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY).encode_image(&scaled) {
+        Ok(()) => encoded,
+        Err(_) => original.to_vec(),
+    }
+}
+
+async fn load_at(
+    app: &AppHandle,
+    dir: &Path,
+    url: &str,
+    size: Option<u32>,
+) -> Result<Vec<u8>, String> {
+    let name = file_name(url, size);
+    if let Some(bytes) = read_cached(dir, &name).await {
+        return Ok(bytes);
+    }
+
+    let lock = in_flight(&name);
+    let result = async {
+        let _guard = lock.lock().await;
+        // Another request may have produced it while this one waited.
+        if let Some(bytes) = read_cached(dir, &name).await {
+            return Ok(bytes);
+        }
+        let bytes = match size {
+            None => download(app, url).await?,
+            Some(size) => {
+                let original = Box::pin(load_at(app, dir, url, None)).await?;
+                rt::spawn_blocking(move || resize(&original, size))
+                    .await
+                    .map_err(|e| format!("Task failed: {}", e))?
+            }
+        };
+        if let Err(e) = store(app, dir, &name, &bytes).await {
+            eprintln!("Album art not cached: {}", e);
+        }
+        Ok(bytes)
+    }
+    .await;
+    done(&name, lock);
+    result
+}
+
+/// The cover at `url`, scaled to fit `size` pixels, from the cache or
+/// downloaded into it.
+pub async fn load(app: &AppHandle, url: &str, size: Option<u32>) -> Result<Vec<u8>, String> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(format!("Not an image URL: {}", url));
+    }
+    load_at(app, &cache_dir(app), url, size.map(snap_size)).await
+}
+
+/// Parses `/<size>/<image URL>`, percent-encoded as one segment or not.
+/// The size is a number of pixels or `full`.
+fn parse_path(path: &str) -> Option<(Option<u32>, String)> {
+    let decoded = urlencoding::decode(path.trim_start_matches('/')).ok()?;
+    let (size, url) = decoded.split_once('/')?;
+    let size = match size {
+        "full" => None,
+        size => Some(size.parse::<u32>().ok()?.max(1)),
+    };
+    Some((size, url.to_string()))
+}
+
+fn respond(responder: UriSchemeResponder, status: StatusCode, bytes: Vec<u8>) {
+    let content_type = if status.is_success() {
+        content_type(&bytes)
+    } else {
+        "text/plain"
+    };
+    let response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "max-age=31536000, immutable")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(bytes);
+    if let Ok(response) = response {
+        responder.respond(response);
+    }
+}
+
+/// Serves `minify-art://localhost/<size>/<image URL>`.
+pub fn handle_request(app: AppHandle, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    let Some((size, url)) = parse_path(request.uri().path()) else {
+        respond(
+            responder,
+            StatusCode::BAD_REQUEST,
+            b"Invalid album art path".to_vec(),
+        );
+        return;
+    };
+    if let Some(size) = size {
+        if let Ok(mut sizes) = SIZES_IN_USE.lock() {
+            sizes.insert(snap_size(size));
+        }
+    }
+    rt::spawn(async move {
+        match load(&app, &url, size).await {
+            Ok(bytes) => respond(responder, StatusCode::OK, bytes),
+            Err(e) => respond(responder, StatusCode::BAD_GATEWAY, e.into_bytes()),
+        }
+    });
+}
+
+/// The URL the layouts show, so prefetching fills the same cache entries.
+fn largest_image(images: &[Image]) -> Option<&str> {
+    images
+        .iter()
+        .max_by_key(|image| image.width.unwrap_or(0))
+        .map(|image| image.url.as_str())
+}
+
+/// Fetches the art of the next queued track in the sizes in use, so it is
+/// on disk when that track starts.
+async fn prefetch_next(app: &AppHandle, track: &CachedTrack) {
+    if track.provider != "spotify" {
+        return;
+    }
+    let Ok(queue) = spotify_api::queue(app, Priority::Background).await else {
+        return;
+    };
+    let Some(url) = queue
+        .queue
+        .first()
+        .and_then(|next| largest_image(&next.album.images))
+    else {
+        return;
+    };
+    let sizes: Vec<u32> = SIZES_IN_USE
+        .lock()
+        .map(|sizes| sizes.iter().copied().collect())
+        .unwrap_or_default();
+    for size in sizes {
+        if let Err(e) = load(app, url, Some(size)).await {
+            eprintln!("Album art prefetch failed: {}", e);
+            return;
+        }
+    }
+}
+
+pub fn init_album_art(app: &AppHandle) {
+    let app = app.clone();
+    let mut receiver = app.state::<PlayerState>().subscribe();
+    rt::spawn(async move {
+        let mut last_track_id: Option<String> = None;
+        while receiver.changed().await.is_ok() {
+            let Some(track) = receiver.borrow_and_update().track.clone() else {
+                continue;
+            };
+            if last_track_id.as_deref() == Some(track.id.as_str()) {
+                continue;
+            }
+            last_track_id = Some(track.id.clone());
+            prefetch_next(&app, &track).await;
+        }
+    });
+}
+
+/// Deletes every cached cover.
+#[tauri::command]
+pub async fn clear_album_art_cache(app: AppHandle) -> Result<(), String> {
+    let dir = cache_dir(&app);
+    if let Ok(mut index) = INDEX.lock() {
+        *index = None;
+    }
+    match fs::remove_dir_all(&dir).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to clear {}: {}", dir.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn index(entries: &[(&str, u64, u64)]) -> Index {
+        let entries: HashMap<String, Entry> = entries
+            .iter()
+            .map(|&(name, bytes, age_secs)| {
+                let last_used = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000 - age_secs);
+                (name.to_string(), Entry { bytes, last_used })
+            })
+            .collect();
+        let total_bytes = entries.values().map(|entry| entry.bytes).sum();
+        Index {
+            entries,
+            total_bytes,
+        }
+    }
+
+    #[test]
+    fn snaps_sizes_up_to_the_next_stored_size() {
+        assert_eq!(snap_size(1), 64);
+        assert_eq!(snap_size(64), 64);
+        assert_eq!(snap_size(65), 128);
+        assert_eq!(snap_size(300), 384);
+        assert_eq!(snap_size(2000), 384);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entries_first() {
+        let mut index = index(&[("new", 100, 10), ("old", 100, 30), ("middle", 100, 20)]);
+        assert_eq!(evict(&mut index, 150), vec!["old", "middle"]);
+        assert_eq!(index.total_bytes, 100);
+        assert!(index.entries.contains_key("new"));
+        assert_eq!(index.entries.len(), 1);
+    }
+
+    #[test]
+    fn keeps_everything_that_fits() {
+        let mut index = index(&[("a", 100, 10), ("b", 50, 20)]);
+        assert!(evict(&mut index, 150).is_empty());
+        assert_eq!(index.total_bytes, 150);
+        assert_eq!(index.entries.len(), 2);
+
+        assert_eq!(evict(&mut index, 0), vec!["b", "a"]);
+        assert_eq!(index.total_bytes, 0);
+        assert!(index.entries.is_empty());
+    }
+
+    #[test]
+    fn parses_encoded_and_plain_paths() {
+        let url = "https://i.scdn.co/image/ab67616d0000b273";
+        let expected = Some((Some(256), url.to_string()));
+        assert_eq!(parse_path(&format!("/256/{}", url)), expected);
+        assert_eq!(
+            parse_path(&format!(
+                "/{}",
+                urlencoding::encode(&format!("256/{}", url))
+            )),
+            expected
+        );
+        assert_eq!(
+            parse_path(&format!("/256/{}", urlencoding::encode(url))),
+            expected
+        );
+        assert_eq!(
+            parse_path(&format!("/full/{}", url)),
+            Some((None, url.to_string()))
+        );
+        assert_eq!(
+            parse_path(&format!("/0/{}", url)),
+            Some((Some(1), url.to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_paths_without_a_size() {
+        assert_eq!(parse_path("/large/https://i.scdn.co/image/a"), None);
+        assert_eq!(parse_path("/-1/https://i.scdn.co/image/a"), None);
+        assert_eq!(parse_path("/https:"), None);
+        assert_eq!(parse_path(""), None);
+    }
+}
@@ -0,0 +1,415 @@
+use crate::credential_store;
+use crate::overlay;
+use crate::player::{self, AiQueueSnapshot, PlayerCommand, PlayerState, PlayerStatus};
+use crate::settings::{self, CachedTrack};
+use crate::tray;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, MethodRouter};
+use axum::{Json, Router};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use tauri::async_runtime as rt;
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+use tokio::time::sleep;
+
+const API_TOKEN_KEY: &str = "api_server_token";
+
+lazy_static::lazy_static! {
+    static ref API_SHUTDOWN: Mutex<Option<watch::Sender<bool>>> = Mutex::new(None);
+    static ref API_STATUS: Mutex<ApiServerStatus> = Mutex::new(ApiServerStatus::default());
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApiServerStatus {
+    pub running: bool,
+    pub address: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone)]
+struct ApiContext {
+    app: AppHandle,
+    token: Arc<String>,
+    shutdown: watch::Receiver<bool>,
+}
+
+/// Messages pushed to WebSocket clients.
+#[derive(Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+enum ApiEvent {
+    Track(PlayerStatus),
+    Playback(PlayerStatus),
+    AiQueue(AiQueueSnapshot),
+}
+
+#[derive(Deserialize)]
+struct SeekBody {
+    position_ms: u64,
+}
+
+#[derive(Deserialize)]
+struct VolumeBody {
+    volume: u8,
+}
+
+#[derive(Deserialize)]
+struct LayoutBody {
+    layout: String,
+}
+
+fn api_error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+async fn load_or_create_token() -> Result<String, String> {
+    tokio::task::spawn_blocking(|| match credential_store::find(API_TOKEN_KEY)? {
+        Some(token) if !token.is_empty() => Ok(token),
+        _ => {
+            let token = generate_token();
+            credential_store::set(API_TOKEN_KEY, &token)
+                .map_err(|e| format!("Failed to save API token: {e}"))?;
+            Ok(token)
+        }
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Compares in constant time so the token can't be guessed byte by byte.
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn require_token(State(ctx): State<ApiContext>, request: Request, next: Next) -> Response {
+    let header_token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    // Browsers can't set headers on WebSocket connections, so `?token=` works too.
+    let query_token = request.uri().query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "token")
+            .map(|(_, value)| value.into_owned())
+    });
+
+    match header_token.or(query_token) {
+        Some(token) if tokens_match(&token, &ctx.token) => next.run(request).await,
+        _ => api_error(StatusCode::UNAUTHORIZED, "Missing or invalid API token"),
+    }
+}
+
+fn command_route(command: PlayerCommand) -> MethodRouter<ApiContext> {
+    post(move |State(ctx): State<ApiContext>| {
+        let command = command.clone();
+        async move {
+            player::send_command(&ctx.app, command);
+            StatusCode::NO_CONTENT
+        }
+    })
+}
+
+async fn get_status(State(ctx): State<ApiContext>) -> Json<PlayerStatus> {
+    Json(ctx.app.state::<PlayerState>().snapshot().status())
+}
+
+async fn get_track(State(ctx): State<ApiContext>) -> Json<Option<CachedTrack>> {
+    Json(ctx.app.state::<PlayerState>().snapshot().track)
+}
+
+async fn get_ai_queue(State(ctx): State<ApiContext>) -> Json<AiQueueSnapshot> {
+    Json(ctx.app.state::<PlayerState>().ai_queue())
+}
+
+async fn post_command(
+    State(ctx): State<ApiContext>,
+    Json(command): Json<PlayerCommand>,
+) -> StatusCode {
+    player::send_command(&ctx.app, command);
+    StatusCode::NO_CONTENT
+}
+
+async fn post_seek(State(ctx): State<ApiContext>, Json(body): Json<SeekBody>) -> StatusCode {
+    player::send_command(
+        &ctx.app,
+        PlayerCommand::Seek {
+            position_ms: body.position_ms,
+        },
+    );
+    StatusCode::NO_CONTENT
+}
+
+async fn post_volume(State(ctx): State<ApiContext>, Json(body): Json<VolumeBody>) -> Response {
+    if body.volume > 100 {
+        return api_error(StatusCode::BAD_REQUEST, "Volume must be between 0 and 100");
+    }
+    player::send_command(
+        &ctx.app,
+        PlayerCommand::SetVolume {
+            volume: body.volume,
+        },
+    );
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn post_layout(State(ctx): State<ApiContext>, Json(body): Json<LayoutBody>) -> Response {
+    if !tray::is_layout(&body.layout) {
+        return api_error(StatusCode::BAD_REQUEST, "Unknown layout");
+    }
+    tray::select_layout(&ctx.app, &body.layout);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn send_event(socket: &mut WebSocket, event: &ApiEvent) -> bool {
+    match serde_json::to_string(event) {
+        Ok(text) => socket.send(Message::Text(text.into())).await.is_ok(),
+        Err(_) => true,
+    }
+}
+
+/// Pushes playback, track and AI queue changes, and runs `PlayerCommand`s
+/// sent by the client as JSON text messages.
+async fn push_events(ctx: ApiContext, mut socket: WebSocket) {
+    let mut playback = ctx.app.state::<PlayerState>().subscribe();
+    let mut ai_queue = ctx.app.state::<PlayerState>().subscribe_ai_queue();
+    let mut shutdown = ctx.shutdown.clone();
+
+    let snapshot = playback.borrow_and_update().clone();
+    let mut last_track = snapshot.track.as_ref().map(|track| track.id.clone());
+    let queue = ai_queue.borrow_and_update().clone();
+    if !send_event(&mut socket, &ApiEvent::Track(snapshot.status())).await
+        || !send_event(&mut socket, &ApiEvent::AiQueue(queue)).await
+    {
+        return;
+    }
+
+    loop {
+        let event = tokio::select! {
+            changed = playback.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                let snapshot = playback.borrow_and_update().clone();
+                let track = snapshot.track.as_ref().map(|track| track.id.clone());
+                if track != last_track {
+                    last_track = track;
+                    ApiEvent::Track(snapshot.status())
+                } else {
+                    ApiEvent::Playback(snapshot.status())
+                }
+            }
+            changed = ai_queue.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                ApiEvent::AiQueue(ai_queue.borrow_and_update().clone())
+            }
+            message = socket.recv() => {
+                match message {
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<PlayerCommand>(text.as_str()) {
+                            Ok(command) => player::send_command(&ctx.app, command),
+                            Err(e) => eprintln!("Ignoring invalid API command: {}", e),
+                        }
+                        continue;
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                    Some(Ok(_)) => continue,
+                }
+            }
+            _ = shutdown.changed() => {
+                let _ = socket.send(Message::Close(None)).await;
+                return;
+            }
+        };
+        if !send_event(&mut socket, &event).await {
+            return;
+        }
+    }
+}
+
+async fn websocket(State(ctx): State<ApiContext>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| push_events(ctx, socket))
+}
+
+fn router(ctx: ApiContext) -> Router {
+    Router::new()
+        .route("/api/v1/status", get(get_status))
+        .route("/api/v1/track", get(get_track))
+        .route("/api/v1/ai-queue", get(get_ai_queue))
+        .route("/api/v1/play", command_route(PlayerCommand::Play))
+        .route("/api/v1/pause", command_route(PlayerCommand::Pause))
+        .route("/api/v1/toggle", command_route(PlayerCommand::PlayPause))
+        .route("/api/v1/next", command_route(PlayerCommand::Next))
+        .route("/api/v1/previous", command_route(PlayerCommand::Previous))
+        .route("/api/v1/like", command_route(PlayerCommand::LikeTrack))
+        .route("/api/v1/seek", post(post_seek))
+        .route("/api/v1/volume", post(post_volume))
+        .route("/api/v1/layout", post(post_layout))
+        .route("/api/v1/command", post(post_command))
+        .route("/api/v1/ws", get(websocket))
+        .with_state(ctx.clone())
+        .merge(overlay::router(ctx.app.clone(), ctx.shutdown.clone()))
+        .layer(middleware::from_fn_with_state(ctx, require_token))
+}
+
+fn set_status(status: ApiServerStatus) -> ApiServerStatus {
+    if let Ok(mut current) = API_STATUS.lock() {
+        *current = status.clone();
+    }
+    status
+}
+
+fn stop() -> bool {
+    let sender = API_SHUTDOWN.lock().ok().and_then(|mut s| s.take());
+    match sender {
+        Some(tx) => {
+            let _ = tx.send(true);
+            true
+        }
+        None => false,
+    }
+}
+
+async fn start(app: &AppHandle) -> ApiServerStatus {
+    if stop() {
+        // Give the old listener a moment to release the port.
+        sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    let settings = settings::read_settings(app.clone());
+    if !settings.api_server_enabled {
+        return set_status(ApiServerStatus::default());
+    }
+
+    let token = match load_or_create_token().await {
+        Ok(token) => token,
+        Err(e) => {
+            return set_status(ApiServerStatus {
+                error: Some(e),
+                ..Default::default()
+            })
+        }
+    };
+
+    let ip = if settings.api_server_allow_lan {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    } else {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    };
+    let addr = SocketAddr::new(ip, settings.api_server_port);
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            let message = if e.kind() == std::io::ErrorKind::AddrInUse {
+                format!("Port {} is already in use", settings.api_server_port)
+            } else {
+                format!("Couldn't listen on {}: {}", addr, e)
+            };
+            eprintln!("API server not started: {}", message);
+            return set_status(ApiServerStatus {
+                error: Some(message),
+                ..Default::default()
+            });
+        }
+    };
+
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+    if let Ok(mut shutdown) = API_SHUTDOWN.lock() {
+        *shutdown = Some(shutdown_tx);
+    }
+
+    let ctx = ApiContext {
+        app: app.clone(),
+        token: Arc::new(token),
+        shutdown: shutdown_rx.clone(),
+    };
+    rt::spawn(async move {
+        let server = axum::serve(listener, router(ctx)).with_graceful_shutdown(async move {
+            let _ = shutdown_rx.changed().await;
+        });
+        if let Err(e) = server.await {
+            eprintln!("API server stopped: {}", e);
+        }
+    });
+
+    set_status(ApiServerStatus {
+        running: true,
+        address: Some(addr.to_string()),
+        error: None,
+    })
+}
+
+#[tauri::command]
+pub fn get_api_server_status() -> ApiServerStatus {
+    API_STATUS
+        .lock()
+        .map(|status| status.clone())
+        .unwrap_or_default()
+}
+
+/// Applies the current settings: starts, stops or rebinds the server.
+#[tauri::command]
+pub async fn restart_api_server(app: AppHandle) -> ApiServerStatus {
+    start(&app).await
+}
+
+#[tauri::command]
+pub async fn get_api_server_token() -> Result<String, String> {
+    load_or_create_token().await
+}
+
+/// Replaces the token and disconnects every client that used the old one.
+#[tauri::command]
+pub async fn regenerate_api_server_token(app: AppHandle) -> Result<String, String> {
+    let token = generate_token();
+    let token_clone = token.clone();
+    tokio::task::spawn_blocking(move || {
+        credential_store::set(API_TOKEN_KEY, &token_clone)
+            .map_err(|e| format!("Failed to save API token: {e}"))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))??;
+
+    if get_api_server_status().running {
+        start(&app).await;
+    }
+    Ok(token)
+}
+
+pub async fn clear_api_server_token() -> Result<(), String> {
+    stop();
+    set_status(ApiServerStatus::default());
+    tokio::task::spawn_blocking(|| {
+        let _ = credential_store::delete(API_TOKEN_KEY);
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))
+}
+
+pub fn init_api_server(app: &AppHandle) {
+    let app = app.clone();
+    rt::spawn(async move {
+        start(&app).await;
+    });
+}
@@ -0,0 +1,62 @@
+//! Replaces files through a sibling temp file that is renamed over them, so
+//! a crash mid-write or a reader like an OBS text source never sees a
+//! half-written file.
+
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+fn write_with(path: &Path, content: &[u8], options: &OpenOptions) -> Result<(), String> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let temp_path = temp_path(path);
+    let mut file = options
+        .open(&temp_path)
+        .map_err(|e| format!("Failed to create {}: {}", temp_path.display(), e))?;
+    file.write_all(content)
+        .and_then(|()| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+    fs::rename(&temp_path, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+fn options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    options
+}
+
+/// Replaces `path` with `content`, creating its directory if needed.
+pub fn write(path: &Path, content: &[u8]) -> Result<(), String> {
+    write_with(path, content, &options())
+}
+
+/// Like `write`, for files only the owner may read, like credentials.
+#[cfg(target_os = "linux")]
+pub fn write_private(path: &Path, content: &[u8]) -> Result<(), String> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    // `mode` only applies to new files, so a temp file left behind by a
+    // crash would keep its own.
+    let _ = fs::remove_file(temp_path(path));
+    let mut options = options();
+    options.mode(0o600);
+    write_with(path, content, &options)
+}
+
+/// Replaces `path` with `value` as pretty-printed JSON.
+pub fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    write(path, &json)
+}
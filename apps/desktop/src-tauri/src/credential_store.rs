@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 #[cfg(not(target_os = "linux"))]
 mod platform {
     use keyring::Entry;
@@ -14,10 +16,12 @@ mod platform {
             .map_err(|error| format!("Failed to save credential: {error}"))
     }
 
-    pub fn get(key: &str) -> Result<String, String> {
-        entry(key)?
-            .get_password()
-            .map_err(|error| format!("Failed to read credential: {error}"))
+    pub fn find(key: &str) -> Result<Option<String>, String> {
+        match entry(key)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(error) => Err(format!("Failed to read credential: {error}")),
+        }
     }
 
     pub fn delete(key: &str) -> Result<(), String> {
@@ -29,6 +33,7 @@ mod platform {
 
 #[cfg(target_os = "linux")]
 mod platform {
+    use crate::atomic_file;
     use base64::{engine::general_purpose, Engine as _};
     use chacha20poly1305::{
         aead::{Aead, KeyInit, Payload},
@@ -145,23 +150,10 @@ mod platform {
     }
 
     fn write_store(path: &Path, store: &CredentialStore) -> Result<(), String> {
-        let temp_path = path.with_extension("json.tmp");
         let content = serde_json::to_vec(store)
             .map_err(|error| format!("Failed to serialize credential store: {error}"))?;
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .mode(0o600)
-            .open(&temp_path)
-            .map_err(|error| format!("Failed to create credential store: {error}"))?;
-        file.write_all(&content)
-            .and_then(|_| file.sync_all())
-            .map_err(|error| format!("Failed to persist credential store: {error}"))?;
-        fs::set_permissions(&temp_path, fs::Permissions::from_mode(0o600))
-            .map_err(|error| format!("Failed to secure credential store: {error}"))?;
-        fs::rename(&temp_path, path)
-            .map_err(|error| format!("Failed to replace credential store: {error}"))
+        atomic_file::write_private(path, &content)
+            .map_err(|error| format!("Failed to persist credential store: {error}"))
     }
 
     fn cipher(key: &[u8; KEY_LENGTH]) -> Result<XChaCha20Poly1305, String> {
@@ -199,7 +191,7 @@ mod platform {
         write_store(&path, &store)
     }
 
-    pub fn get(key: &str) -> Result<String, String> {
+    pub fn find(key: &str) -> Result<Option<String>, String> {
         let _guard = store_lock()
             .lock()
             .map_err(|_| "Credential store lock is poisoned".to_string())?;
@@ -207,10 +199,9 @@ mod platform {
         let encryption_key = read_or_create_key(&directory)?;
         let path = store_path(&directory);
         let store = read_store(&path)?;
-        let encrypted = store
-            .entries
-            .get(key)
-            .ok_or_else(|| format!("Credential not found: {key}"))?;
+        let Some(encrypted) = store.entries.get(key) else {
+            return Ok(None);
+        };
         let nonce = general_purpose::STANDARD
             .decode(&encrypted.nonce)
             .map_err(|_| "Credential nonce is invalid".to_string())?;
@@ -229,7 +220,9 @@ mod platform {
                 },
             )
             .map_err(|_| "Failed to decrypt credential".to_string())?;
-        String::from_utf8(plaintext).map_err(|_| "Credential is not valid UTF-8".to_string())
+        String::from_utf8(plaintext)
+            .map(Some)
+            .map_err(|_| "Credential is not valid UTF-8".to_string())
     }
 
     pub fn delete(key: &str) -> Result<(), String> {
@@ -246,4 +239,51 @@ mod platform {
     }
 }
 
-pub use platform::{delete, get, set};
+/// Debug builds can keep credentials in this map instead of the platform
+/// store, see [`use_memory_store`].
+#[cfg(debug_assertions)]
+static MEMORY_STORE: std::sync::Mutex<Option<HashMap<String, String>>> =
+    std::sync::Mutex::new(None);
+
+/// Keeps credentials in memory for the rest of the process, so tests never
+/// read or write the user's keychain or credential file.
+#[cfg(debug_assertions)]
+pub fn use_memory_store() {
+    let mut store = MEMORY_STORE.lock().unwrap_or_else(|e| e.into_inner());
+    store.get_or_insert_with(HashMap::new);
+}
+
+#[cfg(debug_assertions)]
+fn with_memory_store<T>(f: impl FnOnce(&mut HashMap<String, String>) -> T) -> Option<T> {
+    let mut store = MEMORY_STORE.lock().unwrap_or_else(|e| e.into_inner());
+    store.as_mut().map(f)
+}
+
+#[cfg(not(debug_assertions))]
+fn with_memory_store<T>(_: impl FnOnce(&mut HashMap<String, String>) -> T) -> Option<T> {
+    None
+}
+
+pub fn set(key: &str, value: &str) -> Result<(), String> {
+    with_memory_store(|store| {
+        store.insert(key.to_string(), value.to_string());
+    })
+    .map_or_else(|| platform::set(key, value), Ok)
+}
+
+/// Like `get`, with `None` for a credential that was never saved, so callers
+/// can tell it apart from a store that can't be read.
+pub fn find(key: &str) -> Result<Option<String>, String> {
+    with_memory_store(|store| Ok(store.get(key).cloned())).unwrap_or_else(|| platform::find(key))
+}
+
+pub fn get(key: &str) -> Result<String, String> {
+    find(key)?.ok_or_else(|| format!("Credential not found: {key}"))
+}
+
+pub fn delete(key: &str) -> Result<(), String> {
+    with_memory_store(|store| {
+        store.remove(key);
+    })
+    .map_or_else(|| platform::delete(key), Ok)
+}
@@ -0,0 +1,269 @@
+use crate::ipc::{self, IpcRequest, IpcResponse};
+use crate::player::{PlayerCommand, PlayerStatus};
+use std::io::{ErrorKind, Write};
+
+pub const EXIT_OK: i32 = 0;
+/// The player rejected the command, or `status` found nothing playing.
+pub const EXIT_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NOT_RUNNING: i32 = 3;
+
+const USAGE: &str = "Usage: minify ctl <command> [arguments]
+
+Commands:
+  play                      Start or resume playback
+  pause                     Pause playback
+  toggle                    Toggle between play and pause
+  next                      Skip to the next track
+  prev                      Go back to the previous track
+  seek <position>           Seek to seconds or m:ss, or by +/-seconds
+  volume <level>            Set the volume (0-100), or change it by +/-level
+  like                      Save the current track to your library
+  layout <name>             Switch layout, e.g. LayoutF
+  status [options]          Print the playback status as JSON
+    --format <template>     Print a template instead, e.g. \"{artist} - {title}\"
+    --wait                  Keep running and print a line whenever the track or
+                            play state changes
+
+Placeholders: {title} {artist} {album} {status} {position} {duration} {volume}
+              {provider} {id}
+
+Exit codes: 0 success, 1 command failed or nothing playing, 2 usage error,
+            3 MiniFy is not running";
+
+#[derive(Debug)]
+enum CtlCommand {
+    Send(PlayerCommand),
+    Seek(Position),
+    Volume(Position),
+    Layout(String),
+    Status { format: Option<String>, wait: bool },
+    Help,
+}
+
+/// An absolute value or an offset from the current one.
+#[derive(Debug, Clone, Copy)]
+enum Position {
+    Absolute(u64),
+    Relative(i64),
+}
+
+fn parse_seconds(value: &str) -> Option<u64> {
+    match value.split_once(':') {
+        Some((minutes, seconds)) => {
+            let seconds: u64 = seconds.parse().ok()?;
+            if seconds >= 60 {
+                return None;
+            }
+            Some(minutes.parse::<u64>().ok()? * 60 + seconds)
+        }
+        None => value.parse().ok(),
+    }
+}
+
+fn parse_position(value: &str, parse: impl Fn(&str) -> Option<u64>) -> Option<Position> {
+    if let Some(offset) = value.strip_prefix('+') {
+        parse(offset).map(|offset| Position::Relative(offset as i64))
+    } else if let Some(offset) = value.strip_prefix('-') {
+        parse(offset).map(|offset| Position::Relative(-(offset as i64)))
+    } else {
+        parse(value).map(Position::Absolute)
+    }
+}
+
+fn required<'a>(
+    args: &mut impl Iterator<Item = &'a String>,
+    name: &str,
+) -> Result<&'a String, String> {
+    args.next().ok_or_else(|| format!("Missing {}", name))
+}
+
+fn parse_args(args: &[String]) -> Result<CtlCommand, String> {
+    let mut args = args.iter();
+    let Some(command) = args.next() else {
+        return Ok(CtlCommand::Help);
+    };
+
+    let parsed = match command.as_str() {
+        "play" => CtlCommand::Send(PlayerCommand::Play),
+        "pause" => CtlCommand::Send(PlayerCommand::Pause),
+        "toggle" | "play-pause" => CtlCommand::Send(PlayerCommand::PlayPause),
+        "next" => CtlCommand::Send(PlayerCommand::Next),
+        "prev" | "previous" => CtlCommand::Send(PlayerCommand::Previous),
+        "like" => CtlCommand::Send(PlayerCommand::LikeTrack),
+        "seek" => {
+            let value = required(&mut args, "seek position")?;
+            let position = parse_position(value, parse_seconds)
+                .ok_or_else(|| format!("Invalid seek position \"{}\"", value))?;
+            CtlCommand::Seek(position)
+        }
+        "volume" => {
+            let value = required(&mut args, "volume level")?;
+            let level = parse_position(value, |v| v.parse::<u64>().ok().filter(|v| *v <= 100))
+                .ok_or_else(|| format!("Invalid volume \"{}\", expected 0-100", value))?;
+            CtlCommand::Volume(level)
+        }
+        "layout" => CtlCommand::Layout(required(&mut args, "layout name")?.clone()),
+        "status" => {
+            let mut format = None;
+            let mut wait = false;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" => format = Some(required(&mut args, "format template")?.clone()),
+                    "--wait" => wait = true,
+                    other => return Err(format!("Unknown status option \"{}\"", other)),
+                }
+            }
+            return Ok(CtlCommand::Status { format, wait });
+        }
+        "help" | "--help" | "-h" => CtlCommand::Help,
+        other => return Err(format!("Unknown command \"{}\"", other)),
+    };
+
+    match args.next() {
+        Some(extra) => Err(format!("Unexpected argument \"{}\"", extra)),
+        None => Ok(parsed),
+    }
+}
+
+/// Failure of a single request, already mapped to an exit code.
+struct CtlError {
+    code: i32,
+    message: String,
+}
+
+impl From<std::io::Error> for CtlError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            ErrorKind::NotFound | ErrorKind::ConnectionRefused => CtlError {
+                code: EXIT_NOT_RUNNING,
+                message: "MiniFy is not running".to_string(),
+            },
+            _ => CtlError {
+                code: EXIT_FAILED,
+                message: format!("Failed to talk to MiniFy: {}", e),
+            },
+        }
+    }
+}
+
+fn request(request: IpcRequest) -> Result<IpcResponse, CtlError> {
+    let response = ipc::send_request(&request)?;
+    if response.ok {
+        return Ok(response);
+    }
+    Err(failed(
+        response
+            .error
+            .as_deref()
+            .unwrap_or("MiniFy rejected the command"),
+    ))
+}
+
+fn status() -> Result<PlayerStatus, CtlError> {
+    Ok(request(IpcRequest::Status)?.status.unwrap_or_default())
+}
+
+fn failed(message: &str) -> CtlError {
+    CtlError {
+        code: EXIT_FAILED,
+        message: message.to_string(),
+    }
+}
+
+fn send(command: PlayerCommand) -> Result<(), CtlError> {
+    request(IpcRequest::Command { command }).map(|_| ())
+}
+
+fn seek(position: Position) -> Result<(), CtlError> {
+    let position_ms = match position {
+        Position::Absolute(seconds) => seconds * 1000,
+        Position::Relative(offset) => {
+            let current = status()?;
+            let Some(duration_ms) = current.duration_ms else {
+                return Err(failed("Nothing is playing"));
+            };
+            (current.position_ms as i64 + offset * 1000).clamp(0, duration_ms as i64) as u64
+        }
+    };
+    send(PlayerCommand::Seek { position_ms })
+}
+
+fn set_volume(level: Position) -> Result<(), CtlError> {
+    let volume = match level {
+        Position::Absolute(volume) => volume as u8,
+        Position::Relative(offset) => {
+            let Some(current) = status()?.volume else {
+                return Err(failed(
+                    "The current volume is unknown, set an absolute level first",
+                ));
+            };
+            (current as i64 + offset).clamp(0, 100) as u8
+        }
+    };
+    send(PlayerCommand::SetVolume { volume })
+}
+
+fn render(status: &PlayerStatus, format: Option<&str>) -> String {
+    match format {
+        Some(template) => status.format(template),
+        None => serde_json::to_string(status).unwrap_or_default(),
+    }
+}
+
+fn print_status(format: Option<&str>, wait: bool) -> Result<(), CtlError> {
+    if !wait {
+        let current = status()?;
+        println!("{}", render(&current, format));
+        // Status bars can hide their module when nothing is playing.
+        if current.status == "stopped" {
+            return Err(failed(""));
+        }
+        return Ok(());
+    }
+
+    ipc::watch(|status| {
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{}", render(&status, format));
+        let _ = stdout.flush();
+    })?;
+    // The stream only ends when the player quits.
+    Err(CtlError {
+        code: EXIT_NOT_RUNNING,
+        message: "MiniFy exited".to_string(),
+    })
+}
+
+fn execute(command: CtlCommand) -> Result<(), CtlError> {
+    match command {
+        CtlCommand::Send(command) => send(command),
+        CtlCommand::Seek(position) => seek(position),
+        CtlCommand::Volume(level) => set_volume(level),
+        CtlCommand::Layout(layout) => request(IpcRequest::Layout { layout }).map(|_| ()),
+        CtlCommand::Status { format, wait } => print_status(format.as_deref(), wait),
+        CtlCommand::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+/// Entry point of `minify ctl`; returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let command = match parse_args(args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("minify ctl: {}\n\n{}", e, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    match execute(command) {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            if !e.message.is_empty() {
+                eprintln!("minify ctl: {}", e.message);
+            }
+            e.code
+        }
+    }
+}
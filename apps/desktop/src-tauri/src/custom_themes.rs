@@ -148,6 +148,35 @@ pub fn load_custom_themes(app: AppHandle) -> Vec<CustomTheme> {
         .collect()
 }
 
+/// Themes bundled from `src/themes`, as `(name, json)`.
+pub const BUILTIN_THEMES: &[(&str, &str)] = &[
+    ("dark", include_str!("../../src/themes/dark.json")),
+    ("light", include_str!("../../src/themes/light.json")),
+    ("aurora", include_str!("../../src/themes/aurora.json")),
+    ("bmw", include_str!("../../src/themes/bmw.json")),
+    ("catppuccin", include_str!("../../src/themes/catppuccin.json")),
+    ("chatgpt", include_str!("../../src/themes/chatgpt.json")),
+    ("dracula", include_str!("../../src/themes/dracula.json")),
+    ("ember", include_str!("../../src/themes/ember.json")),
+    ("milka", include_str!("../../src/themes/milka.json")),
+    ("youtube", include_str!("../../src/themes/youtube.json")),
+];
+
+/// Resolves the theme selected in settings, either a built-in name or
+/// `custom:<name>` for a user theme.
+pub fn active_theme(app: &AppHandle) -> Option<CustomTheme> {
+    let selected = crate::settings::read_settings(app.clone()).theme;
+    match selected.strip_prefix("custom:") {
+        Some(name) => load_custom_themes(app.clone())
+            .into_iter()
+            .find(|theme| theme.name == name),
+        None => BUILTIN_THEMES
+            .iter()
+            .find(|(builtin, _)| *builtin == selected)
+            .and_then(|(_, json)| serde_json::from_str(json).ok()),
+    }
+}
+
 #[tauri::command]
 pub fn delete_custom_theme(app: AppHandle, theme_name: String) -> Result<bool, String> {
     let filename = format!("{}.json", sanitize_filename(&theme_name));
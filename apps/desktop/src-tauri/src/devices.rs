@@ -0,0 +1,402 @@
+//! Spotify Connect devices. Spotify forgets a device that has been idle for a
+//! while, so a background task checks that one is active and activates one
+//! again when there isn't. The preferred device and the volume last used on
+//! each device are kept for this machine in `devices.json`.
+
+use crate::atomic_file;
+use crate::offline;
+use crate::playback;
+use crate::player::PlayerState;
+use crate::spotify_api::{self, Device, Priority};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{async_runtime as rt, AppHandle, Emitter, Manager};
+use tokio::time::sleep;
+
+/// The active device, the device list or the preferred device changed.
+/// Payload: `DeviceStatus`.
+pub const DEVICE_CHANGED_EVENT: &str = "device-changed";
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(120);
+/// Leaves the first playback poll time to find the active device.
+const STARTUP_DELAY: Duration = Duration::from_secs(5);
+/// How often to look for the preferred device while it is switched off.
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(30);
+/// Checks without an active device before one is activated again.
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+/// Spotify needs a moment before a transferred device shows up as active.
+const TRANSFER_SETTLE: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PreferredDevice {
+    pub id: String,
+    /// Matched when Spotify gives the device a new id.
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct DeviceMemory {
+    #[serde(default)]
+    preferred: Option<PreferredDevice>,
+    /// Volume last used on each device, by device id.
+    #[serde(default)]
+    volumes: HashMap<String, u8>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DeviceStatus {
+    pub active: Option<Device>,
+    pub devices: Vec<Device>,
+    pub preferred: Option<PreferredDevice>,
+    /// Unix time in milliseconds when an active device was last seen.
+    pub last_successful_ping: i64,
+    pub consecutive_failures: u32,
+}
+
+lazy_static::lazy_static! {
+    static ref MEMORY: Mutex<Option<DeviceMemory>> = Mutex::new(None);
+    static ref ACTIVE: Mutex<Option<Device>> = Mutex::new(None);
+    static ref LAST_ACTIVE_ID: Mutex<Option<String>> = Mutex::new(None);
+    static ref DEVICES: Mutex<Vec<Device>> = Mutex::new(Vec::new());
+}
+
+static FAILURES: AtomicU32 = AtomicU32::new(0);
+static LAST_SUCCESS: AtomicI64 = AtomicI64::new(0);
+/// Set once a device was seen active. That first device keeps its volume,
+/// since it was already in use before the app started.
+static OBSERVED: AtomicBool = AtomicBool::new(false);
+/// Set once the device list was fetched. Before that, a device can't be told
+/// to have just appeared.
+static LISTED: AtomicBool = AtomicBool::new(false);
+
+fn memory_path(app: &AppHandle) -> PathBuf {
+    let mut path = app
+        .path()
+        .app_data_dir()
+        .unwrap_or_else(|_| PathBuf::from("."));
+    fs::create_dir_all(&path).ok();
+    path.push("devices.json");
+    path
+}
+
+fn load_memory(app: &AppHandle) -> DeviceMemory {
+    fs::read_to_string(memory_path(app))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_memory(app: &AppHandle, memory: &DeviceMemory) {
+    if let Err(e) = atomic_file::write_json(&memory_path(app), memory) {
+        eprintln!("Failed to save devices: {}", e);
+    }
+}
+
+/// Runs `f` on the remembered devices and saves them if `f` returns true.
+fn update_memory(app: &AppHandle, f: impl FnOnce(&mut DeviceMemory) -> bool) {
+    if let Ok(mut guard) = MEMORY.lock() {
+        let memory = guard.get_or_insert_with(|| load_memory(app));
+        if f(memory) {
+            save_memory(app, memory);
+        }
+    }
+}
+
+fn memory(app: &AppHandle) -> DeviceMemory {
+    MEMORY
+        .lock()
+        .ok()
+        .map(|mut guard| guard.get_or_insert_with(|| load_memory(app)).clone())
+        .unwrap_or_default()
+}
+
+fn known_devices() -> Vec<Device> {
+    DEVICES.lock().map(|d| d.clone()).unwrap_or_default()
+}
+
+pub fn status(app: &AppHandle) -> DeviceStatus {
+    DeviceStatus {
+        active: ACTIVE.lock().ok().and_then(|a| a.clone()),
+        devices: known_devices(),
+        preferred: memory(app).preferred,
+        last_successful_ping: LAST_SUCCESS.load(Ordering::SeqCst),
+        consecutive_failures: FAILURES.load(Ordering::SeqCst),
+    }
+}
+
+fn emit_status(app: &AppHandle) {
+    let _ = app.emit(DEVICE_CHANGED_EVENT, status(app));
+}
+
+fn record_success() {
+    FAILURES.store(0, Ordering::SeqCst);
+    LAST_SUCCESS.store(chrono::Utc::now().timestamp_millis(), Ordering::SeqCst);
+}
+
+fn find<'a>(devices: &'a [Device], preferred: &PreferredDevice) -> Option<&'a Device> {
+    devices
+        .iter()
+        .find(|d| d.id.as_deref() == Some(preferred.id.as_str()))
+        .or_else(|| devices.iter().find(|d| d.name == preferred.name))
+}
+
+fn remember_volume(app: &AppHandle, device: &Device) {
+    if let (Some(id), Some(volume)) = (&device.id, device.volume_percent) {
+        update_memory(app, |memory| {
+            memory.volumes.insert(id.clone(), volume) != Some(volume)
+        });
+    }
+}
+
+/// Sets the volume last used on `device`, or remembers its volume if there
+/// is none to restore.
+fn restore_volume(app: &AppHandle, device: &Device) {
+    let saved = device
+        .id
+        .as_ref()
+        .and_then(|id| memory(app).volumes.get(id).copied());
+    match (&device.id, saved) {
+        (Some(id), Some(volume))
+            if device.supports_volume && device.volume_percent != Some(volume) =>
+        {
+            let app = app.clone();
+            let id = id.clone();
+            rt::spawn(async move {
+                if let Err(e) = spotify_api::set_volume(&app, volume, Some(&id)).await {
+                    eprintln!("Failed to restore device volume: {}", e);
+                }
+            });
+        }
+        _ => remember_volume(app, device),
+    }
+}
+
+/// Records the device the playback poll found active, if any.
+pub fn observe(app: &AppHandle, device: Option<&Device>) {
+    if device.is_some() {
+        record_success();
+    }
+    let Some(previous) = ACTIVE
+        .lock()
+        .ok()
+        .map(|mut active| std::mem::replace(&mut *active, device.cloned()))
+    else {
+        return;
+    };
+    if previous.as_ref() == device {
+        return;
+    }
+    if let Some(device) = device {
+        if let Ok(mut last) = LAST_ACTIVE_ID.lock() {
+            last.clone_from(&device.id);
+        }
+        let first = !OBSERVED.swap(true, Ordering::SeqCst);
+        let switched = previous.as_ref().and_then(|p| p.id.as_ref()) != device.id.as_ref();
+        if switched && !first {
+            restore_volume(app, device);
+        } else {
+            remember_volume(app, device);
+        }
+    }
+    emit_status(app);
+}
+
+fn set_devices(app: &AppHandle, devices: Vec<Device>) {
+    LISTED.store(true, Ordering::SeqCst);
+    let changed = DEVICES
+        .lock()
+        .map(|mut known| {
+            let changed = *known != devices;
+            *known = devices;
+            changed
+        })
+        .unwrap_or(false);
+    if changed {
+        emit_status(app);
+    }
+}
+
+/// The device to activate when none is: the preferred one, then the last
+/// active one, then a computer, then any.
+fn recovery_target(app: &AppHandle, devices: &[Device]) -> Option<Device> {
+    let usable: Vec<Device> = devices
+        .iter()
+        .filter(|d| d.id.is_some() && !d.is_restricted)
+        .cloned()
+        .collect();
+    let last_active = LAST_ACTIVE_ID.lock().ok().and_then(|id| id.clone());
+    memory(app)
+        .preferred
+        .and_then(|preferred| find(&usable, &preferred).cloned())
+        .or_else(|| {
+            usable
+                .iter()
+                .find(|d| d.id.is_some() && d.id == last_active)
+                .cloned()
+        })
+        .or_else(|| usable.iter().find(|d| d.device_type == "Computer").cloned())
+        .or_else(|| usable.first().cloned())
+}
+
+async fn activate(app: &AppHandle, device: &Device, play: bool) -> Result<(), String> {
+    let id = device
+        .id
+        .as_deref()
+        .ok_or_else(|| format!("{} can't be controlled", device.name))?;
+    spotify_api::transfer_playback(app, id, play).await?;
+    sleep(TRANSFER_SETTLE).await;
+    FAILURES.store(0, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Moves playback to the preferred device when it was just switched on.
+/// Music already playing on another device is left there. A device that was
+/// already on at launch doesn't count as switched on.
+async fn check(app: &AppHandle) -> Result<(), String> {
+    let previous = LISTED.load(Ordering::SeqCst).then(known_devices);
+    let devices = spotify_api::devices(app, Priority::Background).await?;
+    set_devices(app, devices.clone());
+
+    let appeared = previous
+        .zip(memory(app).preferred)
+        .and_then(|(previous, preferred)| {
+            find(&devices, &preferred)
+                .filter(|d| {
+                    !d.is_active && !d.is_restricted && find(&previous, &preferred).is_none()
+                })
+                .cloned()
+        });
+    let playing_elsewhere = app.state::<PlayerState>().snapshot().is_playing;
+    if let Some(device) = appeared.filter(|_| !playing_elsewhere) {
+        eprintln!("Preferred device {} is back, moving playback", device.name);
+        return activate(app, &device, false).await;
+    }
+
+    if devices.iter().any(|d| d.is_active) {
+        record_success();
+        return Ok(());
+    }
+    let failures = FAILURES.fetch_add(1, Ordering::SeqCst) + 1;
+    emit_status(app);
+    if failures >= MAX_CONSECUTIVE_FAILURES {
+        if let Some(device) = recovery_target(app, &devices) {
+            eprintln!("No active Spotify device, activating {}", device.name);
+            activate(app, &device, false).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the preferred device is set but currently switched off.
+fn waiting_for_preferred(app: &AppHandle) -> bool {
+    memory(app)
+        .preferred
+        .is_some_and(|preferred| find(&known_devices(), &preferred).is_none())
+}
+
+async fn run_keep_alive(app: AppHandle) {
+    let mut last_error: Option<String> = None;
+    sleep(STARTUP_DELAY).await;
+    loop {
+        if playback::is_spotify_active(&app) && offline::is_online() {
+            match check(&app).await {
+                Ok(()) => last_error = None,
+                Err(e) => {
+                    FAILURES.fetch_add(1, Ordering::SeqCst);
+                    if last_error.as_ref() != Some(&e) {
+                        eprintln!("Device check failed: {}", e);
+                        last_error = Some(e);
+                    }
+                }
+            }
+        }
+        sleep(if waiting_for_preferred(&app) {
+            DISCOVERY_INTERVAL
+        } else {
+            KEEP_ALIVE_INTERVAL
+        })
+        .await;
+    }
+}
+
+/// Activates a device if none is, so a play command has somewhere to go.
+/// Returns false when Spotify isn't open anywhere.
+pub async fn ensure_active(app: &AppHandle) -> Result<bool, String> {
+    if !playback::is_spotify_active(app) {
+        return Ok(true);
+    }
+    if let Some(playback) = spotify_api::playback_state(app, Priority::Interactive).await? {
+        observe(app, Some(&playback.device));
+        return Ok(true);
+    }
+    let devices = spotify_api::devices(app, Priority::Interactive).await?;
+    set_devices(app, devices.clone());
+    if devices.iter().any(|d| d.is_active) {
+        return Ok(true);
+    }
+    match recovery_target(app, &devices) {
+        Some(device) => activate(app, &device, false).await.map(|()| true),
+        None => Ok(false),
+    }
+}
+
+/// Forgets the preferred device and the volumes, for "clear everything".
+pub fn clear_device_memory(app: &AppHandle) -> bool {
+    if let Ok(mut guard) = MEMORY.lock() {
+        *guard = None;
+    }
+    let path = memory_path(app);
+    !path.exists() || fs::remove_file(&path).is_ok()
+}
+
+pub fn init_devices(app: &AppHandle) {
+    rt::spawn(run_keep_alive(app.clone()));
+}
+
+#[tauri::command]
+pub fn get_device_status(app: AppHandle) -> DeviceStatus {
+    status(&app)
+}
+
+#[tauri::command]
+pub async fn list_devices(app: AppHandle) -> Result<Vec<Device>, String> {
+    let devices = spotify_api::devices(&app, Priority::Interactive).await?;
+    set_devices(&app, devices.clone());
+    Ok(devices)
+}
+
+/// Remembers `device_id` as this machine's device, or forgets the preferred
+/// device when it is `None`. Takes effect the next time the device appears.
+#[tauri::command]
+pub fn set_preferred_device(app: AppHandle, device_id: Option<String>) -> Result<(), String> {
+    let preferred = match device_id {
+        Some(id) => {
+            let device = known_devices()
+                .into_iter()
+                .find(|d| d.id.as_deref() == Some(id.as_str()))
+                .ok_or_else(|| "Unknown device, list the devices first".to_string())?;
+            Some(PreferredDevice {
+                id,
+                name: device.name,
+            })
+        }
+        None => None,
+    };
+    update_memory(&app, |memory| {
+        let changed = memory.preferred != preferred;
+        memory.preferred = preferred;
+        changed
+    });
+    emit_status(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ensure_active_device(app: AppHandle) -> Result<bool, String> {
+    ensure_active(&app).await
+}
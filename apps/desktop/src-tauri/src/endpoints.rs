@@ -0,0 +1,84 @@
+//! Base URLs of the services MiniFy talks to. Debug builds let each one be
+//! pointed at a local stand-in server with an environment variable or
+//! [`Service::set_override`], so the auth code can run without the real
+//! services. Release builds always use the real URLs.
+
+/// Base URLs set with [`Service::set_override`], by the service's `env`.
+#[cfg(debug_assertions)]
+static OVERRIDES: std::sync::Mutex<Vec<(&'static str, String)>> =
+    std::sync::Mutex::new(Vec::new());
+
+/// A service whose base URL can be overridden with `env` in debug builds.
+pub struct Service {
+    pub env: &'static str,
+    pub base_url: &'static str,
+}
+
+impl Service {
+    pub fn base_url(&self) -> String {
+        #[cfg(debug_assertions)]
+        {
+            if let Ok(overrides) = OVERRIDES.lock() {
+                if let Some((_, url)) = overrides.iter().find(|(env, _)| *env == self.env) {
+                    return url.clone();
+                }
+            }
+            if let Ok(url) = std::env::var(self.env) {
+                return url.trim_end_matches('/').to_string();
+            }
+        }
+        self.base_url.to_string()
+    }
+
+    /// Points the service at `url` for the rest of the process, like its
+    /// environment variable but safe to call while other threads run.
+    #[cfg(debug_assertions)]
+    pub fn set_override(&self, url: &str) {
+        let url = url.trim_end_matches('/').to_string();
+        if let Ok(mut overrides) = OVERRIDES.lock() {
+            overrides.retain(|(env, _)| *env != self.env);
+            overrides.push((self.env, url));
+        }
+    }
+}
+
+pub const SPOTIFY_ACCOUNTS: Service = Service {
+    env: "MINIFY_SPOTIFY_ACCOUNTS_URL",
+    base_url: "https://accounts.spotify.com",
+};
+
+pub const SPOTIFY_API: Service = Service {
+    env: "MINIFY_SPOTIFY_API_URL",
+    base_url: "https://api.spotify.com",
+};
+
+pub const LRCLIB: Service = Service {
+    env: "MINIFY_LRCLIB_URL",
+    base_url: "https://lrclib.net",
+};
+
+pub const GOOGLE_ACCOUNTS: Service = Service {
+    env: "MINIFY_GOOGLE_ACCOUNTS_URL",
+    base_url: "https://accounts.google.com",
+};
+
+pub const GOOGLE_OAUTH: Service = Service {
+    env: "MINIFY_GOOGLE_OAUTH_URL",
+    base_url: "https://oauth2.googleapis.com",
+};
+
+/// A path on a service.
+pub struct Endpoint {
+    pub service: &'static Service,
+    pub path: &'static str,
+}
+
+impl Endpoint {
+    pub const fn new(service: &'static Service, path: &'static str) -> Self {
+        Self { service, path }
+    }
+
+    pub fn url(&self) -> String {
+        format!("{}{}", self.service.base_url(), self.path)
+    }
+}
@@ -0,0 +1,183 @@
+//! The HTTP client shared by the OAuth engine, the AI proxy and cover
+//! downloads. One client keeps connections alive between requests, and it
+//! applies the timeouts, proxy and extra CA certificate from the settings.
+
+use crate::settings::{self, Settings};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::time::sleep;
+
+pub const USER_AGENT: &str = concat!(
+    "MiniFy/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/ModioStudio/MiniFy)"
+);
+
+/// Delays before the retries of an idempotent request. Its length is the
+/// number of retries.
+const RETRY_DELAYS_MS: [u64; 2] = [250, 1000];
+
+/// The network part of the settings.
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration,
+    /// `http://`, `https://` or `socks5://` URL every request goes through.
+    pub proxy: Option<String>,
+    /// PEM file with an extra root certificate, e.g. of a TLS-inspecting
+    /// corporate proxy.
+    pub ca_certificate_path: Option<String>,
+}
+
+impl NetworkConfig {
+    pub fn from_settings(settings: &Settings) -> Self {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        Self {
+            connect_timeout: Duration::from_secs(settings.http_connect_timeout_secs.max(1)),
+            read_timeout: Duration::from_secs(settings.http_read_timeout_secs.max(1)),
+            proxy: non_empty(&settings.http_proxy),
+            ca_certificate_path: non_empty(&settings.http_ca_certificate_path),
+        }
+    }
+
+    fn build(&self) -> Result<reqwest::Client, String> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(self.connect_timeout)
+            .read_timeout(self.read_timeout);
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| format!("Invalid proxy URL {}: {}", proxy, e))?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &self.ca_certificate_path {
+            let pem = std::fs::read(path)
+                .map_err(|e| format!("Failed to read CA certificate {}: {}", path, e))?;
+            let certificate = reqwest::Certificate::from_pem(&pem)
+                .map_err(|e| format!("Invalid CA certificate {}: {}", path, e))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        builder
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self::from_settings(&Settings::default())
+    }
+}
+
+/// Tauri state holding the shared client. Cloning a `reqwest::Client` is
+/// cheap and shares its connection pool.
+pub struct HttpClient {
+    client: RwLock<reqwest::Client>,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        let client = NetworkConfig::default()
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        Self {
+            client: RwLock::new(client),
+        }
+    }
+}
+
+impl HttpClient {
+    pub fn get(&self) -> reqwest::Client {
+        self.client
+            .read()
+            .map(|client| client.clone())
+            .unwrap_or_default()
+    }
+
+    /// Swaps in a client for the new settings. Invalid settings keep the
+    /// current client.
+    pub fn configure(&self, config: &NetworkConfig) -> Result<(), String> {
+        let client = config.build()?;
+        if let Ok(mut current) = self.client.write() {
+            *current = client;
+        }
+        Ok(())
+    }
+}
+
+lazy_static::lazy_static! {
+    /// For app handles without the state, like the mock app in tests.
+    static ref FALLBACK: HttpClient = HttpClient::default();
+}
+
+pub fn client<R: Runtime>(app: &AppHandle<R>) -> reqwest::Client {
+    match app.try_state::<HttpClient>() {
+        Some(http) => http.get(),
+        None => FALLBACK.get(),
+    }
+}
+
+/// Rebuilds the shared client from the saved settings.
+pub fn apply_settings<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let config = NetworkConfig::from_settings(&settings::read_settings(app.clone()));
+    match app.try_state::<HttpClient>() {
+        Some(http) => http.configure(&config),
+        None => Ok(()),
+    }
+}
+
+/// Applies the network settings after the frontend saved them.
+#[tauri::command]
+pub fn apply_network_settings(app: AppHandle) -> Result<(), String> {
+    apply_settings(&app)
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
+
+fn is_transient(result: &Result<Response, reqwest::Error>) -> bool {
+    match result {
+        Ok(response) => matches!(
+            response.status(),
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+        ),
+        Err(e) => e.is_connect() || e.is_timeout(),
+    }
+}
+
+/// Sends a request, retrying idempotent ones after connection errors,
+/// timeouts and gateway errors. Other requests are sent once, since
+/// repeating them could apply them twice.
+pub async fn send(request: RequestBuilder) -> Result<Response, reqwest::Error> {
+    let idempotent = request
+        .try_clone()
+        .and_then(|r| r.build().ok())
+        .is_some_and(|r| is_idempotent(r.method()));
+    if !idempotent {
+        return request.send().await;
+    }
+
+    for delay in RETRY_DELAYS_MS {
+        let Some(attempt) = request.try_clone() else {
+            break;
+        };
+        let result = attempt.send().await;
+        if !is_transient(&result) {
+            return result;
+        }
+        sleep(Duration::from_millis(delay)).await;
+    }
+    request.send().await
+}
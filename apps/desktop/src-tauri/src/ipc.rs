@@ -0,0 +1,392 @@
+use crate::player::{self, PlayerCommand, PlayerState, PlayerStatus};
+use crate::tray;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::async_runtime as rt;
+use tauri::{AppHandle, Manager};
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader as AsyncBufReader,
+};
+
+#[cfg(unix)]
+const SOCKET_NAME: &str = "minify.sock";
+#[cfg(windows)]
+const PIPE_NAME: &str = r"\\.\pipe\minify";
+const LOCK_NAME: &str = "minify.lock";
+/// How long a second launch waits for a starting instance to open its socket.
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(10);
+const FORWARD_RETRY: Duration = Duration::from_millis(200);
+
+/// One line of JSON sent by a client of the local socket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IpcRequest {
+    /// Command-line arguments of a second launch of the app.
+    Launch {
+        args: Vec<String>,
+    },
+    /// Playback command from `minify ctl`.
+    Command {
+        command: PlayerCommand,
+    },
+    Layout {
+        layout: String,
+    },
+    Status,
+    /// Keeps the connection open and writes the status again whenever the
+    /// track or the play/pause state changes.
+    Watch,
+}
+
+/// One line of JSON written back for every request.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IpcResponse {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<PlayerStatus>,
+}
+
+impl IpcResponse {
+    pub fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: Some(message.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_status(status: PlayerStatus) -> Self {
+        Self {
+            ok: true,
+            status: Some(status),
+            ..Default::default()
+        }
+    }
+}
+
+/// What a launch argument asks the running instance to do.
+#[derive(Debug, Clone)]
+enum LaunchAction {
+    Show,
+    Toggle,
+    Command(PlayerCommand),
+    Layout(String),
+}
+
+fn unknown_layout(layout: &str) -> String {
+    format!("Unknown layout \"{}\"", layout)
+}
+
+fn layout_action(layout: &str) -> Result<LaunchAction, String> {
+    if !tray::is_layout(layout) {
+        return Err(unknown_layout(layout));
+    }
+    Ok(LaunchAction::Layout(layout.to_string()))
+}
+
+fn parse_launch_args(args: &[String]) -> Result<Vec<LaunchAction>, String> {
+    let mut actions = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let action = match arg.as_str() {
+            "--show" => LaunchAction::Show,
+            "--toggle" => LaunchAction::Toggle,
+            "--play-pause" => LaunchAction::Command(PlayerCommand::PlayPause),
+            "--next" => LaunchAction::Command(PlayerCommand::Next),
+            "--previous" => LaunchAction::Command(PlayerCommand::Previous),
+            "--layout" => match args.next() {
+                Some(layout) => layout_action(layout)?,
+                None => return Err("--layout requires a layout name".to_string()),
+            },
+            other => {
+                if let Some(layout) = other.strip_prefix("--layout=") {
+                    layout_action(layout)?
+                } else {
+                    return Err(format!("Unknown argument \"{}\"", other));
+                }
+            }
+        };
+        actions.push(action);
+    }
+    // Launching the app again without arguments brings the player forward.
+    if actions.is_empty() {
+        actions.push(LaunchAction::Show);
+    }
+    Ok(actions)
+}
+
+fn run_launch_args(app: &AppHandle, args: &[String]) -> IpcResponse {
+    let actions = match parse_launch_args(args) {
+        Ok(actions) => actions,
+        Err(e) => return IpcResponse::error(e),
+    };
+    for action in actions {
+        match action {
+            LaunchAction::Show => tray::show_main_window(app),
+            LaunchAction::Toggle => tray::toggle_main_window(app),
+            LaunchAction::Command(command) => player::send_command(app, command),
+            LaunchAction::Layout(layout) => tray::select_layout(app, &layout),
+        }
+    }
+    IpcResponse::ok()
+}
+
+fn current_status(app: &AppHandle) -> PlayerStatus {
+    app.state::<PlayerState>().snapshot().status()
+}
+
+fn handle_request(app: &AppHandle, request: IpcRequest) -> IpcResponse {
+    match request {
+        IpcRequest::Launch { args } => run_launch_args(app, &args),
+        IpcRequest::Command { command } => {
+            player::send_command(app, command);
+            IpcResponse::ok()
+        }
+        IpcRequest::Layout { layout } => {
+            if !tray::is_layout(&layout) {
+                return IpcResponse::error(unknown_layout(&layout));
+            }
+            tray::select_layout(app, &layout);
+            IpcResponse::ok()
+        }
+        IpcRequest::Status => IpcResponse::with_status(current_status(app)),
+        IpcRequest::Watch => IpcResponse::error("Watch is only valid as a streaming request"),
+    }
+}
+
+async fn write_line<S, T>(stream: &mut S, value: &T) -> std::io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    stream.write_all(&line).await?;
+    stream.flush().await
+}
+
+async fn watch_status<S>(app: &AppHandle, stream: &mut S) -> std::io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let mut receiver = app.state::<PlayerState>().subscribe();
+    let mut last_key: Option<(Option<String>, bool)> = None;
+    loop {
+        let snapshot = receiver.borrow_and_update().clone();
+        let key = (
+            snapshot.track.as_ref().map(|track| track.id.clone()),
+            snapshot.is_playing,
+        );
+        if last_key.as_ref() != Some(&key) {
+            last_key = Some(key);
+            write_line(stream, &IpcResponse::with_status(snapshot.status())).await?;
+        }
+        if receiver.changed().await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+async fn handle_connection<S>(app: AppHandle, stream: S)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut stream = AsyncBufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match stream.read_line(&mut line).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        let response = match serde_json::from_str::<IpcRequest>(line.trim()) {
+            Ok(IpcRequest::Watch) => {
+                let _ = watch_status(&app, stream.get_mut()).await;
+                return;
+            }
+            Ok(request) => handle_request(&app, request),
+            Err(e) => IpcResponse::error(format!("Invalid request: {}", e)),
+        };
+        if write_line(stream.get_mut(), &response).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Where the socket and the instance lock live. Only the user may write
+/// there: `$XDG_RUNTIME_DIR` on Linux, the per-user temp dir on macOS and
+/// Windows. Without `$XDG_RUNTIME_DIR` Linux uses the cache dir rather than
+/// the shared `/tmp`, where another user could take the socket name.
+fn runtime_dir() -> PathBuf {
+    if let Some(dir) = dirs::runtime_dir() {
+        return dir;
+    }
+    if cfg!(target_os = "linux") {
+        if let Some(dir) = dirs::cache_dir() {
+            return dir.join("minify");
+        }
+    }
+    std::env::temp_dir()
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+}
+
+#[cfg(windows)]
+fn create_private_dir(dir: &std::path::Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)
+}
+
+#[cfg(unix)]
+pub fn socket_path() -> PathBuf {
+    runtime_dir().join(SOCKET_NAME)
+}
+
+/// Held by the running instance for as long as it lives. The OS releases the
+/// lock when the process exits, also after a crash.
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// Takes the single-instance lock before the app is built. `None` means
+/// another instance holds it, even if that one hasn't opened its socket yet.
+pub fn lock_instance() -> std::io::Result<Option<InstanceLock>> {
+    let dir = runtime_dir();
+    create_private_dir(&dir)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_NAME))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(InstanceLock { _file: file })),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+#[cfg(unix)]
+fn connect() -> std::io::Result<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(socket_path())
+}
+
+#[cfg(windows)]
+fn connect() -> std::io::Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(PIPE_NAME)
+}
+
+fn open_request(request: &IpcRequest) -> std::io::Result<impl BufRead> {
+    let mut stream = connect()?;
+    let mut line = serde_json::to_vec(request)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    Ok(BufReader::new(stream))
+}
+
+fn parse_response(line: &str) -> std::io::Result<IpcResponse> {
+    serde_json::from_str(line).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Sends one request to the running instance and waits for its response.
+/// Fails with `NotFound`/`ConnectionRefused` when no instance is running.
+pub fn send_request(request: &IpcRequest) -> std::io::Result<IpcResponse> {
+    let mut reader = open_request(request)?;
+    let mut response = String::new();
+    reader.read_line(&mut response)?;
+    parse_response(&response)
+}
+
+/// Calls `on_status` for every status the running instance pushes until it
+/// shuts down.
+pub fn watch(mut on_status: impl FnMut(PlayerStatus)) -> std::io::Result<()> {
+    let reader = open_request(&IpcRequest::Watch)?;
+    for line in reader.lines() {
+        if let Some(status) = parse_response(&line?)?.status {
+            on_status(status);
+        }
+    }
+    Ok(())
+}
+
+/// Hands the launch arguments over to the instance holding the lock. It may
+/// still be starting, so connecting is retried for a few seconds.
+pub fn forward_to_running_instance(args: &[String]) {
+    let request = IpcRequest::Launch {
+        args: args.to_vec(),
+    };
+    let deadline = Instant::now() + FORWARD_TIMEOUT;
+    loop {
+        match send_request(&request) {
+            Ok(response) => {
+                if let Some(error) = response.error {
+                    eprintln!("MiniFy: {}", error);
+                }
+                return;
+            }
+            Err(e) if Instant::now() >= deadline => {
+                eprintln!("MiniFy is already running but didn't answer: {}", e);
+                return;
+            }
+            Err(_) => std::thread::sleep(FORWARD_RETRY),
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn serve(app: AppHandle) -> std::io::Result<()> {
+    let path = socket_path();
+    // This process holds the instance lock, so a socket left here belongs to
+    // an instance that has exited.
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        rt::spawn(handle_connection(app.clone(), stream));
+    }
+}
+
+#[cfg(windows)]
+async fn serve(app: AppHandle) -> std::io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(PIPE_NAME)?;
+    loop {
+        server.connect().await?;
+        let client = server;
+        server = ServerOptions::new().create(PIPE_NAME)?;
+        rt::spawn(handle_connection(app.clone(), client));
+    }
+}
+
+/// Listens on the local socket so later launches can reach this instance.
+pub fn init_ipc_server(app: &AppHandle) {
+    let app = app.clone();
+    rt::spawn(async move {
+        if let Err(e) = serve(app).await {
+            eprintln!("Local control socket unavailable: {}", e);
+        }
+    });
+}
@@ -1,13 +1,37 @@
 use tauri::Manager;
 
 pub mod ai_keyring;
+pub mod album_art;
+pub mod api_server;
+mod atomic_file;
 pub mod custom_themes;
 mod credential_store;
+pub mod ctl;
 pub mod debug;
+pub mod devices;
 pub mod discord_rpc;
+pub mod endpoints;
+pub mod http;
+pub mod ipc;
+pub mod lrc;
+pub mod lyrics;
+#[cfg(debug_assertions)]
+pub mod mock_oauth;
+#[cfg(target_os = "linux")]
+pub mod mpris;
+pub mod now_playing;
+pub mod oauth;
+pub mod offline;
+pub mod overlay;
+pub mod playback;
+pub mod player;
 pub mod resize;
+pub mod resume;
 pub mod settings;
+pub mod shortcuts;
+pub mod spotify_api;
 pub mod spotify_auth;
+pub mod tray;
 pub mod youtube_auth;
 
 mod clear_all {
@@ -17,9 +41,14 @@ mod clear_all {
     pub async fn execute(app: &AppHandle) -> Result<(), String> {
         let settings_cleared = settings::clear_settings(app.clone());
         let themes_cleared = custom_themes::clear_custom_themes(app);
-        let spotify_result = spotify_auth::clear_credentials().await;
-        let youtube_result = youtube_auth::clear_youtube_credentials().await;
+        let spotify_result = spotify_auth::clear_credentials(app.clone()).await;
+        let youtube_result = youtube_auth::clear_youtube_credentials(app.clone()).await;
         let ai_keys_result = ai_keyring::clear_all_ai_keys().await;
+        let api_token_result = api_server::clear_api_server_token().await;
+        let album_art_result = album_art::clear_album_art_cache(app.clone()).await;
+        let lyrics_result = lyrics::clear_lyrics_cache(app.clone()).await;
+        let devices_cleared = devices::clear_device_memory(app);
+        let outbox_cleared = offline::clear_outbox(app);
 
         if !settings_cleared {
             return Err("Failed to clear settings".to_string());
@@ -27,9 +56,18 @@ mod clear_all {
         if !themes_cleared {
             return Err("Failed to clear custom themes".to_string());
         }
+        if !devices_cleared {
+            return Err("Failed to clear Spotify devices".to_string());
+        }
+        if !outbox_cleared {
+            return Err("Failed to clear the offline outbox".to_string());
+        }
         spotify_result?;
         youtube_result?;
         ai_keys_result?;
+        api_token_result?;
+        album_art_result?;
+        lyrics_result?;
 
         Ok(())
     }
@@ -41,6 +79,32 @@ async fn clear_everything(app: tauri::AppHandle) -> Result<(), String> {
 }
 
 pub fn run() {
+    // A second launch only hands its arguments to the running instance, so two
+    // players never poll the provider or race for the OAuth callback port.
+    // The lock is held until `run` returns.
+    #[cfg(desktop)]
+    let _instance_lock = {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if args.first().map(String::as_str) == Some("ctl") {
+            std::process::exit(ctl::run(&args[1..]));
+        }
+        #[cfg(debug_assertions)]
+        if args.first().map(String::as_str) == Some("mock-oauth") {
+            std::process::exit(mock_oauth::run(&args[1..]));
+        }
+        match ipc::lock_instance() {
+            Ok(Some(lock)) => Some(lock),
+            Ok(None) => {
+                ipc::forward_to_running_instance(&args);
+                return;
+            }
+            Err(e) => {
+                eprintln!("Failed to take the single-instance lock: {}", e);
+                None
+            }
+        }
+    };
+
     let discord_state = discord_rpc::DiscordState::new();
 
     let mut builder = tauri::Builder::default()
@@ -50,16 +114,29 @@ pub fn run() {
 
     #[cfg(desktop)]
     {
-        builder = builder.plugin(tauri_plugin_updater::Builder::new().build());
+        builder = builder
+            .plugin(tauri_plugin_updater::Builder::new().build())
+            .plugin(
+                tauri_plugin_global_shortcut::Builder::new()
+                    .with_handler(shortcuts::handle_shortcut)
+                    .build(),
+            );
     }
 
     let app = builder
         .manage(discord_state)
+        .manage(player::PlayerState::new())
+        .manage(shortcuts::ShortcutRegistry::default())
+        .manage(http::HttpClient::default())
+        .register_asynchronous_uri_scheme_protocol(album_art::SCHEME, |ctx, request, responder| {
+            album_art::handle_request(ctx.app_handle().clone(), request, responder)
+        })
         .invoke_handler(tauri::generate_handler![
             clear_everything,
             settings::read_settings,
             settings::write_settings,
             settings::clear_settings,
+            http::apply_network_settings,
             spotify_auth::set_music_provider,
             spotify_auth::get_music_provider,
             spotify_auth::has_music_provider,
@@ -67,14 +144,51 @@ pub fn run() {
             spotify_auth::save_spotify_client_id,
             spotify_auth::needs_spotify_setup,
             spotify_auth::get_spotify_redirect_uri,
-            spotify_auth::get_tokens,
+            spotify_auth::get_access_token,
             spotify_auth::has_valid_tokens,
             spotify_auth::start_oauth_flow,
             spotify_auth::cancel_oauth_flow,
+            spotify_auth::complete_oauth_flow_manually,
             spotify_auth::refresh_access_token,
+            spotify_auth::list_spotify_accounts,
+            spotify_auth::add_spotify_account,
+            spotify_auth::switch_spotify_account,
+            spotify_auth::remove_spotify_account,
+            spotify_auth::has_spotify_scope,
+            spotify_auth::has_spotify_feature,
+            spotify_auth::request_spotify_feature,
             spotify_auth::clear_credentials,
+            spotify_api::spotify_currently_playing,
+            spotify_api::spotify_playback_state,
+            spotify_api::spotify_play,
+            spotify_api::spotify_pause,
+            spotify_api::spotify_next,
+            spotify_api::spotify_previous,
+            spotify_api::spotify_seek,
+            spotify_api::spotify_set_volume,
+            spotify_api::spotify_devices,
+            spotify_api::spotify_transfer_playback,
+            spotify_api::spotify_add_to_queue,
+            spotify_api::spotify_queue,
+            spotify_api::spotify_recently_played,
+            spotify_api::spotify_search_tracks,
+            spotify_api::spotify_user_playlists,
+            spotify_api::spotify_playlist_tracks,
+            spotify_api::spotify_add_to_playlist,
+            spotify_api::spotify_saved_tracks,
+            spotify_api::spotify_save_tracks,
+            spotify_api::spotify_api_get,
+            album_art::clear_album_art_cache,
+            lyrics::get_lyrics,
+            lyrics::clear_lyrics_cache,
+            offline::get_offline_status,
+            offline::dismiss_outbox_conflicts,
+            devices::get_device_status,
+            devices::list_devices,
+            devices::set_preferred_device,
+            devices::ensure_active_device,
             ai_keyring::save_ai_api_key,
-            ai_keyring::get_ai_api_key,
+            ai_keyring::ai_proxy_request,
             ai_keyring::has_ai_api_key,
             ai_keyring::delete_ai_api_key,
             ai_keyring::get_all_ai_providers,
@@ -90,33 +204,77 @@ pub fn run() {
             discord_rpc::disable_discord_rpc,
             discord_rpc::update_discord_presence,
             discord_rpc::is_discord_rpc_enabled,
+            player::report_playback_state,
+            playback::get_playback_snapshot,
+            player::report_ai_queue,
+            api_server::get_api_server_status,
+            api_server::restart_api_server,
+            api_server::get_api_server_token,
+            api_server::regenerate_api_server_token,
+            shortcuts::get_global_shortcuts,
+            shortcuts::reload_global_shortcuts,
             youtube_auth::has_youtube_credentials,
             youtube_auth::has_youtube_client_id,
             youtube_auth::save_youtube_credentials,
             youtube_auth::needs_youtube_setup,
-            youtube_auth::get_youtube_tokens,
+            youtube_auth::get_youtube_access_token,
             youtube_auth::has_valid_youtube_tokens,
             youtube_auth::start_youtube_oauth_flow,
             youtube_auth::cancel_youtube_oauth_flow,
+            youtube_auth::complete_youtube_oauth_flow_manually,
             youtube_auth::refresh_youtube_access_token,
+            youtube_auth::has_youtube_scope,
             youtube_auth::clear_youtube_credentials
         ])
         .setup(|app| {
+            if let Err(e) = http::apply_settings(app.handle()) {
+                eprintln!("Network settings ignored: {}", e);
+            }
             spotify_auth::spawn_token_refresh_task(app.handle().clone());
             youtube_auth::spawn_youtube_token_refresh_task(app.handle().clone());
 
             let state = app.state::<discord_rpc::DiscordState>();
             discord_rpc::init_discord_rpc(&state);
 
+            tray::init_tray(app.handle())?;
+            api_server::init_api_server(app.handle());
+            playback::init_playback(app.handle());
+            offline::init_offline(app.handle());
+            devices::init_devices(app.handle());
+            resume::init_resume(app.handle());
+            album_art::init_album_art(app.handle());
+            lyrics::init_lyrics(app.handle());
+            now_playing::init_now_playing_files(app.handle());
+            #[cfg(target_os = "linux")]
+            mpris::init_mpris(app.handle());
+            #[cfg(desktop)]
+            {
+                shortcuts::init_shortcuts(app.handle());
+                ipc::init_ipc_server(app.handle());
+            }
+
             Ok(())
         })
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
 
-    app.run(|_app_handle, event| {
-        if let tauri::RunEvent::ExitRequested { api, .. } = event {
+    app.run(|app_handle, event| match event {
+        // Closing the player only hides it; the tray keeps the app reachable.
+        tauri::RunEvent::WindowEvent {
+            label,
+            event: tauri::WindowEvent::CloseRequested { api, .. },
+            ..
+        } if label == tray::MAIN_WINDOW_LABEL => {
+            api.prevent_close();
+            if let Some(window) = app_handle.get_webview_window(&label) {
+                let _ = window.hide();
+            }
+        }
+        // `code` is only set for an explicit `exit`, e.g. "Quit" in the tray menu.
+        tauri::RunEvent::ExitRequested { api, code, .. } if code.is_none() => {
             api.prevent_exit();
         }
+        _ => {}
     });
 }
 
@@ -0,0 +1,330 @@
+//! Parser for LRC lyrics, including the enhanced format with a timestamp
+//! per word (`[00:12.00]<00:12.00>Never <00:12.40>gonna`). Text without
+//! any timestamps is kept as unsynced lyrics.
+
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LyricWord {
+    pub start_ms: u64,
+    /// The word as written, including the space after it.
+    pub text: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LyricLine {
+    pub start_ms: u64,
+    pub text: String,
+    /// Empty unless the lyrics time every word.
+    pub words: Vec<LyricWord>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Lyrics {
+    /// Whether the lines have timestamps. Unsynced lines all start at 0.
+    pub synced: bool,
+    pub lines: Vec<LyricLine>,
+}
+
+impl Lyrics {
+    /// The line being sung at `progress_ms`, if the first one has started.
+    pub fn line_at(&self, progress_ms: u64) -> Option<usize> {
+        if !self.synced {
+            return None;
+        }
+        self.lines
+            .partition_point(|line| line.start_ms <= progress_ms)
+            .checked_sub(1)
+    }
+
+    /// When the line after `index` starts; the first line for `None`.
+    pub fn next_start(&self, index: Option<usize>) -> Option<u64> {
+        let next = index.map_or(0, |i| i + 1);
+        self.lines.get(next).map(|line| line.start_ms)
+    }
+}
+
+/// `mm:ss`, `mm:ss.f`, `mm:ss.ff`, `mm:ss.fff` or `mm:ss:ff`, in milliseconds.
+fn parse_time(value: &str) -> Option<u64> {
+    let (minutes, rest) = value.trim().split_once(':')?;
+    let (seconds, fraction) = match rest.split_once(['.', ':']) {
+        Some((seconds, fraction)) => (seconds, fraction),
+        None => (rest, ""),
+    };
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: u64 = seconds.parse().ok()?;
+    if seconds >= 60 || fraction.len() > 3 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let fraction_ms = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<u64>().ok()? * 100,
+        2 => fraction.parse::<u64>().ok()? * 10,
+        _ => fraction.parse::<u64>().ok()?,
+    };
+    Some((minutes * 60 + seconds) * 1000 + fraction_ms)
+}
+
+/// Splits the leading `[...]` tags off a line.
+fn leading_tags(mut line: &str) -> (Vec<&str>, &str) {
+    let mut tags = Vec::new();
+    while let Some(rest) = line.trim_start().strip_prefix('[') {
+        let Some((tag, after)) = rest.split_once(']') else {
+            break;
+        };
+        tags.push(tag);
+        line = after;
+    }
+    (tags, line)
+}
+
+/// Word timestamps of an enhanced LRC line, or `None` for a plain one. Text
+/// before the first timestamp starts with the line, and a `<` that doesn't
+/// open a timestamp, as in `I <3 you`, stays part of the word.
+fn parse_words(text: &str, line_start_ms: u64) -> Option<Vec<LyricWord>> {
+    let mut words = Vec::new();
+    let mut word = LyricWord {
+        start_ms: line_start_ms,
+        text: String::new(),
+    };
+    let mut timed = false;
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        let tag = rest[open + 1..]
+            .split_once('>')
+            .and_then(|(tag, after)| Some((parse_time(tag)?, after)));
+        let Some((start_ms, after)) = tag else {
+            word.text.push_str(&rest[..=open]);
+            rest = &rest[open + 1..];
+            continue;
+        };
+        word.text.push_str(&rest[..open]);
+        let next = LyricWord {
+            start_ms,
+            text: String::new(),
+        };
+        let done = std::mem::replace(&mut word, next);
+        if !done.text.trim().is_empty() {
+            words.push(done);
+        }
+        timed = true;
+        rest = after;
+    }
+    if !timed {
+        return None;
+    }
+    word.text.push_str(rest);
+    if !word.text.trim().is_empty() {
+        words.push(word);
+    }
+    Some(words)
+}
+
+/// Parses LRC text. `[offset:ms]` is applied: a positive offset shows the
+/// lines earlier.
+pub fn parse(text: &str) -> Lyrics {
+    let mut offset_ms: i64 = 0;
+    let mut timed: Vec<(u64, &str)> = Vec::new();
+    let mut plain: Vec<&str> = Vec::new();
+
+    for raw in text.lines() {
+        let (tags, rest) = leading_tags(raw);
+        let times: Vec<u64> = tags.iter().filter_map(|tag| parse_time(tag)).collect();
+        if times.is_empty() {
+            let offset = tags.iter().find_map(|tag| {
+                let (key, value) = tag.split_once(':')?;
+                key.trim()
+                    .eq_ignore_ascii_case("offset")
+                    .then(|| value.trim().trim_start_matches('+').parse().ok())?
+            });
+            if let Some(offset) = offset {
+                offset_ms = offset;
+            } else if tags.iter().all(|tag| !tag.contains(':')) {
+                // Not metadata like `[ar:Artist]`, so text such as `[Chorus]`.
+                plain.push(raw.trim());
+            }
+            continue;
+        }
+        timed.extend(times.into_iter().map(|time| (time, rest)));
+    }
+
+    if timed.is_empty() {
+        // Drop blank lines around the text, keep the ones between verses.
+        let start = plain.iter().position(|line| !line.is_empty());
+        let end = plain.iter().rposition(|line| !line.is_empty());
+        let lines = match (start, end) {
+            (Some(start), Some(end)) => &plain[start..=end],
+            _ => &[],
+        };
+        return Lyrics {
+            synced: false,
+            lines: lines
+                .iter()
+                .map(|text| LyricLine {
+                    start_ms: 0,
+                    text: text.to_string(),
+                    words: Vec::new(),
+                })
+                .collect(),
+        };
+    }
+
+    let shift = |ms: u64| ms.saturating_add_signed(-offset_ms);
+    let mut lines: Vec<LyricLine> = timed
+        .into_iter()
+        .map(|(start_ms, rest)| {
+            let (text, words): (String, Vec<LyricWord>) = match parse_words(rest, start_ms) {
+                Some(words) => (
+                    words.iter().map(|word| word.text.as_str()).collect(),
+                    words
+                        .into_iter()
+                        .map(|word| LyricWord {
+                            start_ms: shift(word.start_ms),
+                            text: word.text,
+                        })
+                        .collect(),
+                ),
+                None => (rest.to_string(), Vec::new()),
+            };
+            LyricLine {
+                start_ms: shift(start_ms),
+                text: text.trim().to_string(),
+                words,
+            }
+        })
+        .collect();
+    // Lines with several timestamps come back once per timestamp.
+    lines.sort_by_key(|line| line.start_ms);
+    Lyrics {
+        synced: true,
+        lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lyrics: &Lyrics) -> Vec<(u64, &str)> {
+        lyrics
+            .lines
+            .iter()
+            .map(|line| (line.start_ms, line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_every_time_format() {
+        assert_eq!(parse_time("01:02"), Some(62_000));
+        assert_eq!(parse_time("01:02.5"), Some(62_500));
+        assert_eq!(parse_time("01:02.45"), Some(62_450));
+        assert_eq!(parse_time("01:02.456"), Some(62_456));
+        assert_eq!(parse_time("01:02:45"), Some(62_450));
+        assert_eq!(parse_time(" 123:00.00 "), Some(7_380_000));
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_time() {
+        for value in [
+            "",
+            "ar:Artist",
+            "01",
+            "01:60",
+            "01:02.4567",
+            "01:02.4a",
+            "-1:00",
+        ] {
+            assert_eq!(parse_time(value), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn sorts_lines_with_several_timestamps() {
+        let lyrics = parse("[00:05.00][00:01.00]Chorus\n[00:03.00]Verse");
+        assert!(lyrics.synced);
+        assert_eq!(
+            texts(&lyrics),
+            [(1_000, "Chorus"), (3_000, "Verse"), (5_000, "Chorus")]
+        );
+    }
+
+    #[test]
+    fn applies_the_offset() {
+        let earlier = parse("[offset:+500]\n[00:02.00]Line");
+        assert_eq!(texts(&earlier), [(1_500, "Line")]);
+        let later = parse("[offset:-500]\n[00:02.00]Line");
+        assert_eq!(texts(&later), [(2_500, "Line")]);
+        let clamped = parse("[offset:5000]\n[00:02.00]Line");
+        assert_eq!(texts(&clamped), [(0, "Line")]);
+    }
+
+    #[test]
+    fn skips_metadata_tags() {
+        let lyrics = parse("[ar:Artist]\n[ti:Title]\n[length: 03:00]\n[00:01.00]Line");
+        assert!(lyrics.synced);
+        assert_eq!(texts(&lyrics), [(1_000, "Line")]);
+    }
+
+    #[test]
+    fn keeps_unsynced_text() {
+        let lyrics = parse("[ar:Artist]\n\n[Chorus]\nFirst line\n\nSecond line\n\n");
+        assert!(!lyrics.synced);
+        assert_eq!(
+            texts(&lyrics),
+            [
+                (0, "[Chorus]"),
+                (0, "First line"),
+                (0, ""),
+                (0, "Second line")
+            ]
+        );
+        assert_eq!(lyrics.line_at(10_000), None);
+    }
+
+    #[test]
+    fn times_every_word_of_enhanced_lines() {
+        let lyrics = parse("[00:12.00]<00:12.00>Never <00:12.40>gonna <00:12.90>give");
+        let line = &lyrics.lines[0];
+        assert_eq!(line.text, "Never gonna give");
+        let words: Vec<(u64, &str)> = line
+            .words
+            .iter()
+            .map(|word| (word.start_ms, word.text.as_str()))
+            .collect();
+        assert_eq!(
+            words,
+            [(12_000, "Never "), (12_400, "gonna "), (12_900, "give")]
+        );
+    }
+
+    #[test]
+    fn keeps_text_around_word_timestamps() {
+        let lyrics = parse("[00:10.00]Hello <00:11.00>world\n[00:20.00]<00:20.00>I <3 you");
+        assert_eq!(
+            texts(&lyrics),
+            [(10_000, "Hello world"), (20_000, "I <3 you")]
+        );
+        let starts: Vec<u64> = lyrics.lines[0].words.iter().map(|w| w.start_ms).collect();
+        assert_eq!(starts, [10_000, 11_000]);
+        assert_eq!(lyrics.lines[1].words.len(), 1);
+    }
+
+    #[test]
+    fn shifts_word_timestamps_with_the_offset() {
+        let lyrics = parse("[offset:1000]\n[00:05.00]<00:05.00>One <00:06.00>two");
+        let starts: Vec<u64> = lyrics.lines[0].words.iter().map(|w| w.start_ms).collect();
+        assert_eq!(lyrics.lines[0].start_ms, 4_000);
+        assert_eq!(starts, [4_000, 5_000]);
+    }
+
+    #[test]
+    fn finds_the_line_being_sung() {
+        let lyrics = parse("[00:01.00]One\n[00:03.00]Two");
+        assert_eq!(lyrics.line_at(500), None);
+        assert_eq!(lyrics.line_at(1_000), Some(0));
+        assert_eq!(lyrics.line_at(2_999), Some(0));
+        assert_eq!(lyrics.line_at(60_000), Some(1));
+        assert_eq!(lyrics.next_start(None), Some(1_000));
+        assert_eq!(lyrics.next_start(Some(1)), None);
+    }
+}
@@ -0,0 +1,542 @@
+//! Lyrics for the playing track. They come from the folder in
+//! `lyrics_folder` when it has a file for the track, otherwise from the disk
+//! cache or the providers in `lyrics_providers`, tried in order. While synced
+//! lyrics are loaded, `lyric-line` follows the playback position.
+
+use crate::endpoints::LRCLIB;
+use crate::http;
+use crate::lrc::{self, LyricLine, Lyrics};
+use crate::player::{PlaybackSnapshot, PlayerState};
+use crate::settings::{self, CachedTrack};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{async_runtime as rt, AppHandle, Emitter, Manager};
+use tokio::sync::{watch, Notify};
+use tokio::time::sleep;
+
+/// Lyrics for a new track were looked up. Payload: `TrackLyrics`.
+pub const LYRICS_CHANGED_EVENT: &str = "lyrics-changed";
+/// A different line is being sung. Payload: `LyricLineUpdate`.
+pub const LYRIC_LINE_EVENT: &str = "lyric-line";
+
+/// How long a track without lyrics is not looked up again.
+const MISS_TTL_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+/// How deep to look into the lyrics folder, e.g. `Artist/Album/Title.lrc`.
+const FOLDER_DEPTH: usize = 3;
+/// Switch lines this much after they start, so rounding never shows the
+/// previous one again.
+const LINE_SLACK: Duration = Duration::from_millis(10);
+
+/// What a provider gets to find lyrics with.
+#[derive(Debug, Clone)]
+pub struct LyricsQuery {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration_ms: u64,
+    pub isrc: Option<String>,
+}
+
+impl LyricsQuery {
+    fn new(track: &CachedTrack) -> Self {
+        Self {
+            title: track.name.clone(),
+            artist: track
+                .artists
+                .first()
+                .map(|artist| artist.name.clone())
+                .unwrap_or_default(),
+            album: track.album.name.clone(),
+            duration_ms: track.duration_ms,
+            isrc: track.isrc.clone(),
+        }
+    }
+}
+
+/// A source of lyrics, enabled by listing its name in `lyrics_providers`.
+pub trait LyricsProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// LRC or plain text for the track, or `None` if the provider has none.
+    fn fetch<'a>(
+        &'a self,
+        app: &'a AppHandle,
+        query: &'a LyricsQuery,
+    ) -> BoxFuture<'a, Result<Option<String>, String>>;
+}
+
+/// lrclib.net, a free database of synced lyrics.
+pub struct Lrclib;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LrclibLyrics {
+    synced_lyrics: Option<String>,
+    plain_lyrics: Option<String>,
+}
+
+impl LyricsProvider for Lrclib {
+    fn name(&self) -> &'static str {
+        "lrclib"
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        app: &'a AppHandle,
+        query: &'a LyricsQuery,
+    ) -> BoxFuture<'a, Result<Option<String>, String>> {
+        Box::pin(async move {
+            let duration = (query.duration_ms / 1000).to_string();
+            let request = http::client(app)
+                .get(format!("{}/api/get", LRCLIB.base_url()))
+                .query(&[
+                    ("track_name", query.title.as_str()),
+                    ("artist_name", query.artist.as_str()),
+                    ("album_name", query.album.as_str()),
+                    ("duration", duration.as_str()),
+                ]);
+            let response = http::send(request)
+                .await
+                .map_err(|e| format!("LRCLIB request failed: {}", e))?;
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            let lyrics: LrclibLyrics = response
+                .error_for_status()
+                .map_err(|e| format!("LRCLIB request failed: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("Unexpected LRCLIB response: {}", e))?;
+            Ok(lyrics
+                .synced_lyrics
+                .filter(|text| !text.trim().is_empty())
+                .or(lyrics.plain_lyrics)
+                .filter(|text| !text.trim().is_empty()))
+        })
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TrackLyrics {
+    pub track_id: String,
+    /// `folder`, or the name of the provider they came from.
+    pub source: Option<String>,
+    /// `None` when no source has lyrics for the track.
+    pub lyrics: Option<Lyrics>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LyricLineUpdate {
+    pub track_id: String,
+    /// `None` before the first line.
+    pub index: Option<usize>,
+    pub line: Option<LyricLine>,
+    /// Where playback was when the line changed, for word highlighting.
+    pub progress_ms: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedLyrics {
+    source: Option<String>,
+    /// `None` records that no provider had lyrics.
+    text: Option<String>,
+    /// Unix time in milliseconds.
+    fetched_at: i64,
+}
+
+lazy_static::lazy_static! {
+    static ref PROVIDERS: Mutex<Vec<Arc<dyn LyricsProvider>>> =
+        Mutex::new(vec![Arc::new(Lrclib)]);
+    static ref CURRENT: Mutex<Option<TrackLyrics>> = Mutex::new(None);
+    static ref LYRICS_LOADED: Notify = Notify::new();
+}
+
+/// Bumped for every track, so a slow lookup for a skipped track is dropped.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Makes another provider available to `lyrics_providers`.
+pub fn register_provider(provider: Arc<dyn LyricsProvider>) {
+    if let Ok(mut providers) = PROVIDERS.lock() {
+        providers.retain(|existing| existing.name() != provider.name());
+        providers.push(provider);
+    }
+}
+
+fn enabled_providers(names: &[String]) -> Vec<Arc<dyn LyricsProvider>> {
+    let providers = PROVIDERS.lock().map(|p| p.clone()).unwrap_or_default();
+    names
+        .iter()
+        .filter_map(|name| providers.iter().find(|p| p.name() == name).cloned())
+        .collect()
+}
+
+fn track_key(track: &CachedTrack) -> String {
+    format!("{}:{}", track.provider, track.id)
+}
+
+fn cache_dir(app: &AppHandle) -> PathBuf {
+    app.path()
+        .app_cache_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join("lyrics")
+}
+
+fn cache_path(app: &AppHandle, track: &CachedTrack) -> PathBuf {
+    let hash = hex::encode(Sha256::digest(track_key(track).as_bytes()));
+    cache_dir(app).join(format!("{}.json", hash))
+}
+
+async fn read_cache(app: &AppHandle, track: &CachedTrack) -> Option<CachedLyrics> {
+    let content = tokio::fs::read_to_string(cache_path(app, track))
+        .await
+        .ok()?;
+    let cached: CachedLyrics = serde_json::from_str(&content).ok()?;
+    let age_ms = chrono::Utc::now().timestamp_millis() - cached.fetched_at;
+    (cached.text.is_some() || age_ms < MISS_TTL_MS).then_some(cached)
+}
+
+async fn write_cache(app: &AppHandle, track: &CachedTrack, cached: &CachedLyrics) {
+    let path = cache_path(app, track);
+    let result = async {
+        tokio::fs::create_dir_all(cache_dir(app)).await?;
+        let json = serde_json::to_string(cached).map_err(std::io::Error::other)?;
+        tokio::fs::write(&path, json).await
+    };
+    if let Err(e) = result.await {
+        eprintln!("Failed to cache lyrics: {}", e);
+    }
+}
+
+/// Letters and digits only, lower case, so `AC/DC` matches files and
+/// folders named `ACDC`, `AC DC` or `AC_DC`.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether `path` is named after the track: `<ISRC>.lrc`,
+/// `<Artist> - <Title>.lrc` or `<Artist>/<Title>.lrc`.
+fn matches_track(path: &Path, query: &LyricsQuery) -> bool {
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()).map(normalize) else {
+        return false;
+    };
+    if stem.is_empty() {
+        return false;
+    }
+    let artist = normalize(&query.artist);
+    let title = normalize(&query.title);
+    if query.isrc.as_deref().map(normalize).as_ref() == Some(&stem) {
+        return true;
+    }
+    // Without an artist the name rules would match any file named after the
+    // title.
+    if artist.is_empty() {
+        return false;
+    }
+    if stem == format!("{}{}", artist, title) {
+        return true;
+    }
+    stem == title
+        && path
+            .ancestors()
+            .skip(1)
+            .take(FOLDER_DEPTH)
+            .filter_map(|dir| dir.file_name()?.to_str())
+            .any(|dir| normalize(dir) == artist)
+}
+
+fn find_in_folder(dir: &Path, query: &LyricsQuery, depth: usize) -> Option<PathBuf> {
+    let mut subdirs = Vec::new();
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirs.push(path);
+            continue;
+        }
+        let is_lyrics = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("lrc") || e.eq_ignore_ascii_case("txt"));
+        if is_lyrics && matches_track(&path, query) {
+            return Some(path);
+        }
+    }
+    if depth == 0 {
+        return None;
+    }
+    subdirs
+        .iter()
+        .find_map(|subdir| find_in_folder(subdir, query, depth - 1))
+}
+
+async fn read_folder(folder: &str, query: &LyricsQuery) -> Option<String> {
+    let folder = PathBuf::from(folder);
+    let query = query.clone();
+    let path = rt::spawn_blocking(move || find_in_folder(&folder, &query, FOLDER_DEPTH))
+        .await
+        .ok()??;
+    tokio::fs::read_to_string(&path).await.ok()
+}
+
+/// Looks the track up in the folder, the cache and then the providers.
+/// Returns where the text came from, and the text.
+async fn find_lyrics(app: &AppHandle, track: &CachedTrack) -> (Option<String>, Option<String>) {
+    let settings = settings::read_settings(app.clone());
+    let query = LyricsQuery::new(track);
+
+    if let Some(folder) = settings.lyrics_folder.as_deref() {
+        if let Some(text) = read_folder(folder, &query).await {
+            return (Some("folder".to_string()), Some(text));
+        }
+    }
+    if let Some(cached) = read_cache(app, track).await {
+        return (cached.source, cached.text);
+    }
+
+    let mut all_answered = true;
+    for provider in enabled_providers(&settings.lyrics_providers) {
+        match provider.fetch(app, &query).await {
+            Ok(Some(text)) => {
+                let source = Some(provider.name().to_string());
+                let cached = CachedLyrics {
+                    source: source.clone(),
+                    text: Some(text.clone()),
+                    fetched_at: chrono::Utc::now().timestamp_millis(),
+                };
+                write_cache(app, track, &cached).await;
+                return (source, Some(text));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Lyrics lookup failed: {}", e);
+                all_answered = false;
+            }
+        }
+    }
+    // A provider that couldn't be reached may have lyrics next time.
+    if all_answered && !settings.lyrics_providers.is_empty() {
+        let cached = CachedLyrics {
+            source: None,
+            text: None,
+            fetched_at: chrono::Utc::now().timestamp_millis(),
+        };
+        write_cache(app, track, &cached).await;
+    }
+    (None, None)
+}
+
+fn current() -> Option<TrackLyrics> {
+    CURRENT.lock().ok().and_then(|c| c.clone())
+}
+
+fn set_current(app: &AppHandle, lyrics: Option<TrackLyrics>) {
+    if let Ok(mut current) = CURRENT.lock() {
+        current.clone_from(&lyrics);
+    }
+    if let Some(lyrics) = lyrics {
+        let _ = app.emit(LYRICS_CHANGED_EVENT, lyrics);
+    }
+    LYRICS_LOADED.notify_one();
+}
+
+async fn load(app: AppHandle, track: CachedTrack, generation: u64) {
+    let (source, text) = find_lyrics(&app, &track).await;
+    if GENERATION.load(Ordering::SeqCst) != generation {
+        return;
+    }
+    set_current(
+        &app,
+        Some(TrackLyrics {
+            track_id: track.id,
+            source,
+            lyrics: text.as_deref().map(lrc::parse),
+        }),
+    );
+}
+
+/// Looks up the lyrics of every new track.
+async fn follow_tracks(app: AppHandle, mut receiver: watch::Receiver<PlaybackSnapshot>) {
+    let mut last_track_id: Option<String> = None;
+    loop {
+        let track = receiver.borrow_and_update().track.clone();
+        let track_id = track.as_ref().map(|track| track.id.clone());
+        if track_id != last_track_id {
+            last_track_id = track_id;
+            let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+            set_current(&app, None);
+            if let Some(track) = track {
+                rt::spawn(load(app.clone(), track, generation));
+            }
+        }
+        if receiver.changed().await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Emits `lyric-line` whenever the position crosses into another line,
+/// sleeping until the next line starts in between.
+async fn follow_lines(app: AppHandle, mut receiver: watch::Receiver<PlaybackSnapshot>) {
+    let mut last: Option<(String, Option<usize>)> = None;
+    loop {
+        let snapshot = receiver.borrow_and_update().clone();
+        let mut until_next: Option<Duration> = None;
+        let playing = current().filter(|current| {
+            snapshot
+                .track
+                .as_ref()
+                .is_some_and(|track| track.id == current.track_id)
+        });
+        if let Some(TrackLyrics {
+            track_id,
+            lyrics: Some(lyrics),
+            ..
+        }) = playing.filter(|current| current.lyrics.as_ref().is_some_and(|l| l.synced))
+        {
+            let progress_ms = snapshot.current_progress_ms();
+            let index = lyrics.line_at(progress_ms);
+            let position = Some((track_id.clone(), index));
+            if last != position {
+                last = position;
+                let update = LyricLineUpdate {
+                    track_id,
+                    index,
+                    line: index.and_then(|i| lyrics.lines.get(i).cloned()),
+                    progress_ms,
+                };
+                let _ = app.emit(LYRIC_LINE_EVENT, update);
+            }
+            if snapshot.is_playing {
+                until_next = lyrics
+                    .next_start(index)
+                    .map(|start| Duration::from_millis(start.saturating_sub(progress_ms)));
+            }
+        }
+
+        tokio::select! {
+            changed = receiver.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+            _ = LYRICS_LOADED.notified() => {}
+            _ = sleep(until_next.unwrap_or_default() + LINE_SLACK), if until_next.is_some() => {}
+        }
+    }
+}
+
+pub fn init_lyrics(app: &AppHandle) {
+    let state = app.state::<PlayerState>();
+    rt::spawn(follow_tracks(app.clone(), state.subscribe()));
+    rt::spawn(follow_lines(app.clone(), state.subscribe()));
+}
+
+/// Lyrics of the playing track, for views that open after they loaded.
+#[tauri::command]
+pub fn get_lyrics() -> Option<TrackLyrics> {
+    current()
+}
+
+/// Deletes every cached lookup, including the misses.
+#[tauri::command]
+pub async fn clear_lyrics_cache(app: AppHandle) -> Result<(), String> {
+    let dir = cache_dir(&app);
+    match tokio::fs::remove_dir_all(&dir).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to clear {}: {}", dir.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(artist: &str, title: &str, isrc: Option<&str>) -> LyricsQuery {
+        LyricsQuery {
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: String::new(),
+            duration_ms: 0,
+            isrc: isrc.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn matches_files_named_after_the_isrc() {
+        let track = query(
+            "Rick Astley",
+            "Never Gonna Give You Up",
+            Some("GBARL9300135"),
+        );
+        assert!(matches_track(Path::new("/lyrics/GBARL9300135.lrc"), &track));
+        assert!(matches_track(Path::new("/lyrics/gbarl9300135.txt"), &track));
+        assert!(!matches_track(
+            Path::new("/lyrics/GBARL9300136.lrc"),
+            &track
+        ));
+        let without_isrc = query("Rick Astley", "Never Gonna Give You Up", None);
+        assert!(!matches_track(
+            Path::new("/lyrics/GBARL9300135.lrc"),
+            &without_isrc
+        ));
+    }
+
+    #[test]
+    fn matches_artist_and_title() {
+        let track = query("AC/DC", "Back In Black", None);
+        assert!(matches_track(
+            Path::new("/lyrics/AC DC - Back in Black.lrc"),
+            &track
+        ));
+        assert!(matches_track(
+            Path::new("/lyrics/acdc_back_in_black.lrc"),
+            &track
+        ));
+        assert!(matches_track(
+            Path::new("/lyrics/AC-DC/Back In Black.lrc"),
+            &track
+        ));
+        assert!(matches_track(
+            Path::new("/lyrics/ACDC/1980/Back In Black.lrc"),
+            &track
+        ));
+        assert!(!matches_track(
+            Path::new("/lyrics/Back In Black.lrc"),
+            &track
+        ));
+        assert!(!matches_track(
+            Path::new("/lyrics/Other/Back In Black.lrc"),
+            &track
+        ));
+        assert!(!matches_track(
+            Path::new("/lyrics/AC DC - Hells Bells.lrc"),
+            &track
+        ));
+    }
+
+    #[test]
+    fn never_matches_names_without_letters_or_digits() {
+        let track = query("!!!", "...", None);
+        assert!(!matches_track(Path::new("/lyrics/!!!/....lrc"), &track));
+        assert!(!matches_track(Path::new("/lyrics/ - .lrc"), &track));
+
+        let no_artist = query("!!!", "Hello", None);
+        assert!(!matches_track(Path::new("/lyrics/Hello.lrc"), &no_artist));
+        assert!(!matches_track(
+            Path::new("/lyrics/ - Hello.lrc"),
+            &no_artist
+        ));
+        assert!(!matches_track(
+            Path::new("/lyrics/!!!/Hello.lrc"),
+            &no_artist
+        ));
+    }
+}
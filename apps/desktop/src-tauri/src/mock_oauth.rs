@@ -0,0 +1,345 @@
+//! Stand-in for the Spotify and Google OAuth services, for debug builds and
+//! integration tests. It serves every endpoint the auth code uses under one
+//! origin, approves every authorization request immediately and checks PKCE
+//! like the real services do.
+//!
+//! Run it with `minify mock-oauth [port]` and export the variables it prints.
+
+use axum::extract::{Form, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::{engine::general_purpose, Engine as _};
+use rand::RngCore;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+use crate::endpoints::{GOOGLE_ACCOUNTS, GOOGLE_OAUTH, SPOTIFY_ACCOUNTS, SPOTIFY_API};
+
+const TOKEN_LIFETIME_SECS: i64 = 3600;
+
+pub const MOCK_USER_ID: &str = "mock-user";
+
+/// Keeps credentials in memory for the rest of the process, so tests that
+/// log in to the mock server never touch the user's real credential store.
+pub fn use_memory_credentials() {
+    crate::credential_store::use_memory_store();
+}
+
+struct PendingCode {
+    client_id: String,
+    redirect_uri: String,
+    code_challenge: Option<String>,
+    scope: String,
+}
+
+#[derive(Default)]
+struct MockState {
+    codes: HashMap<String, PendingCode>,
+    access_tokens: HashMap<String, String>,
+    refresh_tokens: HashMap<String, String>,
+    token_requests: usize,
+}
+
+type Shared = Arc<Mutex<MockState>>;
+
+pub struct MockOAuthServer {
+    addr: SocketAddr,
+    state: Shared,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockOAuthServer {
+    /// Serves on `127.0.0.1:port`; port 0 picks a free one.
+    pub async fn start(port: u16) -> Result<Self, String> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port)))
+            .await
+            .map_err(|e| format!("Couldn't start the mock OAuth server: {}", e))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| format!("Couldn't start the mock OAuth server: {}", e))?;
+
+        let state = Shared::default();
+        let router = Router::new()
+            .route("/authorize", get(authorize))
+            .route("/o/oauth2/v2/auth", get(authorize))
+            .route("/api/token", post(token))
+            .route("/token", post(token))
+            .route("/revoke", post(revoke))
+            .route("/v1/me", get(me))
+            .with_state(state.clone());
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    let _ = shutdown_rx.await;
+                })
+                .await;
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown: Some(shutdown_tx),
+        })
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Environment variables that point every service at this server.
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        [SPOTIFY_ACCOUNTS, SPOTIFY_API, GOOGLE_ACCOUNTS, GOOGLE_OAUTH]
+            .iter()
+            .map(|service| (service.env, self.base_url()))
+            .collect()
+    }
+
+    /// Number of requests the token endpoint has answered.
+    pub fn token_requests(&self) -> usize {
+        self.state.lock().map(|s| s.token_requests).unwrap_or(0)
+    }
+
+    /// Whether a refresh token is still valid, i.e. issued and not revoked.
+    pub fn is_refresh_token_active(&self, refresh_token: &str) -> bool {
+        self.state
+            .lock()
+            .map(|s| s.refresh_tokens.contains_key(refresh_token))
+            .unwrap_or(false)
+    }
+
+    /// Forgets a refresh token as if the user removed access at the provider.
+    pub fn revoke_refresh_token(&self, refresh_token: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            state.refresh_tokens.remove(refresh_token);
+        }
+    }
+}
+
+impl Drop for MockOAuthServer {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+fn random_token(prefix: &str) -> String {
+    let mut bytes = [0u8; 16];
+    rand::rng().fill_bytes(&mut bytes);
+    format!("{}-{}", prefix, hex::encode(bytes))
+}
+
+fn oauth_error(status: StatusCode, error: &str, description: &str) -> Response {
+    let body = json!({ "error": error, "error_description": description });
+    (status, Json(body)).into_response()
+}
+
+async fn authorize(
+    State(state): State<Shared>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let (Some(client_id), Some(redirect_uri)) = (query.get("client_id"), query.get("redirect_uri"))
+    else {
+        return oauth_error(StatusCode::BAD_REQUEST, "invalid_request", "missing client_id");
+    };
+    if query.get("response_type").map(String::as_str) != Some("code") {
+        return oauth_error(StatusCode::BAD_REQUEST, "unsupported_response_type", "");
+    }
+    if query.contains_key("code_challenge")
+        && query.get("code_challenge_method").map(String::as_str) != Some("S256")
+    {
+        return oauth_error(StatusCode::BAD_REQUEST, "invalid_request", "only S256 is supported");
+    }
+
+    let Ok(mut location) = url::Url::parse(redirect_uri) else {
+        return oauth_error(StatusCode::BAD_REQUEST, "invalid_request", "bad redirect_uri");
+    };
+
+    let code = random_token("code");
+    if let Ok(mut state) = state.lock() {
+        state.codes.insert(
+            code.clone(),
+            PendingCode {
+                client_id: client_id.clone(),
+                redirect_uri: redirect_uri.clone(),
+                code_challenge: query.get("code_challenge").cloned(),
+                scope: query.get("scope").cloned().unwrap_or_default(),
+            },
+        );
+    }
+
+    location.query_pairs_mut().append_pair("code", &code);
+    if let Some(st) = query.get("state") {
+        location.query_pairs_mut().append_pair("state", st);
+    }
+    Redirect::to(location.as_str()).into_response()
+}
+
+fn issue_tokens(state: &mut MockState, scope: &str, refresh_token: Option<String>) -> Response {
+    let access_token = random_token("access");
+    let new_refresh = refresh_token.is_none();
+    let refresh_token = refresh_token.unwrap_or_else(|| random_token("refresh"));
+    state
+        .access_tokens
+        .insert(access_token.clone(), scope.to_string());
+    state
+        .refresh_tokens
+        .insert(refresh_token.clone(), scope.to_string());
+
+    let mut body = json!({
+        "access_token": access_token,
+        "token_type": "Bearer",
+        "expires_in": TOKEN_LIFETIME_SECS,
+        "scope": scope,
+    });
+    // Like the real services, a refresh only returns the refresh token when
+    // it changes.
+    if new_refresh {
+        body["refresh_token"] = json!(refresh_token);
+    }
+    Json(body).into_response()
+}
+
+async fn token(
+    State(state): State<Shared>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    let Ok(mut state) = state.lock() else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    state.token_requests += 1;
+
+    match form.get("grant_type").map(String::as_str) {
+        Some("authorization_code") => {
+            let Some(pending) = form.get("code").and_then(|code| state.codes.remove(code)) else {
+                return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", "unknown code");
+            };
+            if form.get("client_id") != Some(&pending.client_id) {
+                return oauth_error(StatusCode::BAD_REQUEST, "invalid_client", "");
+            }
+            if form.get("redirect_uri") != Some(&pending.redirect_uri) {
+                let msg = "redirect_uri mismatch";
+                return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", msg);
+            }
+            match (&pending.code_challenge, form.get("code_verifier")) {
+                (Some(challenge), Some(verifier)) => {
+                    let hash = Sha256::digest(verifier.as_bytes());
+                    if general_purpose::URL_SAFE_NO_PAD.encode(hash) != *challenge {
+                        return oauth_error(
+                            StatusCode::BAD_REQUEST,
+                            "invalid_grant",
+                            "code_verifier mismatch",
+                        );
+                    }
+                }
+                (Some(_), None) => {
+                    let msg = "missing code_verifier";
+                    return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", msg);
+                }
+                (None, _) if !form.contains_key("client_secret") => {
+                    return oauth_error(StatusCode::UNAUTHORIZED, "invalid_client", "");
+                }
+                (None, _) => {}
+            }
+            issue_tokens(&mut state, &pending.scope, None)
+        }
+        Some("refresh_token") => {
+            let Some(refresh_token) = form.get("refresh_token") else {
+                return oauth_error(StatusCode::BAD_REQUEST, "invalid_request", "");
+            };
+            let Some(scope) = state.refresh_tokens.get(refresh_token).cloned() else {
+                let msg = "Refresh token revoked";
+                return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", msg);
+            };
+            issue_tokens(&mut state, &scope, Some(refresh_token.clone()))
+        }
+        _ => oauth_error(StatusCode::BAD_REQUEST, "unsupported_grant_type", ""),
+    }
+}
+
+async fn revoke(
+    State(state): State<Shared>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    let Ok(mut state) = state.lock() else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let token = form.get("token").cloned().unwrap_or_default();
+    let known = state.refresh_tokens.remove(&token).is_some()
+        || state.access_tokens.remove(&token).is_some();
+    if known {
+        StatusCode::OK.into_response()
+    } else {
+        oauth_error(StatusCode::BAD_REQUEST, "invalid_token", "Token expired or revoked")
+    }
+}
+
+async fn me(State(state): State<Shared>, headers: HeaderMap) -> Response {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let known = token.is_some_and(|token| {
+        state
+            .lock()
+            .map(|s| s.access_tokens.contains_key(token))
+            .unwrap_or(false)
+    });
+    if !known {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": { "status": 401, "message": "Invalid access token" } })),
+        )
+            .into_response();
+    }
+    Json(json!({
+        "id": MOCK_USER_ID,
+        "display_name": "Mock User",
+        "images": [],
+    }))
+    .into_response()
+}
+
+/// `minify mock-oauth [port]`: serves until interrupted.
+pub fn run(args: &[String]) -> i32 {
+    let port = match args.first().map(|p| p.parse::<u16>()) {
+        None => 0,
+        Some(Ok(port)) => port,
+        Some(Err(_)) => {
+            eprintln!("Usage: minify mock-oauth [port]");
+            return 2;
+        }
+    };
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Couldn't start the async runtime: {}", e);
+            return 1;
+        }
+    };
+    runtime.block_on(async {
+        let server = match MockOAuthServer::start(port).await {
+            Ok(server) => server,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        };
+        println!("Mock OAuth server listening on {}", server.base_url());
+        println!("Start a debug build of MiniFy with:");
+        for (name, value) in server.env_vars() {
+            println!("  export {}={}", name, value);
+        }
+        let _ = tokio::signal::ctrl_c().await;
+        0
+    })
+}
@@ -0,0 +1,412 @@
+use crate::player::{self, PlaybackSnapshot, PlayerCommand, PlayerState};
+use crate::tray;
+use std::collections::HashMap;
+use tauri::async_runtime as rt;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{mpsc, watch};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+use zbus::{connection, fdo, interface, Connection};
+
+pub const BUS_NAME: &str = "org.mpris.MediaPlayer2.minify";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const NO_TRACK_PATH: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+const TRACK_PATH_PREFIX: &str = "/com/modiostudio/minify/track";
+
+/// What a D-Bus client asked for. Kept separate from `AppHandle` so the
+/// service can run against any bus connection, e.g. a private test bus.
+#[derive(Debug, Clone)]
+pub enum MprisAction {
+    Command(PlayerCommand),
+    Raise,
+    Quit,
+}
+
+fn track_object_path(snapshot: &PlaybackSnapshot) -> ObjectPath<'static> {
+    let Some(track) = &snapshot.track else {
+        return ObjectPath::from_static_str_unchecked(NO_TRACK_PATH);
+    };
+    let element: String = format!("{}_{}", track.provider, track.id)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    ObjectPath::try_from(format!("{TRACK_PATH_PREFIX}/{element}"))
+        .unwrap_or_else(|_| ObjectPath::from_static_str_unchecked(NO_TRACK_PATH))
+}
+
+fn track_url(provider: &str, id: &str) -> Option<String> {
+    match provider {
+        "spotify" => Some(format!("https://open.spotify.com/track/{id}")),
+        "youtube" => Some(format!("https://music.youtube.com/watch?v={id}")),
+        _ => None,
+    }
+}
+
+fn owned(value: Value<'_>) -> Option<OwnedValue> {
+    value.try_to_owned().ok()
+}
+
+fn build_metadata(snapshot: &PlaybackSnapshot) -> HashMap<String, OwnedValue> {
+    let mut metadata = HashMap::new();
+    let mut insert = |key: &str, value: Option<OwnedValue>| {
+        if let Some(value) = value {
+            metadata.insert(key.to_string(), value);
+        }
+    };
+
+    insert(
+        "mpris:trackid",
+        owned(Value::from(track_object_path(snapshot))),
+    );
+
+    let Some(track) = &snapshot.track else {
+        return metadata;
+    };
+
+    let length_us = i64::try_from(track.duration_ms).unwrap_or(i64::MAX / 1000) * 1000;
+    let artists: Vec<String> = track.artists.iter().map(|a| a.name.clone()).collect();
+    let art_url = snapshot.art_url();
+
+    insert("mpris:length", owned(Value::from(length_us)));
+    insert("xesam:title", owned(Value::from(track.name.clone())));
+    insert("xesam:artist", owned(Value::from(artists)));
+    insert("xesam:album", owned(Value::from(track.album.name.clone())));
+    insert(
+        "mpris:artUrl",
+        art_url.and_then(|url| owned(Value::from(url))),
+    );
+    insert(
+        "xesam:url",
+        track_url(&track.provider, &track.id).and_then(|url| owned(Value::from(url))),
+    );
+
+    metadata
+}
+
+struct RootInterface {
+    actions: mpsc::UnboundedSender<MprisAction>,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl RootInterface {
+    fn raise(&self) {
+        let _ = self.actions.send(MprisAction::Raise);
+    }
+
+    fn quit(&self) {
+        let _ = self.actions.send(MprisAction::Quit);
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        "MiniFy".to_string()
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> String {
+        "com.modiostudio.minify".to_string()
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+struct PlayerInterface {
+    actions: mpsc::UnboundedSender<MprisAction>,
+    snapshots: watch::Receiver<PlaybackSnapshot>,
+}
+
+impl PlayerInterface {
+    fn send(&self, command: PlayerCommand) {
+        let _ = self.actions.send(MprisAction::Command(command));
+    }
+
+    fn current(&self) -> PlaybackSnapshot {
+        self.snapshots.borrow().clone()
+    }
+
+    async fn seek_to(&self, emitter: &SignalEmitter<'_>, position_us: i64) {
+        let position_ms = (position_us.max(0) / 1000) as u64;
+        self.send(PlayerCommand::Seek { position_ms });
+        let _ = Self::seeked(emitter, position_ms as i64 * 1000).await;
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl PlayerInterface {
+    fn next(&self) {
+        self.send(PlayerCommand::Next);
+    }
+
+    fn previous(&self) {
+        self.send(PlayerCommand::Previous);
+    }
+
+    fn pause(&self) {
+        self.send(PlayerCommand::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.send(PlayerCommand::PlayPause);
+    }
+
+    fn stop(&self) {
+        self.send(PlayerCommand::Pause);
+    }
+
+    fn play(&self) {
+        self.send(PlayerCommand::Play);
+    }
+
+    async fn seek(&self, offset: i64, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        let snapshot = self.current();
+        let Some(track) = &snapshot.track else {
+            return;
+        };
+        let target = snapshot.current_progress_ms() as i64 * 1000 + offset;
+        // Per the MPRIS spec, seeking past the end behaves like Next.
+        if target > track.duration_ms as i64 * 1000 {
+            self.send(PlayerCommand::Next);
+            return;
+        }
+        self.seek_to(&emitter, target).await;
+    }
+
+    async fn set_position(
+        &self,
+        track_id: ObjectPath<'_>,
+        position: i64,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) {
+        let snapshot = self.current();
+        let Some(track) = &snapshot.track else {
+            return;
+        };
+        if track_id != track_object_path(&snapshot)
+            || position < 0
+            || position > track.duration_ms as i64 * 1000
+        {
+            return;
+        }
+        self.seek_to(&emitter, position).await;
+    }
+
+    fn open_uri(&self, _uri: &str) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "OpenUri is not supported".to_string(),
+        ))
+    }
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        let snapshot = self.current();
+        match (&snapshot.track, snapshot.is_playing) {
+            (None, _) => "Stopped",
+            (Some(_), true) => "Playing",
+            (Some(_), false) => "Paused",
+        }
+        .to_string()
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        build_metadata(&self.current())
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.current()
+            .volume_percent
+            .map(|volume| f64::from(volume) / 100.0)
+            .unwrap_or(1.0)
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, volume: f64) {
+        let volume = (volume.clamp(0.0, 1.0) * 100.0).round() as u8;
+        self.send(PlayerCommand::SetVolume { volume });
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.current().current_progress_ms() as i64 * 1000
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.current().track.is_some()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+async fn emit_property_changes(
+    connection: Connection,
+    mut snapshots: watch::Receiver<PlaybackSnapshot>,
+) -> zbus::Result<()> {
+    let player = connection
+        .object_server()
+        .interface::<_, PlayerInterface>(OBJECT_PATH)
+        .await?;
+    let mut previous = snapshots.borrow().clone();
+
+    while snapshots.changed().await.is_ok() {
+        let current = snapshots.borrow_and_update().clone();
+        let emitter = player.signal_emitter();
+        let iface = player.get().await;
+
+        let previous_track = previous.track.as_ref().map(|t| (&t.provider, &t.id));
+        let current_track = current.track.as_ref().map(|t| (&t.provider, &t.id));
+        if previous_track != current_track {
+            iface.metadata_changed(emitter).await?;
+            iface.can_seek_changed(emitter).await?;
+        }
+        if previous.is_playing != current.is_playing
+            || previous_track.is_none() != current_track.is_none()
+        {
+            iface.playback_status_changed(emitter).await?;
+        }
+        if previous.volume_percent != current.volume_percent {
+            iface.volume_changed(emitter).await?;
+        }
+
+        previous = current;
+    }
+    Ok(())
+}
+
+/// Publishes the MPRIS service on the bus behind `builder` and keeps emitting
+/// `PropertiesChanged` for as long as `snapshots` has a sender.
+pub async fn serve(
+    builder: connection::Builder<'_>,
+    snapshots: watch::Receiver<PlaybackSnapshot>,
+    actions: mpsc::UnboundedSender<MprisAction>,
+) -> zbus::Result<Connection> {
+    let connection = builder
+        .name(BUS_NAME)?
+        .serve_at(
+            OBJECT_PATH,
+            RootInterface {
+                actions: actions.clone(),
+            },
+        )?
+        .serve_at(
+            OBJECT_PATH,
+            PlayerInterface {
+                actions,
+                snapshots: snapshots.clone(),
+            },
+        )?
+        .build()
+        .await?;
+
+    let signal_connection = connection.clone();
+    tokio::spawn(async move {
+        if let Err(e) = emit_property_changes(signal_connection, snapshots).await {
+            eprintln!("MPRIS signal emitter stopped: {}", e);
+        }
+    });
+
+    Ok(connection)
+}
+
+pub fn init_mpris(app: &AppHandle) {
+    let snapshots = app.state::<PlayerState>().subscribe();
+    let (actions_tx, mut actions_rx) = mpsc::unbounded_channel::<MprisAction>();
+
+    let app_handle = app.clone();
+    rt::spawn(async move {
+        let builder = match connection::Builder::session() {
+            Ok(builder) => builder,
+            Err(e) => {
+                eprintln!("MPRIS unavailable, no session bus: {}", e);
+                return;
+            }
+        };
+        // Dropping the connection would take the service off the bus.
+        let _connection = match serve(builder, snapshots, actions_tx).await {
+            Ok(connection) => connection,
+            Err(e) => {
+                eprintln!("Failed to register MPRIS service: {}", e);
+                return;
+            }
+        };
+
+        while let Some(action) = actions_rx.recv().await {
+            match action {
+                MprisAction::Command(command) => player::send_command(&app_handle, command),
+                MprisAction::Raise => tray::show_main_window(&app_handle),
+                MprisAction::Quit => app_handle.exit(0),
+            }
+        }
+    });
+}
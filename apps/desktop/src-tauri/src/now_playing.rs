@@ -0,0 +1,163 @@
+use crate::atomic_file;
+use crate::http;
+use crate::player::{PlayerState, PlayerStatus};
+use crate::settings::{self, Settings};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{async_runtime as rt, AppHandle, Manager};
+use tokio::fs;
+
+/// Settings paths that are unset or blank disable that file.
+fn configured_path(path: &Option<String>) -> Option<PathBuf> {
+    path.as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `atomic_file::write` off the async runtime, so readers like OBS text
+/// sources never pick up a half-written file.
+async fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    let (path, content) = (path.to_path_buf(), content.to_vec());
+    rt::spawn_blocking(move || atomic_file::write(&path, &content))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+async fn download_cover(client: &reqwest::Client, url: &str) -> Result<Vec<u8>, String> {
+    let response = http::send(client.get(url))
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to download cover: {}", e))?;
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download cover: {}", e))?;
+    Ok(bytes.to_vec())
+}
+
+/// The JSON is rewritten this often at most when only the position moved.
+const JSON_POSITION_INTERVAL: Duration = Duration::from_secs(15);
+
+/// What was last written where, so a file is only rewritten when its
+/// content or its configured path changes.
+#[derive(Default)]
+struct Written {
+    text: Option<(PathBuf, String)>,
+    /// The JSON without the position, which changes on every poll.
+    json: Option<(PathBuf, String)>,
+    json_position: Option<(u64, Instant)>,
+    cover: Option<(PathBuf, Option<String>)>,
+}
+
+fn is_written<T: PartialEq>(written: &Option<(PathBuf, T)>, path: &Path, content: &T) -> bool {
+    written
+        .as_ref()
+        .is_some_and(|(written_path, written)| written_path == path && written == content)
+}
+
+impl Written {
+    async fn update(
+        &mut self,
+        client: &reqwest::Client,
+        settings: &Settings,
+        status: &PlayerStatus,
+    ) {
+        let stopped = status.status == "stopped";
+
+        if let Some(path) = configured_path(&settings.now_playing_text_path) {
+            // An empty file lets text sources disappear when nothing is playing.
+            let text = if stopped {
+                String::new()
+            } else {
+                status.format(&settings.now_playing_template)
+            };
+            if !is_written(&self.text, &path, &text) {
+                match write_atomic(&path, text.as_bytes()).await {
+                    Ok(()) => self.text = Some((path, text)),
+                    Err(e) => eprintln!("Now playing file: {}", e),
+                }
+            }
+        }
+
+        if let Some(path) = configured_path(&settings.now_playing_json_path) {
+            let key = serde_json::to_string(&PlayerStatus {
+                position_ms: 0,
+                ..status.clone()
+            })
+            .unwrap_or_default();
+            let position_due = self.json_position.is_none_or(|(position_ms, at)| {
+                position_ms != status.position_ms && at.elapsed() >= JSON_POSITION_INTERVAL
+            });
+            if !is_written(&self.json, &path, &key) || position_due {
+                let json = serde_json::to_string_pretty(status).unwrap_or_default();
+                match write_atomic(&path, json.as_bytes()).await {
+                    Ok(()) => {
+                        self.json = Some((path, key));
+                        self.json_position = Some((status.position_ms, Instant::now()));
+                    }
+                    Err(e) => eprintln!("Now playing file: {}", e),
+                }
+            }
+        }
+
+        let cover_path = configured_path(&settings.now_playing_cover_path);
+        if let Some(path) =
+            cover_path.filter(|path| !is_written(&self.cover, path, &status.art_url))
+        {
+            let result = match &status.art_url {
+                Some(url) => match download_cover(client, url).await {
+                    Ok(bytes) => write_atomic(&path, &bytes).await,
+                    Err(e) => Err(e),
+                },
+                None => match fs::remove_file(&path).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        Err(format!("Failed to remove {}: {}", path.display(), e))
+                    }
+                    _ => Ok(()),
+                },
+            };
+            match result {
+                Ok(()) => self.cover = Some((path, status.art_url.clone())),
+                Err(e) => eprintln!("Now playing file: {}", e),
+            }
+        }
+    }
+}
+
+/// Mirrors the current track into the files configured in settings for
+/// streaming tools that can only read files.
+pub fn init_now_playing_files(app: &AppHandle) {
+    let mut receiver = app.state::<PlayerState>().subscribe();
+    let mut settings_changes = settings::subscribe_changes();
+    let app = app.clone();
+
+    rt::spawn(async move {
+        let mut written = Written::default();
+        let mut settings = settings::read_settings(app.clone());
+        loop {
+            if settings.now_playing_files_enabled {
+                let snapshot = receiver.borrow_and_update().clone();
+                let client = http::client(&app);
+                written.update(&client, &settings, &snapshot.status()).await;
+            } else {
+                // Rewrite everything once the option is turned back on.
+                written = Written::default();
+            }
+
+            tokio::select! {
+                changed = receiver.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+                changed = settings_changes.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    settings = settings::read_settings(app.clone());
+                }
+            }
+        }
+    });
+}
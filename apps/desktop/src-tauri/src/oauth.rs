@@ -0,0 +1,1534 @@
+//! Authorization-code OAuth2 flow shared by all music providers. A provider
+//! module only declares an `OAuthProvider` and forwards its Tauri commands to
+//! the matching `OAuthClient`.
+
+use crate::credential_store;
+use crate::endpoints::Endpoint;
+use crate::http;
+use crate::settings;
+use axum::extract::Query;
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use rand::RngCore;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime as rt;
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio::time::sleep;
+
+/// How long the callback server waits for the browser before giving up.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+/// Tokens are refreshed this many seconds before they expire.
+const REFRESH_MARGIN_SECS: i64 = 300;
+/// Delays between retries of a refresh that failed for a reason other than a
+/// revoked refresh token. The last delay repeats until a refresh succeeds.
+const REFRESH_RETRY_SECS: [i64; 5] = [5, 15, 30, 60, 120];
+/// Longest single sleep of the refresh scheduler. Monotonic timers stop while
+/// the system is suspended, so the scheduler wakes up regularly and compares
+/// against the wall clock to notice a resume.
+const SCHEDULER_TICK_SECS: i64 = 30;
+/// Extra wall-clock time during one tick that counts as a suspend.
+const RESUME_GAP_SECS: i64 = 60;
+/// Sign-out doesn't wait longer than this for each revocation request.
+const REVOKE_TIMEOUT_SECS: u64 = 10;
+
+/// How the app authenticates itself at the token endpoint.
+pub enum ClientAuth {
+    /// Public client using PKCE; only a client ID is needed.
+    Pkce,
+    /// Confidential client that sends a client secret with every token request.
+    ClientSecret,
+}
+
+/// Where the loopback callback server listens.
+pub enum CallbackPorts {
+    /// `oauth_callback_ports` from settings, tried in order, for providers that
+    /// only accept redirect URIs registered up front.
+    Configured,
+    /// Any free port, for providers that accept every loopback port.
+    Ephemeral,
+}
+
+/// Everything that differs between OAuth2 providers.
+pub struct OAuthProvider {
+    /// Identifies the provider in events, e.g. `auth-expired`.
+    pub id: &'static str,
+    /// Shown in error messages and on the callback page.
+    pub name: &'static str,
+    /// Logins report `<prefix>-success` and `<prefix>-failed` to the frontend.
+    pub event_prefix: &'static str,
+    pub authorize_url: Endpoint,
+    pub token_url: Endpoint,
+    /// Scopes requested at login.
+    pub scopes: &'static [&'static str],
+    /// Optional scopes per feature, requested only when the user turns the
+    /// feature on so existing logins keep working without re-consent.
+    pub feature_scopes: &'static [(&'static str, &'static [&'static str])],
+    /// Extra authorization parameters when asking for more scopes.
+    pub incremental_params: &'static [(&'static str, &'static str)],
+    /// Extra query parameters of the authorization URL.
+    pub authorize_params: &'static [(&'static str, &'static str)],
+    pub client_auth: ClientAuth,
+    pub callback_ports: CallbackPorts,
+    /// Endpoint that must accept the new access token before a login counts
+    /// as successful, to catch misconfigured client IDs early.
+    pub verify_url: Option<Endpoint>,
+    /// Endpoint that revokes the grant behind a refresh token on sign-out.
+    pub revoke_url: Option<Endpoint>,
+    /// Reads the account behind a token from the `verify_url` response.
+    /// Providers with one keep tokens per account so users can switch.
+    pub parse_account: Option<fn(&serde_json::Value) -> Option<Account>>,
+    /// Extra authorization parameters when adding another account, so the
+    /// provider asks who to log in as instead of reusing the browser session.
+    pub add_account_params: &'static [(&'static str, &'static str)],
+    /// Prefix of the token keys in the credential store.
+    pub token_key_prefix: &'static str,
+    /// Prefix of the client ID and client secret keys in the credential store.
+    pub client_key_prefix: &'static str,
+    /// Client credentials embedded at build time take precedence over stored ones.
+    pub embedded_client_id: Option<&'static str>,
+    pub embedded_client_secret: Option<&'static str>,
+    /// Brand color of the success page, and the color of the check mark on it.
+    pub accent_color: &'static str,
+    pub accent_foreground: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub id: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountList {
+    pub accounts: Vec<Account>,
+    pub active_account: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_at: i64,
+    /// Scopes the user actually granted, which can be fewer than requested.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+/// The part of a token set the webview may see. Refresh tokens and client
+/// secrets stay in the backend.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessToken {
+    pub access_token: String,
+    pub expires_at: i64,
+}
+
+/// Outcome of signing out. Local state is wiped even when revocation fails, so
+/// `failed` only says which grants may still be active at the provider.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SignOutReport {
+    pub revocation_supported: bool,
+    pub revoked: usize,
+    pub failed: Vec<String>,
+}
+
+impl From<OAuthTokens> for AccessToken {
+    fn from(tokens: OAuthTokens) -> Self {
+        Self {
+            access_token: tokens.access_token,
+            expires_at: tokens.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AuthState {
+    scopes: Vec<String>,
+    client_id: String,
+    client_secret: Option<String>,
+    code_verifier: Option<String>,
+    state_nonce: String,
+    redirect_uri: String,
+}
+
+/// A provider together with its credential caches and the login in progress.
+pub struct OAuthClient {
+    provider: OAuthProvider,
+    client_id: Mutex<Option<String>>,
+    client_secret: Mutex<Option<String>>,
+    tokens: Mutex<Option<OAuthTokens>>,
+    accounts: Mutex<Option<Vec<Account>>>,
+    active_account: Mutex<Option<String>>,
+    auth_state: Mutex<Option<AuthState>>,
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+    /// Held while a refresh request is in flight so concurrent callers share it.
+    refresh_lock: tokio::sync::Mutex<()>,
+    /// Wakes the refresh scheduler when tokens are saved.
+    tokens_changed: Notify,
+}
+
+/// Why the refresh scheduler stopped sleeping.
+enum Wake {
+    Due,
+    TokensChanged,
+    Resumed,
+}
+
+fn cached<T: Clone>(cache: &Mutex<Option<T>>) -> Option<T> {
+    cache.lock().ok().and_then(|g| g.clone())
+}
+
+fn set_cached<T>(cache: &Mutex<Option<T>>, value: Option<T>) {
+    if let Ok(mut cache) = cache.lock() {
+        *cache = value;
+    }
+}
+
+fn embedded(value: Option<&'static str>) -> Option<String> {
+    value.filter(|s| !s.is_empty()).map(|s| s.to_string())
+}
+
+fn urlsafe_b64_no_pad(bytes: &[u8]) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn generate_code_verifier() -> String {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-._~";
+    let mut rng = rand::rng();
+    let mut verifier = String::with_capacity(64);
+    for _ in 0..64 {
+        let idx = (rng.next_u32() as usize) % CHARSET.len();
+        verifier.push(CHARSET[idx] as char);
+    }
+    verifier
+}
+
+fn generate_code_challenge(verifier: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(verifier.as_bytes());
+    let hash = hasher.finalize();
+    urlsafe_b64_no_pad(&hash)
+}
+
+fn generate_state_nonce() -> String {
+    let mut state_bytes = [0u8; 16];
+    rand::rng().fill_bytes(&mut state_bytes);
+    hex::encode(state_bytes)
+}
+
+/// Redirect URI for a callback port. Providers with registered redirect URIs
+/// reject the login unless this exact string is in their app settings.
+fn redirect_uri_for_port(port: u16) -> String {
+    format!("http://127.0.0.1:{}/callback", port)
+}
+
+/// Ports from settings, tried in order so a port taken by a dev server
+/// doesn't block the login.
+fn configured_callback_ports<R: Runtime>(app: &AppHandle<R>) -> Vec<u16> {
+    let mut ports = settings::read_settings(app.clone()).oauth_callback_ports;
+    ports.retain(|port| *port != 0);
+    if ports.is_empty() {
+        return settings::default_oauth_callback_ports();
+    }
+    ports
+}
+
+/// Binds the first free port on the loopback interface.
+async fn bind_callback_listener(ports: &[u16], name: &str) -> Result<(TcpListener, u16), String> {
+    let mut last_error = None;
+    for &port in ports {
+        match TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port))).await {
+            Ok(listener) => {
+                // Port 0 asks the OS for a free port; report the one it picked.
+                let port = listener.local_addr().map(|a| a.port()).unwrap_or(port);
+                return Ok((listener, port));
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    let last_error = last_error.map(|e| e.to_string()).unwrap_or_default();
+    let in_use = match ports {
+        [0] => {
+            return Err(format!(
+                "Couldn't open a local port for the {} login: {}",
+                name, last_error
+            ))
+        }
+        [port] => format!("Port {} is", port),
+        _ => format!(
+            "Ports {} are",
+            ports
+                .iter()
+                .map(|port| port.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    Err(format!(
+        "{} already in use, so MiniFy can't receive the {} login. Close the app that is using it or add another port to oauth_callback_ports in settings, then try again. ({})",
+        in_use, name, last_error
+    ))
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: i64,
+    /// Space-separated granted scopes. Providers may leave it out when nothing
+    /// changed.
+    scope: Option<String>,
+}
+
+fn split_scopes(scope: &str) -> Vec<String> {
+    scope.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+impl OAuthClient {
+    pub const fn new(provider: OAuthProvider) -> Self {
+        Self {
+            provider,
+            client_id: Mutex::new(None),
+            client_secret: Mutex::new(None),
+            tokens: Mutex::new(None),
+            accounts: Mutex::new(None),
+            active_account: Mutex::new(None),
+            auth_state: Mutex::new(None),
+            shutdown: Mutex::new(None),
+            refresh_lock: tokio::sync::Mutex::const_new(()),
+            tokens_changed: Notify::const_new(),
+        }
+    }
+
+    fn needs_client_secret(&self) -> bool {
+        matches!(self.provider.client_auth, ClientAuth::ClientSecret)
+    }
+
+    fn supports_accounts(&self) -> bool {
+        self.provider.parse_account.is_some()
+    }
+
+    /// Keys of the access token, refresh token, expiry and granted scopes.
+    /// Tokens from before accounts existed live under the unscoped legacy keys.
+    fn token_keys_for(&self, account: Option<&str>) -> [String; 4] {
+        const NAMES: [&str; 4] =
+            ["access_token", "refresh_token", "token_expiry", "granted_scopes"];
+        NAMES.map(|name| match account {
+            Some(id) => format!("{}account_{}_{}", self.provider.client_key_prefix, id, name),
+            None => format!("{}{}", self.provider.token_key_prefix, name),
+        })
+    }
+
+    async fn token_keys(&self) -> [String; 4] {
+        let account = self.active_account_id().await;
+        self.token_keys_for(account.as_deref())
+    }
+
+    fn client_key(&self, name: &str) -> String {
+        format!("{}{}", self.provider.client_key_prefix, name)
+    }
+
+    fn emit_success<R: Runtime>(&self, app: &AppHandle<R>) {
+        let event = format!("{}-success", self.provider.event_prefix);
+        let _ = app.emit(&event, json!({}));
+    }
+
+    fn emit_failure<R: Runtime>(&self, app: &AppHandle<R>, error: &str) {
+        let event = format!("{}-failed", self.provider.event_prefix);
+        let _ = app.emit(&event, json!({ "error": error }));
+    }
+
+    async fn stored_credential(key: String) -> Option<String> {
+        tokio::task::spawn_blocking(move || credential_store::get(&key).ok())
+            .await
+            .ok()
+            .flatten()
+    }
+
+    pub async fn client_id(&self) -> Option<String> {
+        if let Some(id) = embedded(self.provider.embedded_client_id) {
+            return Some(id);
+        }
+        if let Some(cached) = cached(&self.client_id) {
+            return Some(cached);
+        }
+        let result = Self::stored_credential(self.client_key("client_id")).await;
+        if result.is_some() {
+            set_cached(&self.client_id, result.clone());
+        }
+        result
+    }
+
+    pub async fn client_secret(&self) -> Option<String> {
+        if let Some(secret) = embedded(self.provider.embedded_client_secret) {
+            return Some(secret);
+        }
+        if let Some(cached) = cached(&self.client_secret) {
+            return Some(cached);
+        }
+        let result = Self::stored_credential(self.client_key("client_secret")).await;
+        if result.is_some() {
+            set_cached(&self.client_secret, result.clone());
+        }
+        result
+    }
+
+    pub async fn has_client_credentials(&self) -> bool {
+        self.client_id().await.is_some()
+            && (!self.needs_client_secret() || self.client_secret().await.is_some())
+    }
+
+    pub async fn save_client_credentials(
+        &self,
+        client_id: String,
+        client_secret: Option<String>,
+    ) -> Result<(), String> {
+        let client_id = client_id.trim().to_string();
+        if client_id.is_empty() {
+            return Err("Client ID is empty".to_string());
+        }
+        let client_secret = client_secret.map(|secret| secret.trim().to_string());
+        if self.needs_client_secret() && client_secret.as_deref().unwrap_or_default().is_empty() {
+            return Err("Client Secret is empty".to_string());
+        }
+
+        let name = self.provider.name;
+        let id_key = self.client_key("client_id");
+        let secret_key = self.client_key("client_secret");
+        let (id, secret) = (client_id.clone(), client_secret.clone());
+        tokio::task::spawn_blocking(move || {
+            credential_store::set(&id_key, &id)
+                .map_err(|e| format!("Failed to save {} client ID: {e}", name))?;
+            if let Some(secret) = secret {
+                credential_store::set(&secret_key, &secret)
+                    .map_err(|e| format!("Failed to save {} client secret: {e}", name))?;
+            }
+            Ok::<(), String>(())
+        })
+        .await
+        .map_err(|e| format!("Task failed: {}", e))??;
+
+        set_cached(&self.client_id, Some(client_id));
+        if client_secret.is_some() {
+            set_cached(&self.client_secret, client_secret);
+        }
+        Ok(())
+    }
+
+    /// Stores `tokens` for `account`. Callers hold `refresh_lock` so the
+    /// active account can't change before the cache is updated.
+    async fn save_tokens(
+        &self,
+        account: Option<&str>,
+        tokens: &OAuthTokens,
+    ) -> Result<(), String> {
+        let name = self.provider.name;
+        let keys = self.token_keys_for(account);
+        let values = [
+            tokens.access_token.clone(),
+            tokens.refresh_token.clone(),
+            tokens.expires_at.to_string(),
+            tokens.scopes.join(" "),
+        ];
+
+        tokio::task::spawn_blocking(move || {
+            credential_store::set(&keys[0], &values[0])
+                .map_err(|e| format!("Failed to save {} access token: {e}", name))?;
+            credential_store::set(&keys[1], &values[1])
+                .map_err(|e| format!("Failed to save {} refresh token: {e}", name))?;
+            credential_store::set(&keys[2], &values[2])
+                .map_err(|e| format!("Failed to save {} token expiry: {e}", name))?;
+            credential_store::set(&keys[3], &values[3])
+                .map_err(|e| format!("Failed to save {} granted scopes: {e}", name))?;
+            Ok::<(), String>(())
+        })
+        .await
+        .map_err(|e| format!("Task failed: {}", e))??;
+
+        set_cached(&self.tokens, Some(tokens.clone()));
+        self.tokens_changed.notify_one();
+        Ok(())
+    }
+
+    /// Forgets tokens the provider no longer accepts and asks the frontend to
+    /// log in again. Client credentials are kept so the user only has to
+    /// approve access.
+    async fn expire_tokens<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        account: Option<&str>,
+        error: &str,
+    ) {
+        eprintln!("[oauth] {} refresh token was revoked: {}", self.provider.name, error);
+        set_cached(&self.tokens, None);
+
+        let keys = self.token_keys_for(account);
+        let _ = tokio::task::spawn_blocking(move || {
+            for key in keys {
+                let _ = credential_store::delete(&key);
+            }
+        })
+        .await;
+
+        let _ = app.emit(
+            "auth-expired",
+            json!({ "provider": self.provider.id, "error": error }),
+        );
+    }
+
+    pub async fn tokens(&self) -> Result<OAuthTokens, String> {
+        if let Some(cached) = cached(&self.tokens) {
+            return Ok(cached);
+        }
+
+        let keys = self.token_keys().await;
+        let result = self.stored_tokens(keys).await?;
+        set_cached(&self.tokens, Some(result.clone()));
+        Ok(result)
+    }
+
+    async fn stored_tokens(&self, keys: [String; 4]) -> Result<OAuthTokens, String> {
+        let name = self.provider.name;
+        // Logins from before scopes were recorded got the default scopes.
+        let default_scopes = self.provider.scopes.join(" ");
+        tokio::task::spawn_blocking(move || -> Result<OAuthTokens, String> {
+            let access_token = credential_store::get(&keys[0])
+                .map_err(|e| format!("Failed to get {} access token: {e}", name))?;
+            let refresh_token = credential_store::get(&keys[1])
+                .map_err(|e| format!("Failed to get {} refresh token: {e}", name))?;
+            let expires_at = credential_store::get(&keys[2])
+                .map_err(|e| format!("Failed to get {} token expiry: {e}", name))?
+                .parse::<i64>()
+                .map_err(|e| format!("Failed to parse expiry: {}", e))?;
+            let scopes = credential_store::get(&keys[3]).unwrap_or(default_scopes);
+
+            Ok(OAuthTokens {
+                access_token,
+                refresh_token,
+                expires_at,
+                scopes: split_scopes(&scopes),
+            })
+        })
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+    }
+
+    /// Returns an access token that is valid for at least another minute,
+    /// refreshing it first if needed.
+    pub async fn access_token<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+    ) -> Result<AccessToken, String> {
+        let tokens = self.tokens().await?;
+        if Utc::now().timestamp() + 60 < tokens.expires_at {
+            return Ok(tokens.into());
+        }
+        self.refresh(app).await.map(AccessToken::from)
+    }
+
+    pub async fn has_valid_tokens(&self) -> bool {
+        match self.tokens().await {
+            Ok(tokens) => Utc::now().timestamp() < tokens.expires_at,
+            Err(_) => false,
+        }
+    }
+
+    /// Stops the callback server and forgets the login in progress.
+    pub fn cancel_flow(&self) {
+        set_cached(&self.auth_state, None);
+        if let Ok(mut shutdown) = self.shutdown.lock() {
+            if let Some(tx) = shutdown.take() {
+                let _ = tx.send(());
+            }
+        }
+    }
+
+    /// Revokes the grant behind the refresh token. A token the provider no
+    /// longer knows counts as revoked.
+    async fn revoke(&self, client: &reqwest::Client, tokens: &OAuthTokens) -> Result<(), String> {
+        let Some(endpoint) = &self.provider.revoke_url else {
+            return Ok(());
+        };
+        let name = self.provider.name;
+        let request = client
+            .post(endpoint.url())
+            .timeout(Duration::from_secs(REVOKE_TIMEOUT_SECS))
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .form(&[("token", tokens.refresh_token.as_str())]);
+        let response = http::send(request)
+            .await
+            .map_err(|e| format!("Couldn't reach {} to revoke access: {}", name, e))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let text = response.text().await.unwrap_or_default();
+        if status == StatusCode::BAD_REQUEST && text.contains("invalid_token") {
+            return Ok(());
+        }
+        Err(format!("{} refused to revoke access: {} - {}", name, status, text))
+    }
+
+    /// Revokes every stored grant at the provider, then deletes the tokens of
+    /// every account and the stored client credentials. Local state is wiped
+    /// even when revocation fails.
+    pub async fn clear<R: Runtime>(&self, app: &AppHandle<R>) -> Result<SignOutReport, String> {
+        self.cancel_flow();
+        let _guard = self.refresh_lock.lock().await;
+        let accounts = self.accounts().await;
+
+        let mut report = SignOutReport {
+            revocation_supported: self.provider.revoke_url.is_some(),
+            ..Default::default()
+        };
+        if report.revocation_supported {
+            let client = http::client(app);
+            let mut token_keys = vec![self.token_keys_for(None)];
+            token_keys.extend(accounts.iter().map(|a| self.token_keys_for(Some(&a.id))));
+            for keys in token_keys {
+                let Ok(tokens) = self.stored_tokens(keys).await else {
+                    continue;
+                };
+                match self.revoke(&client, &tokens).await {
+                    Ok(()) => report.revoked += 1,
+                    Err(e) => {
+                        eprintln!("[oauth] {}", e);
+                        report.failed.push(e);
+                    }
+                }
+            }
+        }
+
+        set_cached(&self.client_id, None);
+        set_cached(&self.client_secret, None);
+        set_cached(&self.tokens, None);
+        set_cached(&self.accounts, None);
+        set_cached(&self.active_account, None);
+
+        let mut keys = self.token_keys_for(None).to_vec();
+        for account in &accounts {
+            keys.extend(self.token_keys_for(Some(&account.id)));
+        }
+        keys.extend([
+            self.client_key("client_id"),
+            self.client_key("client_secret"),
+            self.client_key("accounts"),
+            self.client_key("active_account"),
+        ]);
+        tokio::task::spawn_blocking(move || {
+            for key in keys {
+                let _ = credential_store::delete(&key);
+            }
+        })
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?;
+        Ok(report)
+    }
+
+    async fn active_account_id(&self) -> Option<String> {
+        if !self.supports_accounts() {
+            return None;
+        }
+        if let Some(cached) = cached(&self.active_account) {
+            return Some(cached);
+        }
+        let result = Self::stored_credential(self.client_key("active_account")).await;
+        if result.is_some() {
+            set_cached(&self.active_account, result.clone());
+        }
+        result
+    }
+
+    pub async fn accounts(&self) -> Vec<Account> {
+        if let Some(cached) = cached(&self.accounts) {
+            return cached;
+        }
+        let accounts: Vec<Account> = Self::stored_credential(self.client_key("accounts"))
+            .await
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        set_cached(&self.accounts, Some(accounts.clone()));
+        accounts
+    }
+
+    pub async fn account_list(&self) -> AccountList {
+        AccountList {
+            accounts: self.accounts().await,
+            active_account: self.active_account_id().await,
+        }
+    }
+
+    async fn save_accounts(&self, accounts: Vec<Account>) -> Result<(), String> {
+        let json = serde_json::to_string(&accounts).map_err(|e| e.to_string())?;
+        let key = self.client_key("accounts");
+        tokio::task::spawn_blocking(move || credential_store::set(&key, &json))
+            .await
+            .map_err(|e| format!("Task failed: {}", e))?
+            .map_err(|e| format!("Failed to save accounts: {e}"))?;
+        set_cached(&self.accounts, Some(accounts));
+        Ok(())
+    }
+
+    /// Adds an account or refreshes its name and avatar.
+    async fn upsert_account(&self, account: Account) -> Result<(), String> {
+        let mut accounts = self.accounts().await;
+        match accounts.iter_mut().find(|a| a.id == account.id) {
+            Some(existing) => *existing = account,
+            None => accounts.push(account),
+        }
+        self.save_accounts(accounts).await
+    }
+
+    /// Makes `account` the one whose tokens are used. The token cache is
+    /// dropped so the next request loads the new account's tokens.
+    async fn set_active_account(&self, account: Option<String>) -> Result<(), String> {
+        let key = self.client_key("active_account");
+        let value = account.clone();
+        tokio::task::spawn_blocking(move || match value {
+            Some(id) => credential_store::set(&key, &id),
+            None => {
+                let _ = credential_store::delete(&key);
+                Ok(())
+            }
+        })
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+        .map_err(|e| format!("Failed to save active account: {e}"))?;
+
+        set_cached(&self.active_account, account);
+        set_cached(&self.tokens, None);
+        self.tokens_changed.notify_one();
+        Ok(())
+    }
+
+    async fn emit_account_changed<R: Runtime>(&self, app: &AppHandle<R>) {
+        if !self.supports_accounts() {
+            return;
+        }
+        let active = self.active_account_id().await;
+        let account = self
+            .accounts()
+            .await
+            .into_iter()
+            .find(|a| Some(&a.id) == active.as_ref());
+        let _ = app.emit(
+            "account-changed",
+            json!({ "provider": self.provider.id, "account": account }),
+        );
+    }
+
+    pub async fn switch_account<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        account_id: String,
+    ) -> Result<(), String> {
+        if !self.accounts().await.iter().any(|a| a.id == account_id) {
+            return Err(format!("Unknown {} account: {}", self.provider.name, account_id));
+        }
+        if self.active_account_id().await.as_ref() == Some(&account_id) {
+            return Ok(());
+        }
+        // Don't let a refresh of the old account finish after the switch.
+        let _guard = self.refresh_lock.lock().await;
+        self.set_active_account(Some(account_id)).await?;
+        self.emit_account_changed(app).await;
+        Ok(())
+    }
+
+    /// Forgets an account and its tokens. Removing the active account switches
+    /// to the next one, or logs out if it was the last.
+    pub async fn remove_account<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        account_id: String,
+    ) -> Result<(), String> {
+        let mut accounts = self.accounts().await;
+        let Some(index) = accounts.iter().position(|a| a.id == account_id) else {
+            return Err(format!("Unknown {} account: {}", self.provider.name, account_id));
+        };
+        accounts.remove(index);
+        let next = accounts.first().map(|a| a.id.clone());
+        self.save_accounts(accounts).await?;
+
+        let keys = self.token_keys_for(Some(&account_id));
+        if let Ok(tokens) = self.stored_tokens(keys.clone()).await {
+            if let Err(e) = self.revoke(&http::client(app), &tokens).await {
+                eprintln!("[oauth] {}", e);
+            }
+        }
+        let _ = tokio::task::spawn_blocking(move || {
+            for key in keys {
+                let _ = credential_store::delete(&key);
+            }
+        })
+        .await;
+
+        if self.active_account_id().await.as_ref() == Some(&account_id) {
+            let _guard = self.refresh_lock.lock().await;
+            self.set_active_account(next).await?;
+            self.emit_account_changed(app).await;
+        }
+        Ok(())
+    }
+
+    /// Moves tokens saved before accounts existed to the account they belong
+    /// to, so upgrading doesn't log the user out.
+    async fn migrate_legacy_tokens<R: Runtime>(&self, app: &AppHandle<R>) {
+        if !self.supports_accounts() || self.active_account_id().await.is_some() {
+            return;
+        }
+        let Ok(token) = self.access_token(app).await else {
+            return;
+        };
+        let Ok(tokens) = self.tokens().await else {
+            return;
+        };
+        let client = http::client(app);
+        let profile = match self.verify_access(&client, &token.access_token).await {
+            Ok(profile) => profile,
+            Err(e) => {
+                eprintln!("[oauth] Couldn't look up the {} account: {}", self.provider.name, e);
+                return;
+            }
+        };
+        let Some(account) = self.account_from_profile(profile.as_ref()) else {
+            return;
+        };
+
+        let _guard = self.refresh_lock.lock().await;
+        if self.upsert_account(account.clone()).await.is_err()
+            || self.set_active_account(Some(account.id.clone())).await.is_err()
+            || self.save_tokens(Some(&account.id), &tokens).await.is_err()
+        {
+            return;
+        }
+        let legacy = self.token_keys_for(None);
+        let _ = tokio::task::spawn_blocking(move || {
+            for key in legacy {
+                let _ = credential_store::delete(&key);
+            }
+        })
+        .await;
+    }
+
+    /// Redirect URIs that must be registered with the provider, one per
+    /// configured port. Empty for providers that accept any loopback port.
+    pub fn redirect_uris<R: Runtime>(&self, app: &AppHandle<R>) -> Vec<String> {
+        match self.provider.callback_ports {
+            CallbackPorts::Configured => configured_callback_ports(app)
+                .into_iter()
+                .map(redirect_uri_for_port)
+                .collect(),
+            CallbackPorts::Ephemeral => Vec::new(),
+        }
+    }
+
+    async fn request_tokens(
+        &self,
+        client: &reqwest::Client,
+        form: &[(&str, &str)],
+    ) -> Result<TokenResponse, TokenError> {
+        let request = client
+            .post(self.provider.token_url.url())
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .form(form);
+        let response = http::send(request)
+            .await
+            .map_err(|e| TokenError::failed(format!("Token request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            let revoked = serde_json::from_str::<serde_json::Value>(&text)
+                .ok()
+                .is_some_and(|body| body["error"] == "invalid_grant");
+            return Err(TokenError {
+                message: format!("Token request failed: {} - {}", status, text),
+                revoked,
+            });
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| TokenError::failed(format!("Failed to parse token response: {}", e)))
+    }
+
+    async fn exchange_code(
+        &self,
+        client: &reqwest::Client,
+        state: &AuthState,
+        code: &str,
+    ) -> Result<OAuthTokens, String> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &state.redirect_uri),
+            ("client_id", &state.client_id),
+        ];
+        if let Some(verifier) = &state.code_verifier {
+            form.push(("code_verifier", verifier));
+        }
+        if let Some(secret) = &state.client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let tr = self
+            .request_tokens(client, &form)
+            .await
+            .map_err(|e| e.message)?;
+        let expires_at = Utc::now().timestamp() + tr.expires_in - 30;
+        let refresh_token = tr
+            .refresh_token
+            .ok_or_else(|| "Missing refresh_token in response".to_string())?;
+
+        let scopes = match tr.scope {
+            Some(scope) => split_scopes(&scope),
+            None => state.scopes.clone(),
+        };
+
+        Ok(OAuthTokens {
+            access_token: tr.access_token,
+            refresh_token,
+            expires_at,
+            scopes,
+        })
+    }
+
+    /// Checks the token against `verify_url` and returns the response body.
+    async fn verify_access(
+        &self,
+        client: &reqwest::Client,
+        access_token: &str,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let Some(endpoint) = &self.provider.verify_url else {
+            return Ok(None);
+        };
+        let request = client.get(endpoint.url()).bearer_auth(access_token);
+        let response = http::send(request)
+            .await
+            .map_err(|e| format!("Verification request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(format!("Verification failed: {} - {}", status, text));
+        }
+        Ok(response.json().await.ok())
+    }
+
+    fn account_from_profile(&self, profile: Option<&serde_json::Value>) -> Option<Account> {
+        let parse = self.provider.parse_account?;
+        profile.and_then(parse)
+    }
+
+    /// Checks that fresh tokens from a login work and stores them, under the
+    /// logged-in account if the provider has accounts.
+    async fn finish_login<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        tokens: &OAuthTokens,
+    ) -> Result<(), LoginError> {
+        let client = http::client(app);
+        let profile = match self.verify_access(&client, &tokens.access_token).await {
+            Ok(profile) => profile,
+            Err(e) => {
+                self.emit_failure(app, &e);
+                return Err(LoginError::new("Invalid credentials - check your Client ID", e));
+            }
+        };
+
+        let _guard = self.refresh_lock.lock().await;
+        let mut account_id = None;
+        if self.supports_accounts() {
+            let Some(account) = self.account_from_profile(profile.as_ref()) else {
+                let e = format!("{} didn't say which account logged in", self.provider.name);
+                self.emit_failure(app, &e);
+                return Err(LoginError::new("Failed to read your account", e));
+            };
+            if let Err(e) = self.upsert_account(account.clone()).await {
+                self.emit_failure(app, &e);
+                return Err(LoginError::new("Failed to save credentials", e));
+            }
+            if let Err(e) = self.set_active_account(Some(account.id.clone())).await {
+                self.emit_failure(app, &e);
+                return Err(LoginError::new("Failed to save credentials", e));
+            }
+            account_id = Some(account.id);
+        }
+
+        if let Err(e) = self.save_tokens(account_id.as_deref(), tokens).await {
+            self.emit_failure(app, &e);
+            return Err(LoginError::new("Failed to save credentials", e));
+        }
+        self.emit_account_changed(app).await;
+        self.emit_success(app);
+        Ok(())
+    }
+
+    /// Starts the callback server and opens the provider's login page.
+    pub async fn start_flow<R: Runtime>(&'static self, app: AppHandle<R>) -> Result<(), String> {
+        self.start_flow_with(app, &[], Vec::new()).await
+    }
+
+    /// Logs in to another account. The new account becomes the active one.
+    pub async fn add_account<R: Runtime>(&'static self, app: AppHandle<R>) -> Result<(), String> {
+        self.start_flow_with(app, self.provider.add_account_params, Vec::new())
+            .await
+    }
+
+    fn feature_scopes(&self, feature: &str) -> Result<&'static [&'static str], String> {
+        self.provider
+            .feature_scopes
+            .iter()
+            .find(|(name, _)| *name == feature)
+            .map(|(_, scopes)| *scopes)
+            .ok_or_else(|| format!("Unknown {} feature: {}", self.provider.name, feature))
+    }
+
+    pub async fn has_scope(&self, scope: &str) -> bool {
+        self.tokens()
+            .await
+            .is_ok_and(|tokens| tokens.scopes.iter().any(|s| s == scope))
+    }
+
+    /// Whether every scope the feature needs has been granted.
+    pub async fn has_feature(&self, feature: &str) -> Result<bool, String> {
+        let needed = self.feature_scopes(feature)?;
+        let granted = self.tokens().await.map(|t| t.scopes).unwrap_or_default();
+        Ok(needed.iter().all(|scope| granted.iter().any(|s| s == scope)))
+    }
+
+    /// Asks the user to grant the scopes of a feature on top of the ones they
+    /// already granted. Completes like a normal login.
+    pub async fn request_feature<R: Runtime>(
+        &'static self,
+        app: AppHandle<R>,
+        feature: &str,
+    ) -> Result<(), String> {
+        let needed = self.feature_scopes(feature)?;
+        let mut scopes = self.tokens().await.map(|t| t.scopes).unwrap_or_default();
+        scopes.extend(needed.iter().map(|s| s.to_string()));
+        self.start_flow_with(app, self.provider.incremental_params, scopes)
+            .await
+    }
+
+    async fn start_flow_with<R: Runtime>(
+        &'static self,
+        app: AppHandle<R>,
+        extra_params: &[(&str, &str)],
+        extra_scopes: Vec<String>,
+    ) -> Result<(), String> {
+        let auth_url = self.prepare_flow(app, extra_params, extra_scopes).await?;
+        webbrowser::open(&auth_url).map_err(|e| format!("Failed to open browser: {}", e))?;
+        Ok(())
+    }
+
+    /// Starts the callback server and returns the authorization URL without
+    /// opening a browser, so tests can drive the login themselves.
+    pub async fn begin_flow<R: Runtime>(
+        &'static self,
+        app: AppHandle<R>,
+    ) -> Result<String, String> {
+        self.prepare_flow(app, &[], Vec::new()).await
+    }
+
+    async fn prepare_flow<R: Runtime>(
+        &'static self,
+        app: AppHandle<R>,
+        extra_params: &[(&str, &str)],
+        extra_scopes: Vec<String>,
+    ) -> Result<String, String> {
+        self.cancel_flow();
+        sleep(Duration::from_millis(100)).await;
+
+        let name = self.provider.name;
+        let client_id = self.client_id().await.ok_or_else(|| {
+            format!("No {} Client ID configured. Please set up your credentials first.", name)
+        })?;
+        let client_secret = if self.needs_client_secret() {
+            Some(self.client_secret().await.ok_or_else(|| {
+                format!("No {} Client Secret configured. Please set up your credentials first.", name)
+            })?)
+        } else {
+            None
+        };
+
+        let ports = match self.provider.callback_ports {
+            CallbackPorts::Configured => configured_callback_ports(&app),
+            CallbackPorts::Ephemeral => vec![0],
+        };
+        // Without a callback server the login can still be completed by
+        // pasting the redirect URL into `complete_flow_manually`.
+        let (listener, port) = match bind_callback_listener(&ports, name).await {
+            Ok((listener, port)) => (Some(listener), port),
+            Err(msg) => match configured_callback_ports(&app).first() {
+                Some(&port) => {
+                    eprintln!("{}. Continuing without a callback server", msg);
+                    (None, port)
+                }
+                None => {
+                    self.emit_failure(&app, &msg);
+                    return Err(msg);
+                }
+            },
+        };
+        let redirect_uri = redirect_uri_for_port(port);
+
+        let code_verifier = match self.provider.client_auth {
+            ClientAuth::Pkce => Some(generate_code_verifier()),
+            ClientAuth::ClientSecret => None,
+        };
+        let state_nonce = generate_state_nonce();
+
+        let mut scopes: Vec<String> =
+            self.provider.scopes.iter().map(|s| s.to_string()).collect();
+        for scope in extra_scopes {
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        query
+            .append_pair("client_id", &client_id)
+            .append_pair("response_type", "code")
+            .append_pair("redirect_uri", &redirect_uri)
+            .append_pair("scope", &scopes.join(" "))
+            .append_pair("state", &state_nonce);
+        if let Some(verifier) = &code_verifier {
+            query
+                .append_pair("code_challenge_method", "S256")
+                .append_pair("code_challenge", &generate_code_challenge(verifier));
+        }
+        query.extend_pairs(self.provider.authorize_params);
+        query.extend_pairs(extra_params);
+        let auth_url = format!("{}?{}", self.provider.authorize_url.url(), query.finish());
+
+        set_cached(
+            &self.auth_state,
+            Some(AuthState {
+                scopes,
+                client_id,
+                client_secret,
+                code_verifier,
+                state_nonce,
+                redirect_uri,
+            }),
+        );
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        set_cached(&self.shutdown, Some(shutdown_tx));
+
+        let app_handle = app.clone();
+        rt::spawn(async move {
+            let (callback_done_tx, mut callback_done_rx) = mpsc::channel::<()>(1);
+
+            let router = Router::new().route(
+                "/callback",
+                get({
+                    let app = app_handle.clone();
+                    move |query: Query<HashMap<String, String>>| {
+                        let app = app.clone();
+                        let done_tx = callback_done_tx.clone();
+                        async move {
+                            let result = self.handle_callback(query, app).await;
+                            let _ = done_tx.send(()).await;
+                            result
+                        }
+                    }
+                }),
+            );
+
+            let server = async move {
+                match listener {
+                    Some(listener) => {
+                        let _ = axum::serve(listener, router).await;
+                    }
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                _ = server => {}
+                _ = callback_done_rx.recv() => {
+                    sleep(Duration::from_millis(500)).await;
+                }
+                _ = shutdown_rx => {}
+                _ = sleep(LOGIN_TIMEOUT) => {
+                    self.emit_failure(&app_handle, "OAuth timeout - please try again");
+                }
+            }
+
+            set_cached(&self.shutdown, None);
+        });
+
+        Ok(auth_url)
+    }
+
+    async fn handle_callback<R: Runtime>(
+        &self,
+        query: Query<HashMap<String, String>>,
+        app: AppHandle<R>,
+    ) -> Html<String> {
+        if let Some(err) = query.get("error") {
+            self.emit_failure(&app, err);
+            return Html(error_page("Authentication was denied or failed"));
+        }
+
+        let Some(code) = query.get("code") else {
+            self.emit_failure(&app, "missing_code");
+            return Html(error_page("Missing authorization code"));
+        };
+
+        let Some(st) = cached(&self.auth_state) else {
+            self.emit_failure(&app, "no_auth_state");
+            return Html(error_page(
+                "Session expired - please close old browser tabs and try again",
+            ));
+        };
+
+        if query.get("state") != Some(&st.state_nonce) {
+            return Html(error_page(
+                "This login session has expired. Please close this tab and try again in the app.",
+            ));
+        }
+
+        set_cached(&self.auth_state, None);
+
+        let tokens = match self.exchange_code(&http::client(&app), &st, code).await {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                self.emit_failure(&app, &e);
+                return Html(error_page(&format!("Token exchange failed: {}", e)));
+            }
+        };
+        match self.finish_login(&app, &tokens).await {
+            Ok(()) => Html(success_page(&self.provider)),
+            Err(e) => Html(error_page(e.page_message)),
+        }
+    }
+
+    /// Completes the login with the redirect URL (or bare code) the user copied
+    /// from the browser, for setups where the loopback callback can't be
+    /// reached. Parse and state errors leave the login pending so the user can
+    /// paste again.
+    pub async fn complete_flow_manually<R: Runtime>(
+        &self,
+        app: AppHandle<R>,
+        input: String,
+    ) -> Result<(), String> {
+        let pasted = parse_pasted_authorization(&input)?;
+
+        let st = cached(&self.auth_state)
+            .ok_or_else(|| "No login in progress - please start the login again".to_string())?;
+
+        match &pasted.state {
+            Some(state) if *state != st.state_nonce => {
+                return Err(
+                    "This URL belongs to an older login attempt. Please paste the latest one."
+                        .to_string(),
+                );
+            }
+            // Without PKCE the state is all that ties the code to this login.
+            None if st.code_verifier.is_none() => {
+                return Err(
+                    "Please paste the full URL from the address bar, not just the code."
+                        .to_string(),
+                );
+            }
+            _ => {}
+        }
+
+        let tokens = self
+            .exchange_code(&http::client(&app), &st, &pasted.code)
+            .await?;
+        self.cancel_flow();
+
+        self.finish_login(&app, &tokens).await.map_err(|e| e.error)
+    }
+
+    /// Exchanges the refresh token for a new access token. Concurrent callers
+    /// wait for the request already in flight instead of sending their own.
+    pub async fn refresh<R: Runtime>(&self, app: &AppHandle<R>) -> Result<OAuthTokens, String> {
+        let account = self.active_account_id().await;
+        let stale = self.tokens().await?;
+        let _guard = self.refresh_lock.lock().await;
+
+        // The tokens may belong to an account that was switched away from
+        // while this caller waited for the lock.
+        if self.active_account_id().await != account {
+            return Err(format!(
+                "The {} account changed during the token refresh",
+                self.provider.name
+            ));
+        }
+
+        // Another caller refreshed while this one waited for the lock.
+        if let Some(current) = cached(&self.tokens) {
+            if current.access_token != stale.access_token {
+                return Ok(current);
+            }
+        }
+
+        match self
+            .request_refresh(&http::client(app), account.as_deref(), stale)
+            .await
+        {
+            Ok(tokens) => Ok(tokens),
+            Err(e) => {
+                if e.revoked {
+                    self.expire_tokens(app, account.as_deref(), &e.message).await;
+                }
+                Err(e.message)
+            }
+        }
+    }
+
+    async fn request_refresh(
+        &self,
+        client: &reqwest::Client,
+        account: Option<&str>,
+        tokens: OAuthTokens,
+    ) -> Result<OAuthTokens, TokenError> {
+        let name = self.provider.name;
+        let client_id = self
+            .client_id()
+            .await
+            .ok_or_else(|| TokenError::failed(format!("No {} Client ID configured", name)))?;
+        let client_secret = if self.needs_client_secret() {
+            Some(self.client_secret().await.ok_or_else(|| {
+                TokenError::failed(format!("No {} Client Secret configured", name))
+            })?)
+        } else {
+            None
+        };
+
+        let mut form = vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", tokens.refresh_token.as_str()),
+            ("client_id", client_id.as_str()),
+        ];
+        if let Some(secret) = &client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let rr = self.request_tokens(client, &form).await.map_err(|e| TokenError {
+            message: e.message.replacen("Token request", "Refresh", 1),
+            revoked: e.revoked,
+        })?;
+
+        // Providers only rotate the refresh token sometimes.
+        let updated = OAuthTokens {
+            access_token: rr.access_token,
+            refresh_token: rr.refresh_token.unwrap_or(tokens.refresh_token),
+            expires_at: Utc::now().timestamp() + rr.expires_in - 30,
+            scopes: rr.scope.map(|s| split_scopes(&s)).unwrap_or(tokens.scopes),
+        };
+        self.save_tokens(account, &updated)
+            .await
+            .map_err(TokenError::failed)?;
+        Ok(updated)
+    }
+
+    /// Sleeps until `deadline` (a Unix timestamp), new tokens are saved or the
+    /// system resumes from suspend, whichever comes first.
+    async fn sleep_until(&self, deadline: i64) -> Wake {
+        loop {
+            let now = Utc::now().timestamp();
+            if now >= deadline {
+                return Wake::Due;
+            }
+            let step = (deadline - now).min(SCHEDULER_TICK_SECS);
+            tokio::select! {
+                _ = self.tokens_changed.notified() => return Wake::TokensChanged,
+                _ = sleep(Duration::from_secs(step as u64)) => {}
+            }
+            if Utc::now().timestamp() - now > step + RESUME_GAP_SECS {
+                return Wake::Resumed;
+            }
+        }
+    }
+
+    /// Keeps the access token fresh: refreshes shortly before `expires_at`,
+    /// right after a resume from suspend, and with backoff after failures.
+    /// Stops refreshing once the provider revokes the refresh token, until the
+    /// user logs in again.
+    pub fn spawn_refresh_task<R: Runtime>(&'static self, app: AppHandle<R>) {
+        rt::spawn(async move {
+            self.migrate_legacy_tokens(&app).await;
+            let mut failures = 0;
+            loop {
+                let Ok(tokens) = self.tokens().await else {
+                    self.tokens_changed.notified().await;
+                    failures = 0;
+                    continue;
+                };
+
+                let deadline = if failures == 0 {
+                    tokens.expires_at - REFRESH_MARGIN_SECS
+                } else {
+                    let delay = REFRESH_RETRY_SECS[(failures - 1).min(REFRESH_RETRY_SECS.len() - 1)];
+                    Utc::now().timestamp() + delay
+                };
+
+                match self.sleep_until(deadline).await {
+                    Wake::TokensChanged => {
+                        failures = 0;
+                        continue;
+                    }
+                    Wake::Due | Wake::Resumed => {}
+                }
+
+                match self.refresh(&app).await {
+                    Ok(_) => failures = 0,
+                    Err(e) => {
+                        failures += 1;
+                        eprintln!("[oauth] {} token refresh failed: {}", self.provider.name, e);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// A failed token endpoint request. `revoked` is set for `invalid_grant`,
+/// which retrying can't fix.
+struct TokenError {
+    message: String,
+    revoked: bool,
+}
+
+impl TokenError {
+    fn failed(message: String) -> Self {
+        Self {
+            message,
+            revoked: false,
+        }
+    }
+}
+
+/// A failed login step: the detailed error for the frontend and a short
+/// message for the page shown in the browser.
+struct LoginError {
+    page_message: &'static str,
+    error: String,
+}
+
+impl LoginError {
+    fn new(page_message: &'static str, error: String) -> Self {
+        Self {
+            page_message,
+            error,
+        }
+    }
+}
+
+fn success_page(provider: &OAuthProvider) -> String {
+    format!(
+        r##"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>MiniFy - {name} Connected</title></head>
+<body style="font-family:system-ui,sans-serif;background:#0a0a0a;color:#fff;display:flex;justify-content:center;align-items:center;height:100vh;margin:0">
+<div style="text-align:center;padding:2rem;background:rgba(255,255,255,0.05);border-radius:16px;border:1px solid rgba(255,255,255,0.1)">
+<div style="width:64px;height:64px;background:{accent};border-radius:12px;display:flex;align-items:center;justify-content:center;margin:0 auto 1rem">
+<svg width="32" height="32" viewBox="0 0 24 24" fill="none" stroke="{foreground}" stroke-width="3"><polyline points="20 6 9 17 4 12"></polyline></svg>
+</div>
+<h1 style="color:{accent};margin:0 0 0.5rem;font-size:1.5rem">{name} Connected!</h1>
+<p style="color:rgba(255,255,255,0.6);margin:0">You can close this window</p>
+<script>setTimeout(()=>window.close(),1500)</script>
+</div></body></html>"##,
+        name = escape_html(provider.name),
+        accent = provider.accent_color,
+        foreground = provider.accent_foreground,
+    )
+}
+
+fn escape_html(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#x27;")
+}
+
+fn error_page(message: &str) -> String {
+    let escaped_message = escape_html(message);
+    format!(
+        r##"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>MiniFy - Error</title></head>
+<body style="font-family:system-ui,sans-serif;background:#0a0a0a;color:#fff;display:flex;justify-content:center;align-items:center;height:100vh;margin:0">
+<div style="text-align:center;padding:2rem;background:rgba(255,255,255,0.05);border-radius:16px;border:1px solid rgba(239,68,68,0.3);max-width:400px">
+<div style="width:64px;height:64px;background:#ef4444;border-radius:12px;display:flex;align-items:center;justify-content:center;margin:0 auto 1rem">
+<svg width="32" height="32" viewBox="0 0 24 24" fill="none" stroke="white" stroke-width="2"><circle cx="12" cy="12" r="10"></circle><line x1="15" y1="9" x2="9" y2="15"></line><line x1="9" y1="9" x2="15" y2="15"></line></svg>
+</div>
+<h1 style="color:#ef4444;margin:0 0 0.5rem;font-size:1.25rem">Authentication Failed</h1>
+<p style="color:rgba(255,255,255,0.6);margin:0;font-size:0.9rem">{}</p>
+<p style="color:rgba(255,255,255,0.4);margin:1rem 0 0;font-size:0.8rem">Please close this window and try again in the app</p>
+</div></body></html>"##,
+        escaped_message
+    )
+}
+
+/// Authorization response pasted by the user when the browser can't reach the
+/// loopback callback server, e.g. inside Flatpak or over remote desktop.
+#[derive(Debug, Clone)]
+pub struct PastedAuthorization {
+    pub code: String,
+    /// Only known when a full redirect URL was pasted.
+    pub state: Option<String>,
+}
+
+/// Accepts the full redirect URL from the browser's address bar, just its
+/// query string, or the bare authorization code.
+pub fn parse_pasted_authorization(input: &str) -> Result<PastedAuthorization, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Paste the URL from your browser's address bar".to_string());
+    }
+
+    let query = match input.split_once('?') {
+        Some((_, query)) => query,
+        None if input.contains('=') => input,
+        None if input.chars().any(char::is_whitespace) => {
+            return Err("That doesn't look like a redirect URL or authorization code".to_string());
+        }
+        None => {
+            return Ok(PastedAuthorization {
+                code: input.to_string(),
+                state: None,
+            });
+        }
+    };
+    let query = query.split('#').next().unwrap_or_default();
+
+    let mut code = None;
+    let mut state = None;
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "code" => code = Some(value.into_owned()),
+            "state" => state = Some(value.into_owned()),
+            "error" => return Err(format!("Authorization failed: {}", value)),
+            _ => {}
+        }
+    }
+
+    match code.filter(|code| !code.is_empty()) {
+        Some(code) => Ok(PastedAuthorization { code, state }),
+        None => Err("The pasted URL doesn't contain an authorization code".to_string()),
+    }
+}
@@ -0,0 +1,490 @@
+//! Offline mode. Spotify requests that fail to connect mark the app offline
+//! until a probe reaches Spotify again. Meanwhile the player keeps showing
+//! the last known track, and library and playlist changes go into an outbox
+//! in `outbox.json` that is replayed once the connection is back. Changes
+//! that fail for a reason that may pass, like a rate limit or a token refresh
+//! that didn't go through, stay queued and are retried later, up to
+//! `MAX_REPLAY_ATTEMPTS` times. Each change is only sent while the account it
+//! was made with is the active one.
+
+use crate::atomic_file;
+use crate::endpoints::SPOTIFY_API;
+use crate::http;
+use crate::oauth::AccountList;
+use crate::playback;
+use crate::spotify_api::{self, SpotifyError};
+use crate::spotify_auth::SPOTIFY;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{async_runtime as rt, AppHandle, Emitter, Manager, Runtime};
+use tokio::time::sleep;
+
+/// Connectivity or the outbox changed. Payload: `OfflineStatus`.
+pub const OFFLINE_STATUS_EVENT: &str = "offline-status";
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long to wait before replaying again after a change failed, indexed
+/// by the number of failures in a row.
+const REPLAY_RETRY_SECS: [u64; 4] = [10, 30, 120, 600];
+/// Failed replays after which a change is given up on and becomes a
+/// conflict, so it doesn't hold up the changes queued behind it for good.
+const MAX_REPLAY_ATTEMPTS: u32 = 8;
+
+/// A change that can wait for the connection to come back.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Operation {
+    AddToPlaylist {
+        playlist_id: String,
+        uris: Vec<String>,
+    },
+    SaveTracks {
+        ids: Vec<String>,
+    },
+}
+
+impl Operation {
+    async fn apply<R: Runtime>(&self, app: &AppHandle<R>) -> Result<(), SpotifyError> {
+        match self {
+            Operation::AddToPlaylist { playlist_id, uris } => {
+                spotify_api::add_to_playlist(app, playlist_id, uris).await
+            }
+            Operation::SaveTracks { ids } => spotify_api::save_tracks(app, ids).await,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PendingOperation {
+    pub id: u64,
+    pub operation: Operation,
+    /// The Spotify account the change was made with.
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Unix time in milliseconds.
+    pub queued_at: i64,
+    /// Replays that failed for a reason that may pass.
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+/// A queued operation Spotify rejected on replay, e.g. because the playlist
+/// was deleted in the meantime, or one that kept failing. It is dropped from
+/// the outbox.
+#[derive(Serialize, Debug, Clone)]
+pub struct Conflict {
+    pub operation: PendingOperation,
+    pub error: String,
+}
+
+/// What `apply_or_queue` did with a change.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Spotify has the change.
+    Applied,
+    /// The change waits in the outbox for the connection or for the changes
+    /// queued before it.
+    Queued,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct OfflineStatus {
+    pub online: bool,
+    pub pending: Vec<PendingOperation>,
+    pub conflicts: Vec<Conflict>,
+}
+
+lazy_static::lazy_static! {
+    static ref OUTBOX: Mutex<Option<Vec<PendingOperation>>> = Mutex::new(None);
+    static ref CONFLICTS: Mutex<Vec<Conflict>> = Mutex::new(Vec::new());
+}
+
+static ONLINE: AtomicBool = AtomicBool::new(true);
+static REPLAYING: AtomicBool = AtomicBool::new(false);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn outbox_path<R: Runtime>(app: &AppHandle<R>) -> PathBuf {
+    let mut path = app
+        .path()
+        .app_data_dir()
+        .unwrap_or_else(|_| PathBuf::from("."));
+    fs::create_dir_all(&path).ok();
+    path.push("outbox.json");
+    path
+}
+
+fn load_outbox<R: Runtime>(app: &AppHandle<R>) -> Vec<PendingOperation> {
+    let pending: Vec<PendingOperation> = fs::read_to_string(outbox_path(app))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let next_id = pending.iter().map(|p| p.id).max().unwrap_or(0) + 1;
+    NEXT_ID.fetch_max(next_id, Ordering::SeqCst);
+    pending
+}
+
+fn save_outbox<R: Runtime>(app: &AppHandle<R>, pending: &[PendingOperation]) {
+    if let Err(e) = atomic_file::write_json(&outbox_path(app), pending) {
+        eprintln!("Failed to save outbox: {}", e);
+    }
+}
+
+/// Runs `f` on the outbox and saves it afterwards.
+fn with_outbox<R: Runtime, T>(
+    app: &AppHandle<R>,
+    f: impl FnOnce(&mut Vec<PendingOperation>) -> T,
+) -> Option<T> {
+    let mut guard = OUTBOX.lock().ok()?;
+    let pending = guard.get_or_insert_with(|| load_outbox(app));
+    let result = f(pending);
+    save_outbox(app, pending);
+    Some(result)
+}
+
+fn pending<R: Runtime>(app: &AppHandle<R>) -> Vec<PendingOperation> {
+    OUTBOX
+        .lock()
+        .ok()
+        .map(|mut guard| guard.get_or_insert_with(|| load_outbox(app)).clone())
+        .unwrap_or_default()
+}
+
+pub fn status<R: Runtime>(app: &AppHandle<R>) -> OfflineStatus {
+    OfflineStatus {
+        online: is_online(),
+        pending: pending(app),
+        conflicts: CONFLICTS.lock().map(|c| c.clone()).unwrap_or_default(),
+    }
+}
+
+fn emit_status<R: Runtime>(app: &AppHandle<R>) {
+    let _ = app.emit(OFFLINE_STATUS_EVENT, status(app));
+}
+
+pub fn is_online() -> bool {
+    ONLINE.load(Ordering::SeqCst)
+}
+
+/// Records whether Spotify could be reached. Going offline starts probing
+/// for the connection; coming back replays the outbox.
+pub fn set_online<R: Runtime>(app: &AppHandle<R>, online: bool) {
+    if ONLINE.swap(online, Ordering::SeqCst) == online {
+        return;
+    }
+    emit_status(app);
+    if online {
+        eprintln!("Connection to Spotify restored");
+        playback::poll_soon();
+        replay_soon(app);
+    } else {
+        eprintln!("Connection to Spotify lost, working offline");
+        rt::spawn(probe_until_online(app.clone()));
+    }
+}
+
+/// Whether Spotify answers at all. Any HTTP response counts.
+async fn reachable<R: Runtime>(app: &AppHandle<R>) -> bool {
+    http::client(app)
+        .head(SPOTIFY_API.base_url())
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .is_ok()
+}
+
+async fn probe_until_online<R: Runtime>(app: AppHandle<R>) {
+    while !is_online() {
+        sleep(PROBE_INTERVAL).await;
+        if reachable(&app).await {
+            set_online(&app, true);
+        }
+    }
+}
+
+/// Checks the connection after a request failed without an HTTP response,
+/// so a failure that isn't about the network doesn't count.
+pub async fn check_connection<R: Runtime>(app: &AppHandle<R>) {
+    if is_online() && !reachable(app).await {
+        set_online(app, false);
+    }
+}
+
+/// Whether the account `entry` was made with is still signed in. Changes
+/// queued before accounts were recorded have none and always are.
+fn is_known(entry: &PendingOperation, accounts: &AccountList) -> bool {
+    entry
+        .account
+        .as_ref()
+        .is_none_or(|id| accounts.accounts.iter().any(|a| a.id == *id))
+}
+
+/// The changes of `pending` that can be sent as the active account.
+fn sendable(pending: Vec<PendingOperation>, accounts: &AccountList) -> Vec<PendingOperation> {
+    pending
+        .into_iter()
+        .filter(|p| is_known(p, accounts))
+        .filter(|p| p.account.is_none() || p.account == accounts.active_account)
+        .collect()
+}
+
+/// The queued changes that can be sent now, those of the active account.
+/// Changes of accounts that were removed are dropped, as they can't be sent
+/// as anyone else.
+async fn replayable<R: Runtime>(app: &AppHandle<R>) -> Vec<PendingOperation> {
+    let accounts = SPOTIFY.account_list().await;
+    if pending(app).iter().any(|p| !is_known(p, &accounts)) {
+        with_outbox(app, |pending| pending.retain(|p| is_known(p, &accounts)));
+        eprintln!("Dropped queued changes of removed Spotify accounts");
+        emit_status(app);
+    }
+    sendable(pending(app), &accounts)
+}
+
+fn enqueue<R: Runtime>(app: &AppHandle<R>, account: Option<String>, operation: Operation) {
+    let entry = PendingOperation {
+        id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+        operation,
+        account,
+        queued_at: chrono::Utc::now().timestamp_millis(),
+        attempts: 0,
+    };
+    with_outbox(app, |pending| pending.push(entry));
+    emit_status(app);
+}
+
+/// Applies `operation` now, or queues it while offline. Operations queue
+/// behind older ones so they reach Spotify in order, even while online.
+pub async fn apply_or_queue<R: Runtime>(
+    app: &AppHandle<R>,
+    operation: Operation,
+) -> Result<Outcome, String> {
+    let account = SPOTIFY.account_list().await.active_account;
+    if !is_online() || !replayable(app).await.is_empty() {
+        enqueue(app, account, operation);
+        replay_soon(app);
+        return Ok(Outcome::Queued);
+    }
+    match operation.apply(app).await {
+        Ok(()) => Ok(Outcome::Applied),
+        Err(_) if !is_online() => {
+            enqueue(app, account, operation);
+            Ok(Outcome::Queued)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Starts sending the outbox unless that is already under way, e.g. after
+/// switching to an account with queued changes.
+pub fn replay_soon<R: Runtime>(app: &AppHandle<R>) {
+    if is_online() && !REPLAYING.swap(true, Ordering::SeqCst) {
+        let app = app.clone();
+        rt::spawn(async move {
+            let mut failures = 0;
+            loop {
+                if let Replayed::Failed = replay(&app).await {
+                    let delay = REPLAY_RETRY_SECS[failures.min(REPLAY_RETRY_SECS.len() - 1)];
+                    failures += 1;
+                    sleep(Duration::from_secs(delay)).await;
+                    continue;
+                }
+                REPLAYING.store(false, Ordering::SeqCst);
+                // Something may have been queued just as the replay finished.
+                if !is_online()
+                    || replayable(&app).await.is_empty()
+                    || REPLAYING.swap(true, Ordering::SeqCst)
+                {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Whether Spotify refused the change itself, so sending it again won't
+/// help: any 4xx but an expired token, a timeout or a rate limit. Server
+/// errors and failures without a response may pass.
+fn is_conflict(error: &SpotifyError) -> bool {
+    match error {
+        SpotifyError::Status(status, _) => {
+            status.is_client_error()
+                && !matches!(
+                    *status,
+                    StatusCode::UNAUTHORIZED
+                        | StatusCode::REQUEST_TIMEOUT
+                        | StatusCode::TOO_MANY_REQUESTS
+                )
+        }
+        _ => false,
+    }
+}
+
+enum Replayed {
+    All,
+    Offline,
+    /// A change failed in a way that may pass; it is still first in line.
+    Failed,
+}
+
+/// Sends the queued operations in order. Stops when the connection drops
+/// again or a change fails for a reason that may pass; operations Spotify
+/// rejects or that failed too often become conflicts.
+async fn replay<R: Runtime>(app: &AppHandle<R>) -> Replayed {
+    while is_online() {
+        let Some(next) = replayable(app).await.into_iter().next() else {
+            return Replayed::All;
+        };
+        let rejected = match next.operation.apply(app).await {
+            Ok(()) => None,
+            Err(_) if !is_online() => return Replayed::Offline,
+            Err(error) if is_conflict(&error) => Some(error),
+            Err(error) if next.attempts + 1 < MAX_REPLAY_ATTEMPTS => {
+                eprintln!("Queued change failed, retrying later: {}", error);
+                with_outbox(app, |pending| {
+                    if let Some(entry) = pending.iter_mut().find(|p| p.id == next.id) {
+                        entry.attempts += 1;
+                    }
+                });
+                return Replayed::Failed;
+            }
+            Err(error) => Some(error),
+        };
+        with_outbox(app, |pending| pending.retain(|p| p.id != next.id));
+        if let Some(error) = rejected {
+            eprintln!("Queued change rejected: {}", error);
+            if let Ok(mut conflicts) = CONFLICTS.lock() {
+                conflicts.push(Conflict {
+                    operation: next,
+                    error: error.to_string(),
+                });
+            }
+        }
+        emit_status(app);
+    }
+    Replayed::Offline
+}
+
+/// Replays what was left in the outbox when the app last quit.
+pub fn init_offline(app: &AppHandle) {
+    if !pending(app).is_empty() {
+        replay_soon(app);
+    }
+}
+
+/// Forgets the outbox and its conflicts, for signing out and "clear
+/// everything".
+pub fn clear_outbox<R: Runtime>(app: &AppHandle<R>) -> bool {
+    if let Ok(mut guard) = OUTBOX.lock() {
+        *guard = Some(Vec::new());
+    }
+    if let Ok(mut conflicts) = CONFLICTS.lock() {
+        conflicts.clear();
+    }
+    emit_status(app);
+    let path = outbox_path(app);
+    !path.exists() || fs::remove_file(&path).is_ok()
+}
+
+#[tauri::command]
+pub fn get_offline_status(app: AppHandle) -> OfflineStatus {
+    status(&app)
+}
+
+#[tauri::command]
+pub fn dismiss_outbox_conflicts(app: AppHandle) {
+    if let Ok(mut conflicts) = CONFLICTS.lock() {
+        conflicts.clear();
+    }
+    emit_status(&app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oauth::Account;
+
+    fn status(code: u16) -> SpotifyError {
+        SpotifyError::Status(StatusCode::from_u16(code).unwrap(), String::new())
+    }
+
+    fn entry(id: u64, account: Option<&str>) -> PendingOperation {
+        PendingOperation {
+            id,
+            operation: Operation::SaveTracks { ids: Vec::new() },
+            account: account.map(str::to_string),
+            queued_at: 0,
+            attempts: 0,
+        }
+    }
+
+    fn accounts(ids: &[&str], active: Option<&str>) -> AccountList {
+        AccountList {
+            accounts: ids
+                .iter()
+                .map(|id| Account {
+                    id: id.to_string(),
+                    display_name: id.to_string(),
+                    avatar_url: None,
+                })
+                .collect(),
+            active_account: active.map(str::to_string),
+        }
+    }
+
+    fn ids(pending: Vec<PendingOperation>) -> Vec<u64> {
+        pending.into_iter().map(|p| p.id).collect()
+    }
+
+    #[test]
+    fn refused_changes_are_conflicts() {
+        for code in [400, 403, 404, 409, 410, 422] {
+            assert!(is_conflict(&status(code)), "{}", code);
+        }
+    }
+
+    #[test]
+    fn changes_that_may_pass_are_retried() {
+        for code in [401, 408, 429, 500, 502, 503] {
+            assert!(!is_conflict(&status(code)), "{}", code);
+        }
+        assert!(!is_conflict(&SpotifyError::Network(String::new())));
+        assert!(!is_conflict(&SpotifyError::RateLimited(
+            Duration::from_secs(60)
+        )));
+        assert!(!is_conflict(&SpotifyError::Auth(String::new())));
+    }
+
+    #[test]
+    fn sends_only_the_changes_of_the_active_account() {
+        let pending = vec![entry(1, Some("a")), entry(2, Some("b")), entry(3, None)];
+        let signed_in = accounts(&["a", "b"], Some("a"));
+        assert_eq!(ids(sendable(pending.clone(), &signed_in)), vec![1, 3]);
+
+        let switched = accounts(&["a", "b"], Some("b"));
+        assert_eq!(ids(sendable(pending.clone(), &switched)), vec![2, 3]);
+
+        let signed_out = accounts(&["a", "b"], None);
+        assert_eq!(ids(sendable(pending, &signed_out)), vec![3]);
+    }
+
+    #[test]
+    fn drops_the_changes_of_removed_accounts() {
+        let signed_in = accounts(&["a"], Some("a"));
+        assert!(is_known(&entry(1, Some("a")), &signed_in));
+        assert!(is_known(&entry(2, None), &signed_in));
+        assert!(!is_known(&entry(3, Some("b")), &signed_in));
+
+        let pending = vec![entry(1, Some("a")), entry(3, Some("b"))];
+        assert_eq!(
+            ids(sendable(pending, &accounts(&[], Some("b")))),
+            Vec::<u64>::new()
+        );
+    }
+}
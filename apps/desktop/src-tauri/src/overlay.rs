@@ -0,0 +1,153 @@
+use crate::custom_themes::{self, CustomTheme};
+use crate::player::{PlaybackSnapshot, PlayerState};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use futures_util::stream::{self, Stream};
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::Infallible;
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+const OVERLAY_PAGE: &str = include_str!("overlay.html");
+/// The theme lives in settings.json, so open overlays check it periodically.
+const THEME_POLL_INTERVAL_SECS: u64 = 2;
+
+/// CSS custom properties of the overlay page, filled from the theme.
+fn theme_variables(theme: &CustomTheme) -> BTreeMap<&'static str, String> {
+    let mut variables = BTreeMap::new();
+    let mut set = |name: &'static str, value: Option<String>| {
+        if let Some(value) = value {
+            variables.insert(name, value);
+        }
+    };
+
+    if let Some(panel) = &theme.panel {
+        set("--panel-bg", panel.background.clone());
+        set("--panel-radius", panel.border_radius.map(|r| format!("{r}px")));
+        set("--panel-shadow", panel.shadow.clone());
+    }
+    if let Some(typography) = &theme.typography {
+        if let Some(title) = &typography.song_title {
+            set("--title-color", title.color.clone());
+            set("--title-weight", title.weight.map(|w| w.to_string()));
+        }
+        if let Some(artist) = &typography.song_artist {
+            set("--artist-color", artist.color.clone());
+            set("--artist-weight", artist.weight.map(|w| w.to_string()));
+        }
+    }
+    if let Some(playbar) = &theme.playbar {
+        set("--track-bg", playbar.track_bg.clone());
+        set("--track-fill", playbar.track_fill.clone());
+        set("--time-color", playbar.time_text_color.clone());
+    }
+    if let Some(cover) = &theme.cover {
+        set("--cover-radius", cover.border_radius.map(|r| format!("{r}px")));
+        set("--cover-border", cover.border_color.clone());
+    }
+    variables
+}
+
+fn status_event(snapshot: &PlaybackSnapshot) -> Event {
+    Event::default()
+        .event("status")
+        .json_data(snapshot.status())
+        .unwrap_or_default()
+}
+
+/// Variables of the active theme, empty without one.
+fn active_theme_variables(app: &AppHandle) -> BTreeMap<&'static str, String> {
+    custom_themes::active_theme(app)
+        .as_ref()
+        .map(theme_variables)
+        .unwrap_or_default()
+}
+
+fn theme_event(variables: &BTreeMap<&'static str, String>) -> Event {
+    Event::default()
+        .event("theme")
+        .json_data(variables)
+        .unwrap_or_default()
+}
+
+struct OverlayEvents {
+    app: AppHandle,
+    playback: watch::Receiver<PlaybackSnapshot>,
+    shutdown: watch::Receiver<bool>,
+    theme_check: Interval,
+    /// Last sent. Compared in full, so edits to the active theme reach open
+    /// overlays, not just switching themes.
+    theme_variables: BTreeMap<&'static str, String>,
+    pending: VecDeque<Event>,
+}
+
+impl OverlayEvents {
+    fn new(app: AppHandle, shutdown: watch::Receiver<bool>) -> Self {
+        let mut playback = app.state::<PlayerState>().subscribe();
+        let snapshot = playback.borrow_and_update().clone();
+        let theme_variables = active_theme_variables(&app);
+        let mut theme_check = interval(std::time::Duration::from_secs(THEME_POLL_INTERVAL_SECS));
+        theme_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Self {
+            app,
+            playback,
+            shutdown,
+            theme_check,
+            pending: VecDeque::from([theme_event(&theme_variables), status_event(&snapshot)]),
+            theme_variables,
+        }
+    }
+
+    fn check_theme(&mut self) {
+        let variables = active_theme_variables(&self.app);
+        if variables != self.theme_variables {
+            self.pending.push_back(theme_event(&variables));
+            self.theme_variables = variables;
+        }
+    }
+
+    async fn next(&mut self) -> Option<Event> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            tokio::select! {
+                changed = self.playback.changed() => {
+                    changed.ok()?;
+                    let snapshot = self.playback.borrow_and_update().clone();
+                    self.pending.push_back(status_event(&snapshot));
+                }
+                _ = self.theme_check.tick() => self.check_theme(),
+                _ = self.shutdown.changed() => return None,
+            }
+        }
+    }
+}
+
+fn event_stream(
+    app: AppHandle,
+    shutdown: watch::Receiver<bool>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(OverlayEvents::new(app, shutdown), |mut events| async move {
+        let event = events.next().await?;
+        Some((Ok(event), events))
+    })
+}
+
+/// Now-playing page for OBS browser sources, served by the control API.
+/// `?variant=card|bar|minimal` picks the layout.
+pub fn router(app: AppHandle, shutdown: watch::Receiver<bool>) -> Router {
+    Router::new()
+        .route("/overlay", get(|| async { Html(OVERLAY_PAGE) }))
+        .route(
+            "/overlay/events",
+            get(move || {
+                let events = event_stream(app.clone(), shutdown.clone());
+                async move { Sse::new(events).keep_alive(KeepAlive::default()) }
+            }),
+        )
+}
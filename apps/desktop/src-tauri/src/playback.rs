@@ -0,0 +1,256 @@
+//! The one source of playback state. A background task polls Spotify and
+//! records what it sees in `PlayerState`; YouTube plays inside the webview,
+//! which reports its state instead. Every change is turned into events for
+//! the webview, so neither it nor MPRIS, Discord or the overlays poll a
+//! provider themselves.
+
+use crate::devices;
+use crate::player::{PlaybackSnapshot, PlayerState};
+use crate::settings::{
+    self, CachedTrack, CachedTrackAlbum, CachedTrackAlbumImage, CachedTrackArtist,
+};
+use crate::spotify_api::{self, Priority};
+use crate::tray::MAIN_WINDOW_LABEL;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{async_runtime as rt, AppHandle, Emitter, Manager, State};
+use tokio::sync::{watch, Notify};
+use tokio::time::{interval, sleep, MissedTickBehavior};
+
+/// A different track, or none, is playing. Payload: `PlaybackSnapshot`.
+pub const TRACK_CHANGED_EVENT: &str = "track-changed";
+/// Playback was paused, resumed or seeked. Payload: `PlaybackSnapshot`.
+pub const PLAYBACK_STATE_CHANGED_EVENT: &str = "playback-state-changed";
+/// The interpolated position while playing. Payload: `ProgressUpdate`.
+pub const PROGRESS_EVENT: &str = "progress";
+
+const PLAYING_INTERVAL: Duration = Duration::from_secs(5);
+const PAUSED_INTERVAL: Duration = Duration::from_secs(15);
+const IDLE_INTERVAL: Duration = Duration::from_secs(20);
+const ERROR_INTERVAL: Duration = Duration::from_secs(15);
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+/// Intervals are this many times longer while the window is hidden.
+const HIDDEN_BACKOFF: u32 = 3;
+/// How long after the expected end of a track to look for the next one.
+const BOUNDARY_GRACE: Duration = Duration::from_millis(500);
+/// Spotify needs a moment before a command shows up in the playback state.
+const COMMAND_SETTLE: Duration = Duration::from_millis(400);
+const PROGRESS_TICK: Duration = Duration::from_secs(1);
+/// A position further than this from the interpolated one counts as a seek.
+const SEEK_TOLERANCE_MS: u64 = 2000;
+
+lazy_static::lazy_static! {
+    static ref POLL_NOW: Notify = Notify::new();
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressUpdate {
+    pub track_id: Option<String>,
+    pub progress_ms: u64,
+    pub duration_ms: Option<u64>,
+}
+
+/// Polls again shortly, e.g. after a player command changed the state.
+pub fn poll_soon() {
+    POLL_NOW.notify_one();
+}
+
+fn cached_track(track: &spotify_api::Track) -> CachedTrack {
+    let id = track.id.clone().unwrap_or_default();
+    CachedTrack {
+        uri: track
+            .uri
+            .clone()
+            .unwrap_or_else(|| format!("spotify:track:{}", id)),
+        id,
+        name: track.name.clone(),
+        duration_ms: track.duration_ms,
+        artists: track
+            .artists
+            .iter()
+            .map(|artist| CachedTrackArtist {
+                id: artist.id.clone().unwrap_or_default(),
+                name: artist.name.clone(),
+            })
+            .collect(),
+        album: CachedTrackAlbum {
+            id: track.album.id.clone().unwrap_or_default(),
+            name: track.album.name.clone(),
+            images: track
+                .album
+                .images
+                .iter()
+                .map(|image| CachedTrackAlbumImage {
+                    url: image.url.clone(),
+                    height: image.height.unwrap_or(0),
+                    width: image.width.unwrap_or(0),
+                })
+                .collect(),
+        },
+        provider: "spotify".to_string(),
+        isrc: track
+            .external_ids
+            .as_ref()
+            .and_then(|ids| ids.get("isrc"))
+            .and_then(|isrc| isrc.as_str())
+            .map(str::to_string),
+    }
+}
+
+fn is_window_hidden(app: &AppHandle) -> bool {
+    app.get_webview_window(MAIN_WINDOW_LABEL)
+        .map(|window| {
+            !window.is_visible().unwrap_or(true) || window.is_minimized().unwrap_or(false)
+        })
+        .unwrap_or(true)
+}
+
+/// Polls often enough to notice the end of a track right away, and rarely
+/// when nothing is going to change on its own.
+fn next_poll_delay(snapshot: &PlaybackSnapshot, hidden: bool) -> Duration {
+    let backoff = if hidden { HIDDEN_BACKOFF } else { 1 };
+    match &snapshot.track {
+        Some(track) if snapshot.is_playing => {
+            let remaining = track
+                .duration_ms
+                .saturating_sub(snapshot.current_progress_ms());
+            let until_boundary = Duration::from_millis(remaining) + BOUNDARY_GRACE;
+            until_boundary.clamp(MIN_INTERVAL, PLAYING_INTERVAL * backoff)
+        }
+        Some(_) => PAUSED_INTERVAL * backoff,
+        None => IDLE_INTERVAL * backoff,
+    }
+}
+
+pub fn is_spotify_active(app: &AppHandle) -> bool {
+    settings::read_settings(app.clone())
+        .active_music_provider
+        .is_none_or(|provider| provider == "spotify")
+}
+
+async fn poll_spotify(app: &AppHandle, state: &PlayerState) -> Result<(), String> {
+    let playback = spotify_api::playback_state(app, Priority::Background).await?;
+    devices::observe(app, playback.as_ref().map(|playback| &playback.device));
+    match playback {
+        Some(playback) => state.report(
+            playback.item.as_ref().map(cached_track),
+            playback.is_playing,
+            playback.progress_ms.unwrap_or(0),
+            playback.device.volume_percent,
+        ),
+        None => state.report(None, false, 0, None),
+    }
+    Ok(())
+}
+
+async fn run_poller(app: AppHandle) {
+    let state = app.state::<PlayerState>();
+    let mut last_error: Option<String> = None;
+    loop {
+        let delay = if is_spotify_active(&app) {
+            match poll_spotify(&app, &state).await {
+                Ok(()) => {
+                    last_error = None;
+                    next_poll_delay(&state.snapshot(), is_window_hidden(&app))
+                }
+                Err(e) => {
+                    // Signed out or offline; say so once, not on every poll.
+                    if last_error.as_ref() != Some(&e) {
+                        eprintln!("Playback poll failed: {}", e);
+                        last_error = Some(e);
+                    }
+                    ERROR_INTERVAL
+                }
+            }
+        } else {
+            IDLE_INTERVAL
+        };
+
+        tokio::select! {
+            _ = sleep(delay) => {}
+            _ = POLL_NOW.notified() => sleep(COMMAND_SETTLE).await,
+        }
+    }
+}
+
+fn track_id(snapshot: &PlaybackSnapshot) -> Option<&str> {
+    snapshot.track.as_ref().map(|track| track.id.as_str())
+}
+
+/// The snapshot with the position it has now, for event payloads.
+fn current(snapshot: &PlaybackSnapshot) -> PlaybackSnapshot {
+    PlaybackSnapshot {
+        progress_ms: snapshot.current_progress_ms(),
+        ..snapshot.clone()
+    }
+}
+
+fn progress_update(snapshot: &PlaybackSnapshot) -> ProgressUpdate {
+    ProgressUpdate {
+        track_id: track_id(snapshot).map(str::to_string),
+        progress_ms: snapshot.current_progress_ms(),
+        duration_ms: snapshot.track.as_ref().map(|track| track.duration_ms),
+    }
+}
+
+async fn emit_changes(app: AppHandle, mut receiver: watch::Receiver<PlaybackSnapshot>) {
+    let mut previous = receiver.borrow_and_update().clone();
+    let mut ticker = interval(PROGRESS_TICK);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        tokio::select! {
+            changed = receiver.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let snapshot = receiver.borrow_and_update().clone();
+                if track_id(&snapshot) != track_id(&previous) {
+                    let _ = app.emit(TRACK_CHANGED_EVENT, current(&snapshot));
+                } else {
+                    let expected = previous.current_progress_ms();
+                    let seeked = snapshot.progress_ms.abs_diff(expected) > SEEK_TOLERANCE_MS;
+                    if snapshot.is_playing != previous.is_playing || seeked {
+                        let _ = app.emit(PLAYBACK_STATE_CHANGED_EVENT, current(&snapshot));
+                    }
+                }
+                previous = snapshot;
+            }
+            _ = ticker.tick() => {
+                if previous.is_playing && previous.track.is_some() {
+                    let _ = app.emit(PROGRESS_EVENT, progress_update(&previous));
+                }
+            }
+        }
+    }
+}
+
+/// Current playback with the interpolated position, for views that start
+/// listening to the events late.
+#[tauri::command]
+pub fn get_playback_snapshot(state: State<PlayerState>) -> PlaybackSnapshot {
+    current(&state.snapshot())
+}
+
+/// Shows the last played track until the first poll, and while offline.
+fn restore_last_played(app: &AppHandle) {
+    let settings = settings::read_settings(app.clone());
+    let active = settings
+        .active_music_provider
+        .as_deref()
+        .unwrap_or("spotify");
+    if let Some(last) = settings
+        .last_played_track
+        .filter(|last| last.track.provider == active)
+    {
+        app.state::<PlayerState>()
+            .report(Some(last.track), false, last.progress_ms, None);
+    }
+}
+
+pub fn init_playback(app: &AppHandle) {
+    restore_last_played(app);
+    let receiver = app.state::<PlayerState>().subscribe();
+    rt::spawn(emit_changes(app.clone(), receiver));
+    rt::spawn(run_poller(app.clone()));
+}
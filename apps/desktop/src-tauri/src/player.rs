@@ -0,0 +1,251 @@
+use crate::settings::CachedTrack;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::watch;
+
+/// Playback is controlled through the frontend's music provider (YouTube's
+/// player lives in the webview), so native integrations never talk to a
+/// provider directly. They emit a `PlayerCommand` on this event and the frontend executes it with
+/// whatever music provider is active.
+pub const PLAYER_COMMAND_EVENT: &str = "player-command";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlayerCommand {
+    PlayPause,
+    Play,
+    Pause,
+    Next,
+    Previous,
+    Seek { position_ms: u64 },
+    SetVolume { volume: u8 },
+    LikeTrack,
+    SetLayout { layout: String },
+    SetTheme { theme: String },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlaybackSnapshot {
+    pub track: Option<CachedTrack>,
+    pub is_playing: bool,
+    /// Position when the state was observed; see `current_progress_ms`.
+    pub progress_ms: u64,
+    /// Last volume reported by the device or set through a `PlayerCommand`.
+    pub volume_percent: Option<u8>,
+    #[serde(skip)]
+    pub observed_at: Option<Instant>,
+}
+
+/// Flat view of a snapshot for scripts, status bars and overlays.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerStatus {
+    /// `playing`, `paused` or `stopped`.
+    pub status: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub art_url: Option<String>,
+    pub provider: Option<String>,
+    pub id: Option<String>,
+    pub position_ms: u64,
+    pub duration_ms: Option<u64>,
+    pub volume: Option<u8>,
+}
+
+fn format_time(ms: u64) -> String {
+    let seconds = ms / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl PlayerStatus {
+    /// Fills `{title}`, `{artist}`, `{album}`, `{status}`, `{position}`,
+    /// `{duration}`, `{volume}`, `{provider}` and `{id}` in `template`.
+    /// Placeholders without a value are replaced by an empty string.
+    pub fn format(&self, template: &str) -> String {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        [
+            ("{title}", text(&self.title)),
+            ("{artist}", text(&self.artist)),
+            ("{album}", text(&self.album)),
+            ("{status}", self.status.clone()),
+            ("{position}", format_time(self.position_ms)),
+            (
+                "{duration}",
+                self.duration_ms.map(format_time).unwrap_or_default(),
+            ),
+            (
+                "{volume}",
+                self.volume.map(|v| v.to_string()).unwrap_or_default(),
+            ),
+            ("{provider}", text(&self.provider)),
+            ("{id}", text(&self.id)),
+        ]
+        .iter()
+        .fold(template.to_string(), |output, (placeholder, value)| {
+            output.replace(placeholder, value)
+        })
+    }
+}
+
+impl PlaybackSnapshot {
+    /// Position now: the observed one plus the time played since, up to the
+    /// end of the track.
+    pub fn current_progress_ms(&self) -> u64 {
+        let played = match (self.is_playing, self.observed_at) {
+            (true, Some(at)) => at.elapsed().as_millis() as u64,
+            _ => 0,
+        };
+        let progress = self.progress_ms + played;
+        match &self.track {
+            Some(track) => progress.min(track.duration_ms),
+            None => progress,
+        }
+    }
+
+    pub fn playback_status(&self) -> &'static str {
+        match (&self.track, self.is_playing) {
+            (None, _) => "stopped",
+            (Some(_), true) => "playing",
+            (Some(_), false) => "paused",
+        }
+    }
+
+    /// Largest album image of the current track.
+    pub fn art_url(&self) -> Option<String> {
+        self.track.as_ref().and_then(|track| {
+            track
+                .album
+                .images
+                .iter()
+                .max_by_key(|image| image.width)
+                .map(|image| image.url.clone())
+        })
+    }
+
+    pub fn status(&self) -> PlayerStatus {
+        let track = self.track.as_ref();
+        PlayerStatus {
+            status: self.playback_status().to_string(),
+            title: track.map(|track| track.name.clone()),
+            artist: self.artist_names(),
+            album: track.map(|track| track.album.name.clone()),
+            art_url: self.art_url(),
+            provider: track.map(|track| track.provider.clone()),
+            id: track.map(|track| track.id.clone()),
+            position_ms: self.current_progress_ms(),
+            duration_ms: track.map(|track| track.duration_ms),
+            volume: self.volume_percent,
+        }
+    }
+
+    pub fn artist_names(&self) -> Option<String> {
+        self.track.as_ref().map(|track| {
+            track
+                .artists
+                .iter()
+                .map(|artist| artist.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueuedTrack {
+    pub name: String,
+    pub artists: String,
+    pub uri: String,
+}
+
+/// State of the AI DJ queue, which lives in the frontend store.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AiQueueSnapshot {
+    pub is_active: bool,
+    pub is_loading: bool,
+    pub queue: Vec<QueuedTrack>,
+    pub current_index: usize,
+    pub error: Option<String>,
+}
+
+/// Last observed playback state: polled by `playback` for Spotify, reported by
+/// the webview for YouTube. Native integrations (tray, media keys, ...)
+/// subscribe to it instead of polling the provider themselves.
+pub struct PlayerState {
+    sender: watch::Sender<PlaybackSnapshot>,
+    ai_queue: watch::Sender<AiQueueSnapshot>,
+}
+
+impl PlayerState {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(PlaybackSnapshot::default());
+        let (ai_queue, _) = watch::channel(AiQueueSnapshot::default());
+        Self { sender, ai_queue }
+    }
+
+    pub fn snapshot(&self) -> PlaybackSnapshot {
+        self.sender.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<PlaybackSnapshot> {
+        self.sender.subscribe()
+    }
+
+    /// Records an observation of the provider's playback. A `None` volume
+    /// keeps the last known one.
+    pub fn report(
+        &self,
+        track: Option<CachedTrack>,
+        is_playing: bool,
+        progress_ms: u64,
+        volume_percent: Option<u8>,
+    ) {
+        self.sender.send_modify(|snapshot| {
+            snapshot.track = track;
+            snapshot.is_playing = is_playing;
+            snapshot.progress_ms = progress_ms;
+            snapshot.volume_percent = volume_percent.or(snapshot.volume_percent);
+            snapshot.observed_at = Some(Instant::now());
+        });
+    }
+
+    pub fn ai_queue(&self) -> AiQueueSnapshot {
+        self.ai_queue.borrow().clone()
+    }
+
+    pub fn subscribe_ai_queue(&self) -> watch::Receiver<AiQueueSnapshot> {
+        self.ai_queue.subscribe()
+    }
+}
+
+impl Default for PlayerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn send_command(app: &AppHandle, command: PlayerCommand) {
+    if let PlayerCommand::SetVolume { volume } = command {
+        if let Some(state) = app.try_state::<PlayerState>() {
+            state
+                .sender
+                .send_modify(|snapshot| snapshot.volume_percent = Some(volume));
+        }
+    }
+    let _ = app.emit(PLAYER_COMMAND_EVENT, command);
+}
+
+#[tauri::command]
+pub fn report_playback_state(
+    state: State<PlayerState>,
+    track: Option<CachedTrack>,
+    is_playing: bool,
+    progress_ms: u64,
+) {
+    state.report(track, is_playing, progress_ms, None);
+}
+
+#[tauri::command]
+pub fn report_ai_queue(state: State<PlayerState>, queue: AiQueueSnapshot) {
+    state.ai_queue.send_replace(queue);
+}
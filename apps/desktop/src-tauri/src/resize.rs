@@ -12,6 +12,7 @@ pub enum Layout {
     SearchSongs,
     AIDJ,
     Volume,
+    Lyrics,
 }
 
 #[tauri::command]
@@ -27,6 +28,7 @@ pub fn set_layout(window: WebviewWindow, layout: Layout) {
         Layout::SearchSongs => LogicalSize { width: 400.0, height: 600.0 },
         Layout::AIDJ => LogicalSize { width: 400.0, height: 600.0 },
         Layout::Volume => LogicalSize { width: 300.0, height: 280.0 },
+        Layout::Lyrics => LogicalSize { width: 400.0, height: 320.0 },
     };
 
     window.set_size(Size::Logical(size)).unwrap();
@@ -0,0 +1,68 @@
+//! Resume on launch. With `resume_on_launch` set, the last played Spotify
+//! track starts again where it was left, a few seconds earlier so the
+//! listener finds their place. Nothing happens when the track is stale or
+//! something is already playing.
+
+use crate::devices;
+use crate::playback;
+use crate::settings;
+use crate::spotify_api::{self, PlayRequest, Priority};
+use tauri::{async_runtime as rt, AppHandle};
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+
+async fn resume_last_played(app: &AppHandle) -> Result<(), String> {
+    let settings = settings::read_settings(app.clone());
+    if !settings.resume_on_launch {
+        return Ok(());
+    }
+    let Some(last) = settings.last_played_track else {
+        return Ok(());
+    };
+    if last.track.provider != "spotify" || !playback::is_spotify_active(app) {
+        return Ok(());
+    }
+    let age_ms = chrono::Utc::now().timestamp_millis() - last.cached_at;
+    let max_age_ms = i64::try_from(settings.resume_max_age_hours)
+        .unwrap_or(i64::MAX)
+        .saturating_mul(HOUR_MS);
+    if age_ms > max_age_ms {
+        eprintln!("Not resuming {}, last played too long ago", last.track.name);
+        return Ok(());
+    }
+
+    if spotify_api::playback_state(app, Priority::Interactive)
+        .await?
+        .is_some_and(|playback| playback.is_playing)
+    {
+        eprintln!("Not resuming, something else is playing");
+        return Ok(());
+    }
+    if !devices::ensure_active(app).await? {
+        return Err("No Spotify device to play on".to_string());
+    }
+
+    let rewind_ms = settings.resume_rewind_secs.saturating_mul(1000);
+    let position_ms = match last.progress_ms.saturating_sub(rewind_ms) {
+        // The track had ended; play it from the start.
+        position if position >= last.track.duration_ms => 0,
+        position => position,
+    };
+    let request = PlayRequest {
+        uris: Some(vec![last.track.uri]),
+        position_ms: Some(position_ms),
+        ..Default::default()
+    };
+    spotify_api::play(app, &request, None)
+        .await
+        .map_err(String::from)
+}
+
+pub fn init_resume(app: &AppHandle) {
+    let app = app.clone();
+    rt::spawn(async move {
+        if let Err(e) = resume_last_played(&app).await {
+            eprintln!("Failed to resume playback: {}", e);
+        }
+    });
+}
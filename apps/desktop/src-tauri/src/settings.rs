@@ -1,9 +1,24 @@
 ﻿use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::sync::watch;
 
-fn get_settings_path(app: &AppHandle) -> PathBuf {
+lazy_static::lazy_static! {
+    static ref CHANGES: watch::Sender<u64> = watch::channel(0).0;
+}
+
+/// Changes after every write or clear of the settings file, so background
+/// tasks can keep a copy instead of reading the file again and again.
+pub fn subscribe_changes() -> watch::Receiver<u64> {
+    CHANGES.subscribe()
+}
+
+fn notify_changed() {
+    CHANGES.send_modify(|generation| *generation += 1);
+}
+
+fn get_settings_path<R: Runtime>(app: &AppHandle<R>) -> PathBuf {
     let mut path = app.path().app_data_dir().unwrap_or_else(|_| PathBuf::from("."));
     fs::create_dir_all(&path).ok();
     path.push("settings.json");
@@ -39,6 +54,8 @@ pub struct CachedTrack {
     pub album: CachedTrackAlbum,
     pub uri: String,
     pub provider: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isrc: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -73,6 +90,56 @@ pub struct Settings {
     pub music_visualizer_intensity: u8,
     #[serde(default)]
     pub last_played_track: Option<LastPlayedTrack>,
+    #[serde(default)]
+    pub api_server_enabled: bool,
+    #[serde(default = "default_api_server_port")]
+    pub api_server_port: u16,
+    /// Listen on all interfaces instead of loopback only.
+    #[serde(default)]
+    pub api_server_allow_lan: bool,
+    #[serde(default)]
+    pub now_playing_files_enabled: bool,
+    #[serde(default)]
+    pub now_playing_text_path: Option<String>,
+    #[serde(default = "default_now_playing_template")]
+    pub now_playing_template: String,
+    #[serde(default)]
+    pub now_playing_json_path: Option<String>,
+    #[serde(default)]
+    pub now_playing_cover_path: Option<String>,
+    /// Local ports for the Spotify login callback, tried in order.
+    #[serde(default = "default_oauth_callback_ports")]
+    pub oauth_callback_ports: Vec<u16>,
+    /// Longest wait for a connection to a service.
+    #[serde(default = "default_http_connect_timeout_secs")]
+    pub http_connect_timeout_secs: u64,
+    /// Longest wait for the next chunk of a response.
+    #[serde(default = "default_http_read_timeout_secs")]
+    pub http_read_timeout_secs: u64,
+    /// HTTP or SOCKS5 proxy URL for all requests made by the backend.
+    #[serde(default)]
+    pub http_proxy: Option<String>,
+    /// PEM file with an extra trusted root certificate.
+    #[serde(default)]
+    pub http_ca_certificate_path: Option<String>,
+    /// Disk space for cached album art, in megabytes.
+    #[serde(default = "default_album_art_cache_mb")]
+    pub album_art_cache_mb: u64,
+    /// Start the last played track again when the app launches.
+    #[serde(default)]
+    pub resume_on_launch: bool,
+    /// How far before the saved position to resume.
+    #[serde(default = "default_resume_rewind_secs")]
+    pub resume_rewind_secs: u64,
+    /// A last played track older than this isn't resumed.
+    #[serde(default = "default_resume_max_age_hours")]
+    pub resume_max_age_hours: u64,
+    /// Folder with `.lrc` files, looked at before any provider.
+    #[serde(default)]
+    pub lyrics_folder: Option<String>,
+    /// Lyrics providers to ask, in order. Empty keeps lyrics offline.
+    #[serde(default = "default_lyrics_providers")]
+    pub lyrics_providers: Vec<String>,
 }
 
 fn default_true() -> bool {
@@ -95,6 +162,42 @@ fn default_visualizer_intensity() -> u8 {
     100
 }
 
+fn default_api_server_port() -> u16 {
+    8975
+}
+
+fn default_now_playing_template() -> String {
+    "{artist} - {title}".to_string()
+}
+
+pub fn default_oauth_callback_ports() -> Vec<u16> {
+    vec![3000, 38917, 48231]
+}
+
+fn default_http_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_http_read_timeout_secs() -> u64 {
+    30
+}
+
+fn default_album_art_cache_mb() -> u64 {
+    200
+}
+
+fn default_resume_rewind_secs() -> u64 {
+    5
+}
+
+fn default_resume_max_age_hours() -> u64 {
+    24
+}
+
+fn default_lyrics_providers() -> Vec<String> {
+    vec!["lrclib".to_string()]
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AIProviderConfig {
     pub provider: String,
@@ -119,12 +222,31 @@ impl Default for Settings {
             music_visualizer_color: "theme".into(),
             music_visualizer_intensity: 100,
             last_played_track: None,
+            api_server_enabled: false,
+            api_server_port: default_api_server_port(),
+            api_server_allow_lan: false,
+            now_playing_files_enabled: false,
+            now_playing_text_path: None,
+            now_playing_template: default_now_playing_template(),
+            now_playing_json_path: None,
+            now_playing_cover_path: None,
+            oauth_callback_ports: default_oauth_callback_ports(),
+            http_connect_timeout_secs: default_http_connect_timeout_secs(),
+            http_read_timeout_secs: default_http_read_timeout_secs(),
+            http_proxy: None,
+            http_ca_certificate_path: None,
+            album_art_cache_mb: default_album_art_cache_mb(),
+            resume_on_launch: false,
+            resume_rewind_secs: default_resume_rewind_secs(),
+            resume_max_age_hours: default_resume_max_age_hours(),
+            lyrics_folder: None,
+            lyrics_providers: default_lyrics_providers(),
         }
     }
 }
 
 #[tauri::command]
-pub fn read_settings(app: AppHandle) -> Settings {
+pub fn read_settings<R: Runtime>(app: AppHandle<R>) -> Settings {
     let path = get_settings_path(&app);
     
     if !path.exists() {
@@ -150,7 +272,10 @@ pub fn write_settings(app: AppHandle, settings: Settings) -> bool {
     };
     
     match fs::write(&path, &json) {
-        Ok(_) => true,
+        Ok(_) => {
+            notify_changed();
+            true
+        }
         Err(e) => {
             eprintln!("Failed to write settings: {}", e);
             false
@@ -162,9 +287,7 @@ pub fn write_settings(app: AppHandle, settings: Settings) -> bool {
 pub fn clear_settings(app: AppHandle) -> bool {
     let path = get_settings_path(&app);
     
-    if path.exists() {
-        fs::remove_file(&path).is_ok()
-    } else {
-        true
-    }
+    let cleared = !path.exists() || fs::remove_file(&path).is_ok();
+    notify_changed();
+    cleared
 }
@@ -0,0 +1,304 @@
+use crate::player::{self, PlayerCommand};
+use crate::tray;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::async_runtime as rt;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+use tokio::time::sleep;
+
+/// Defaults written to the user's keymap on first launch.
+const DEFAULT_KEYMAP: &str = include_str!("../../src/config/shortcuts.json");
+const KEYMAP_POLL_INTERVAL_SECS: u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    PlayPause,
+    Next,
+    Previous,
+    LikeTrack,
+    ToggleWindow,
+    CycleLayout,
+}
+
+impl FromStr for ShortcutAction {
+    type Err = String;
+
+    fn from_str(action: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(action.to_string()))
+            .map_err(|_| format!("Unknown action \"{}\"", action))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BindingStatus {
+    Registered,
+    Invalid,
+    Conflict,
+    Refused,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShortcutBinding {
+    pub action: String,
+    pub accelerator: String,
+    pub status: BindingStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShortcutReport {
+    pub path: String,
+    pub error: Option<String>,
+    pub bindings: Vec<ShortcutBinding>,
+}
+
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    actions: Mutex<HashMap<u32, ShortcutAction>>,
+    report: Mutex<ShortcutReport>,
+}
+
+struct ParsedKeymap {
+    bindings: Vec<ShortcutBinding>,
+    shortcuts: Vec<(usize, Shortcut, ShortcutAction)>,
+}
+
+fn binding(
+    action: &str,
+    accelerator: &str,
+    status: BindingStatus,
+    error: Option<String>,
+) -> ShortcutBinding {
+    ShortcutBinding {
+        action: action.to_string(),
+        accelerator: accelerator.to_string(),
+        status,
+        error,
+    }
+}
+
+/// Validates every entry of an action → accelerator map. Entries that are
+/// invalid or bound to the same key combination as another action are
+/// reported and left out; everything else is ready to be registered.
+fn parse_keymap(content: &str) -> Result<ParsedKeymap, String> {
+    let entries: BTreeMap<String, String> =
+        serde_json::from_str(content).map_err(|e| format!("Invalid keymap JSON: {}", e))?;
+
+    let mut bindings = Vec::new();
+    let mut candidates: Vec<(usize, Shortcut, ShortcutAction)> = Vec::new();
+
+    for (action_name, accelerator) in &entries {
+        let action = match ShortcutAction::from_str(action_name) {
+            Ok(action) => action,
+            Err(e) => {
+                bindings.push(binding(
+                    action_name,
+                    accelerator,
+                    BindingStatus::Invalid,
+                    Some(e),
+                ));
+                continue;
+            }
+        };
+        match Shortcut::from_str(accelerator) {
+            Ok(shortcut) => {
+                candidates.push((bindings.len(), shortcut, action));
+                bindings.push(binding(
+                    action_name,
+                    accelerator,
+                    BindingStatus::Registered,
+                    None,
+                ));
+            }
+            Err(e) => {
+                bindings.push(binding(
+                    action_name,
+                    accelerator,
+                    BindingStatus::Invalid,
+                    Some(e.to_string()),
+                ));
+            }
+        }
+    }
+
+    let mut shortcuts = Vec::new();
+    for (index, shortcut, action) in &candidates {
+        let others: Vec<&str> = candidates
+            .iter()
+            .filter(|(other, other_shortcut, _)| other != index && other_shortcut == shortcut)
+            .map(|(other, _, _)| bindings[*other].action.as_str())
+            .collect();
+        if others.is_empty() {
+            shortcuts.push((*index, *shortcut, *action));
+        } else {
+            let message = format!("Same shortcut as {}", others.join(", "));
+            bindings[*index].status = BindingStatus::Conflict;
+            bindings[*index].error = Some(message);
+        }
+    }
+
+    Ok(ParsedKeymap {
+        bindings,
+        shortcuts,
+    })
+}
+
+fn keymap_path(app: &AppHandle) -> PathBuf {
+    let mut path = app
+        .path()
+        .app_data_dir()
+        .unwrap_or_else(|_| PathBuf::from("."));
+    fs::create_dir_all(&path).ok();
+    path.push("shortcuts.json");
+    path
+}
+
+fn read_keymap(path: &Path) -> Result<String, String> {
+    if !path.exists() {
+        fs::write(path, DEFAULT_KEYMAP).map_err(|e| format!("Failed to create keymap: {}", e))?;
+    }
+    fs::read_to_string(path).map_err(|e| format!("Failed to read keymap: {}", e))
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn apply_keymap(app: &AppHandle) -> ShortcutReport {
+    let path = keymap_path(app);
+    let mut report = ShortcutReport {
+        path: path.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+
+    // A broken file keeps the previous bindings active until it is fixed.
+    let parsed = match read_keymap(&path).and_then(|content| parse_keymap(&content)) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            report.error = Some(e);
+            if let Some(registry) = app.try_state::<ShortcutRegistry>() {
+                if let Ok(previous) = registry.report.lock() {
+                    report.bindings = previous.bindings.clone();
+                }
+            }
+            return report;
+        }
+    };
+
+    let global_shortcut = app.global_shortcut();
+    let _ = global_shortcut.unregister_all();
+
+    let mut bindings = parsed.bindings;
+    let mut actions = HashMap::new();
+    for (index, shortcut, action) in parsed.shortcuts {
+        match global_shortcut.register(shortcut) {
+            Ok(()) => {
+                actions.insert(shortcut.id(), action);
+            }
+            Err(e) => {
+                bindings[index].status = BindingStatus::Refused;
+                bindings[index].error = Some(e.to_string());
+            }
+        }
+    }
+    report.bindings = bindings;
+
+    if let Some(registry) = app.try_state::<ShortcutRegistry>() {
+        if let Ok(mut registered) = registry.actions.lock() {
+            *registered = actions;
+        }
+    }
+    report
+}
+
+fn reload(app: &AppHandle) -> ShortcutReport {
+    let report = apply_keymap(app);
+    for refused in report
+        .bindings
+        .iter()
+        .filter(|b| b.status != BindingStatus::Registered)
+    {
+        eprintln!(
+            "Global shortcut {} ({}) not active: {}",
+            refused.action,
+            refused.accelerator,
+            refused.error.as_deref().unwrap_or("unknown error")
+        );
+    }
+    if let Some(registry) = app.try_state::<ShortcutRegistry>() {
+        if let Ok(mut current) = registry.report.lock() {
+            *current = report.clone();
+        }
+    }
+    let _ = app.emit("shortcuts-changed", &report);
+    report
+}
+
+fn run_action(app: &AppHandle, action: ShortcutAction) {
+    match action {
+        ShortcutAction::PlayPause => player::send_command(app, PlayerCommand::PlayPause),
+        ShortcutAction::Next => player::send_command(app, PlayerCommand::Next),
+        ShortcutAction::Previous => player::send_command(app, PlayerCommand::Previous),
+        ShortcutAction::LikeTrack => player::send_command(app, PlayerCommand::LikeTrack),
+        ShortcutAction::ToggleWindow => tray::toggle_main_window(app),
+        ShortcutAction::CycleLayout => tray::cycle_layout(app),
+    }
+}
+
+/// Handler for the global shortcut plugin; fires on key press only.
+pub fn handle_shortcut(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let Some(registry) = app.try_state::<ShortcutRegistry>() else {
+        return;
+    };
+    let action = match registry.actions.lock() {
+        Ok(actions) => actions.get(&shortcut.id()).copied(),
+        Err(_) => None,
+    };
+    if let Some(action) = action {
+        run_action(app, action);
+    }
+}
+
+#[tauri::command]
+pub fn get_global_shortcuts(registry: State<ShortcutRegistry>) -> ShortcutReport {
+    registry
+        .report
+        .lock()
+        .map(|report| report.clone())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn reload_global_shortcuts(app: AppHandle) -> ShortcutReport {
+    reload(&app)
+}
+
+/// Registers the keymap and re-applies it whenever the file changes on disk.
+pub fn init_shortcuts(app: &AppHandle) {
+    reload(app);
+
+    let app = app.clone();
+    rt::spawn(async move {
+        let path = keymap_path(&app);
+        let mut last_modified = modified_at(&path);
+        loop {
+            sleep(std::time::Duration::from_secs(KEYMAP_POLL_INTERVAL_SECS)).await;
+            let modified = modified_at(&path);
+            if modified != last_modified {
+                last_modified = modified;
+                reload(&app);
+            }
+        }
+    });
+}
@@ -0,0 +1,908 @@
+//! Typed client for the Spotify Web API. Every call, from the backend and
+//! from the webview, goes through one scheduler: it caps the requests in
+//! flight, lets waiting requests in by priority, and holds all of them back
+//! while Spotify's `Retry-After` from a 429 response is running.
+
+use crate::endpoints::SPOTIFY_API;
+use crate::http;
+use crate::offline::{self, Operation, Outcome};
+use crate::playback;
+use crate::spotify_auth::SPOTIFY;
+use reqwest::header::{CONTENT_LENGTH, RETRY_AFTER};
+use reqwest::{Method, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Runtime};
+use tokio::sync::oneshot;
+
+/// Requests sent to Spotify at the same time. Everything else waits in the
+/// queue.
+const MAX_IN_FLIGHT: usize = 4;
+
+/// 429 responses a single request sits out before giving up.
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// A `Retry-After` longer than this fails the request instead of leaving the
+/// caller hanging. Later requests still wait until it has passed.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(30);
+
+/// Used when a 429 response has no usable `Retry-After` header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Order in which waiting requests are sent. Playback controls the user is
+/// waiting on go first; polling and prefetching go last.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Background,
+    #[default]
+    Normal,
+    Interactive,
+}
+
+struct Waiter {
+    priority: Priority,
+    seq: u64,
+    wake: oneshot::Sender<()>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    /// Higher priority first, then first come, first served.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[derive(Default)]
+struct Scheduler {
+    in_flight: usize,
+    next_seq: u64,
+    waiting: BinaryHeap<Waiter>,
+}
+
+lazy_static::lazy_static! {
+    static ref SCHEDULER: Mutex<Scheduler> = Mutex::new(Scheduler::default());
+    /// End of the current rate limit, shared by all requests.
+    static ref RATE_LIMITED_UNTIL: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+/// A slot in the scheduler. Dropping it hands the slot to the next waiter.
+struct Permit;
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let Ok(mut queue) = SCHEDULER.lock() else {
+            return;
+        };
+        while let Some(waiter) = queue.waiting.pop() {
+            // A waiter whose caller has gone away doesn't take the slot.
+            if waiter.wake.send(()).is_ok() {
+                return;
+            }
+        }
+        queue.in_flight = queue.in_flight.saturating_sub(1);
+    }
+}
+
+async fn acquire(priority: Priority) -> Permit {
+    let wake = {
+        let Ok(mut queue) = SCHEDULER.lock() else {
+            return Permit;
+        };
+        if queue.in_flight < MAX_IN_FLIGHT && queue.waiting.is_empty() {
+            queue.in_flight += 1;
+            return Permit;
+        }
+        let (tx, rx) = oneshot::channel();
+        queue.next_seq += 1;
+        let seq = queue.next_seq;
+        queue.waiting.push(Waiter {
+            priority,
+            seq,
+            wake: tx,
+        });
+        rx
+    };
+    // The slot is handed over by the permit that is dropped.
+    let _ = wake.await;
+    Permit
+}
+
+fn rate_limited_until() -> Option<Instant> {
+    RATE_LIMITED_UNTIL
+        .lock()
+        .ok()
+        .and_then(|until| *until)
+        .filter(|until| *until > Instant::now())
+}
+
+/// Waits for the current rate limit to end, or fails right away when it
+/// lasts longer than `MAX_RATE_LIMIT_WAIT`.
+async fn wait_out_rate_limit() -> Result<(), SpotifyError> {
+    while let Some(until) = rate_limited_until() {
+        let wait = until.saturating_duration_since(Instant::now());
+        if wait > MAX_RATE_LIMIT_WAIT {
+            return Err(SpotifyError::RateLimited(wait));
+        }
+        tokio::time::sleep_until(until.into()).await;
+    }
+    Ok(())
+}
+
+fn start_rate_limit(wait: Duration) {
+    let until = Instant::now() + wait;
+    if let Ok(mut current) = RATE_LIMITED_UNTIL.lock() {
+        if current.is_none_or(|current| current < until) {
+            eprintln!("Spotify rate limit hit, pausing requests for {:?}", wait);
+            *current = Some(until);
+        }
+    }
+}
+
+fn retry_after(response: &Response) -> Duration {
+    response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_RETRY_AFTER)
+}
+
+/// Why a Web API request failed. Commands hand it to the webview as text;
+/// the backend matches on it.
+#[derive(Debug, Clone)]
+pub enum SpotifyError {
+    /// Spotify answered with an error status and this body.
+    Status(StatusCode, String),
+    /// Spotify couldn't be reached or the response didn't come through.
+    Network(String),
+    /// Spotify is rate limiting requests for at least this long.
+    RateLimited(Duration),
+    /// No access token for the active account, e.g. after a failed refresh.
+    Auth(String),
+    /// The request couldn't be built.
+    Request(String),
+    /// Spotify answered with something the endpoint doesn't document.
+    Response(String),
+}
+
+impl fmt::Display for SpotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpotifyError::Status(status, body) => write!(f, "{}: {}", status, body),
+            SpotifyError::RateLimited(wait) => write!(
+                f,
+                "Spotify is rate limiting requests, try again in {} s",
+                wait.as_secs().max(1)
+            ),
+            SpotifyError::Network(message)
+            | SpotifyError::Auth(message)
+            | SpotifyError::Request(message)
+            | SpotifyError::Response(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<SpotifyError> for String {
+    fn from(error: SpotifyError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Sends a request to the Web API as the active account and returns the
+/// response body. 401 responses are retried once with a refreshed token, 429
+/// responses after `Retry-After`.
+async fn fetch<R: Runtime>(
+    app: &AppHandle<R>,
+    method: Method,
+    path: &str,
+    body: Option<&serde_json::Value>,
+    priority: Priority,
+) -> Result<String, SpotifyError> {
+    if !offline::is_online() {
+        return Err(SpotifyError::Network(
+            "Spotify is unreachable, working offline".to_string(),
+        ));
+    }
+    let url = format!("{}{}", SPOTIFY_API.base_url(), path);
+    let client = http::client(app);
+
+    let mut refreshed = false;
+    let mut rate_limited = 0;
+    loop {
+        // Rate limited requests wait without a slot, so they don't hold up
+        // requests queued behind them.
+        wait_out_rate_limit().await?;
+        let _permit = acquire(priority).await;
+        if rate_limited_until().is_some() {
+            continue;
+        }
+        let token = match SPOTIFY.access_token(app).await {
+            Ok(token) => token,
+            Err(e) => {
+                offline::check_connection(app).await;
+                return Err(SpotifyError::Auth(e));
+            }
+        };
+        let mut builder = client
+            .request(method.clone(), &url)
+            .bearer_auth(&token.access_token);
+        builder = match body {
+            Some(body) => builder.json(body),
+            // Spotify answers 411 to bodiless PUT and POST requests without it.
+            None if method != Method::GET => builder.header(CONTENT_LENGTH, 0),
+            None => builder,
+        };
+
+        let response = match http::send(builder).await {
+            Ok(response) => response,
+            Err(e) => {
+                offline::check_connection(app).await;
+                return Err(SpotifyError::Network(format!(
+                    "Spotify request failed: {}",
+                    e
+                )));
+            }
+        };
+        offline::set_online(app, true);
+        let status = response.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let wait = retry_after(&response);
+            start_rate_limit(wait);
+            rate_limited += 1;
+            if rate_limited > MAX_RATE_LIMIT_RETRIES || wait > MAX_RATE_LIMIT_WAIT {
+                return Err(SpotifyError::RateLimited(wait));
+            }
+            continue;
+        }
+        if status == StatusCode::UNAUTHORIZED && !refreshed {
+            refreshed = true;
+            SPOTIFY.refresh(app).await.map_err(SpotifyError::Auth)?;
+            continue;
+        }
+
+        let text = response.text().await.map_err(|e| {
+            SpotifyError::Network(format!("Failed to read Spotify response: {}", e))
+        })?;
+        if !status.is_success() {
+            return Err(SpotifyError::Status(status, text));
+        }
+        return Ok(text);
+    }
+}
+
+/// Like `fetch`, parsing the response. Empty responses, which Spotify sends
+/// when there is nothing to report, come back as `None`.
+pub async fn request<T: DeserializeOwned, R: Runtime>(
+    app: &AppHandle<R>,
+    method: Method,
+    path: &str,
+    body: Option<&serde_json::Value>,
+    priority: Priority,
+) -> Result<Option<T>, SpotifyError> {
+    let text = fetch(app, method, path, body, priority).await?;
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(&text).map(Some).map_err(|e| {
+        SpotifyError::Response(format!("Unexpected Spotify response from {}: {}", path, e))
+    })
+}
+
+async fn get<T: DeserializeOwned, R: Runtime>(
+    app: &AppHandle<R>,
+    path: &str,
+    priority: Priority,
+) -> Result<T, SpotifyError> {
+    request(app, Method::GET, path, None, priority)
+        .await?
+        .ok_or_else(|| SpotifyError::Response(format!("Spotify returned nothing for {}", path)))
+}
+
+/// Sends a command the user is waiting on. Its response, if any, is ignored.
+async fn send<R: Runtime>(
+    app: &AppHandle<R>,
+    method: Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> Result<(), SpotifyError> {
+    fetch(app, method, path, body.as_ref(), Priority::Interactive).await?;
+    if path.starts_with("/v1/me/player") {
+        playback::poll_soon();
+    }
+    Ok(())
+}
+
+fn encode(value: &str) -> String {
+    urlencoding::encode(value).into_owned()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Image {
+    pub url: String,
+    #[serde(default)]
+    pub height: Option<u32>,
+    #[serde(default)]
+    pub width: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimplifiedArtist {
+    /// `None` for local files.
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub uri: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimplifiedAlbum {
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub images: Vec<Image>,
+    #[serde(default)]
+    pub uri: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Track {
+    pub id: Option<String>,
+    pub name: String,
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub artists: Vec<SimplifiedArtist>,
+    pub album: SimplifiedAlbum,
+    #[serde(default)]
+    pub uri: Option<String>,
+    #[serde(default)]
+    pub explicit: bool,
+    #[serde(default)]
+    pub is_local: bool,
+    /// ISRC and friends.
+    #[serde(default)]
+    pub external_ids: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Device {
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub device_type: String,
+    pub volume_percent: Option<u8>,
+    #[serde(default)]
+    pub is_active: bool,
+    #[serde(default)]
+    pub is_restricted: bool,
+    #[serde(default)]
+    pub supports_volume: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybackContext {
+    #[serde(rename = "type")]
+    pub context_type: String,
+    pub uri: String,
+}
+
+/// `GET /me/player/currently-playing`. `item` is `None` for ads and
+/// episodes, which are not requested.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrentlyPlaying {
+    pub is_playing: bool,
+    pub progress_ms: Option<u64>,
+    pub item: Option<Track>,
+    #[serde(default)]
+    pub context: Option<PlaybackContext>,
+    /// Server time of the snapshot in milliseconds since the epoch.
+    #[serde(default)]
+    pub timestamp: i64,
+}
+
+/// `GET /me/player`: the current playback together with its device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybackState {
+    pub device: Device,
+    pub is_playing: bool,
+    pub progress_ms: Option<u64>,
+    pub item: Option<Track>,
+    #[serde(default)]
+    pub context: Option<PlaybackContext>,
+    #[serde(default)]
+    pub shuffle_state: bool,
+    #[serde(default)]
+    pub repeat_state: Option<String>,
+    #[serde(default)]
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Paging<T> {
+    pub items: Vec<T>,
+    #[serde(default)]
+    pub total: u32,
+    #[serde(default)]
+    pub limit: u32,
+    #[serde(default)]
+    pub offset: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistOwner {
+    pub id: String,
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistTracksRef {
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimplifiedPlaylist {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub images: Option<Vec<Image>>,
+    pub owner: PlaylistOwner,
+    pub tracks: PlaylistTracksRef,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistItem {
+    /// `None` for tracks that were removed from Spotify.
+    pub track: Option<Track>,
+    pub added_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayHistory {
+    pub track: Track,
+    pub played_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTrack {
+    pub track: Track,
+    pub added_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerQueue {
+    pub currently_playing: Option<Track>,
+    #[serde(default)]
+    pub queue: Vec<Track>,
+}
+
+#[derive(Deserialize)]
+struct SearchResults {
+    tracks: Paging<Track>,
+}
+
+#[derive(Deserialize)]
+struct Devices {
+    devices: Vec<Device>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PlayOffset {
+    Position { position: u32 },
+    Uri { uri: String },
+}
+
+/// Body of `PUT /me/player/play`. An empty request resumes playback.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uris: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<PlayOffset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position_ms: Option<u64>,
+}
+
+pub async fn currently_playing<R: Runtime>(
+    app: &AppHandle<R>,
+    priority: Priority,
+) -> Result<Option<CurrentlyPlaying>, SpotifyError> {
+    let path = "/v1/me/player/currently-playing?additional_types=track";
+    request(app, Method::GET, path, None, priority).await
+}
+
+/// `None` when no device is active.
+pub async fn playback_state<R: Runtime>(
+    app: &AppHandle<R>,
+    priority: Priority,
+) -> Result<Option<PlaybackState>, SpotifyError> {
+    request(app, Method::GET, "/v1/me/player", None, priority).await
+}
+
+pub async fn play<R: Runtime>(
+    app: &AppHandle<R>,
+    play: &PlayRequest,
+    device_id: Option<&str>,
+) -> Result<(), SpotifyError> {
+    let path = match device_id {
+        Some(id) => format!("/v1/me/player/play?device_id={}", encode(id)),
+        None => "/v1/me/player/play".to_string(),
+    };
+    let body = serde_json::to_value(play).map_err(|e| SpotifyError::Request(e.to_string()))?;
+    let body = body
+        .as_object()
+        .is_some_and(|b| !b.is_empty())
+        .then_some(body);
+    send(app, Method::PUT, &path, body).await
+}
+
+pub async fn pause<R: Runtime>(app: &AppHandle<R>) -> Result<(), SpotifyError> {
+    send(app, Method::PUT, "/v1/me/player/pause", None).await
+}
+
+pub async fn next<R: Runtime>(app: &AppHandle<R>) -> Result<(), SpotifyError> {
+    send(app, Method::POST, "/v1/me/player/next", None).await
+}
+
+pub async fn previous<R: Runtime>(app: &AppHandle<R>) -> Result<(), SpotifyError> {
+    send(app, Method::POST, "/v1/me/player/previous", None).await
+}
+
+pub async fn seek<R: Runtime>(app: &AppHandle<R>, position_ms: u64) -> Result<(), SpotifyError> {
+    let path = format!("/v1/me/player/seek?position_ms={}", position_ms);
+    send(app, Method::PUT, &path, None).await
+}
+
+pub async fn set_volume<R: Runtime>(
+    app: &AppHandle<R>,
+    volume_percent: u8,
+    device_id: Option<&str>,
+) -> Result<(), SpotifyError> {
+    let mut path = format!(
+        "/v1/me/player/volume?volume_percent={}",
+        volume_percent.min(100)
+    );
+    if let Some(id) = device_id {
+        path.push_str(&format!("&device_id={}", encode(id)));
+    }
+    send(app, Method::PUT, &path, None).await
+}
+
+pub async fn devices<R: Runtime>(
+    app: &AppHandle<R>,
+    priority: Priority,
+) -> Result<Vec<Device>, SpotifyError> {
+    let devices: Devices = get(app, "/v1/me/player/devices", priority).await?;
+    Ok(devices.devices)
+}
+
+pub async fn transfer_playback<R: Runtime>(
+    app: &AppHandle<R>,
+    device_id: &str,
+    play: bool,
+) -> Result<(), SpotifyError> {
+    let body = serde_json::json!({ "device_ids": [device_id], "play": play });
+    send(app, Method::PUT, "/v1/me/player", Some(body)).await
+}
+
+pub async fn add_to_queue<R: Runtime>(app: &AppHandle<R>, uri: &str) -> Result<(), SpotifyError> {
+    let path = format!("/v1/me/player/queue?uri={}", encode(uri));
+    send(app, Method::POST, &path, None).await
+}
+
+pub async fn queue<R: Runtime>(
+    app: &AppHandle<R>,
+    priority: Priority,
+) -> Result<PlayerQueue, SpotifyError> {
+    get(app, "/v1/me/player/queue", priority).await
+}
+
+pub async fn recently_played<R: Runtime>(
+    app: &AppHandle<R>,
+    limit: u32,
+    priority: Priority,
+) -> Result<Vec<PlayHistory>, SpotifyError> {
+    let path = format!("/v1/me/player/recently-played?limit={}", limit.clamp(1, 50));
+    let page: Paging<PlayHistory> = get(app, &path, priority).await?;
+    Ok(page.items)
+}
+
+pub async fn search_tracks<R: Runtime>(
+    app: &AppHandle<R>,
+    query: &str,
+    limit: u32,
+    priority: Priority,
+) -> Result<Vec<Track>, SpotifyError> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let path = format!(
+        "/v1/search?q={}&type=track&limit={}",
+        encode(query),
+        limit.clamp(1, 50)
+    );
+    let results: SearchResults = get(app, &path, priority).await?;
+    Ok(results.tracks.items)
+}
+
+pub async fn user_playlists<R: Runtime>(
+    app: &AppHandle<R>,
+    limit: u32,
+    offset: u32,
+    priority: Priority,
+) -> Result<Paging<SimplifiedPlaylist>, SpotifyError> {
+    let path = format!(
+        "/v1/me/playlists?limit={}&offset={}",
+        limit.clamp(1, 50),
+        offset
+    );
+    get(app, &path, priority).await
+}
+
+pub async fn playlist_tracks<R: Runtime>(
+    app: &AppHandle<R>,
+    playlist_id: &str,
+    limit: u32,
+    offset: u32,
+    priority: Priority,
+) -> Result<Paging<PlaylistItem>, SpotifyError> {
+    let path = format!(
+        "/v1/playlists/{}/tracks?limit={}&offset={}",
+        encode(playlist_id),
+        limit.clamp(1, 100),
+        offset
+    );
+    get(app, &path, priority).await
+}
+
+pub async fn add_to_playlist<R: Runtime>(
+    app: &AppHandle<R>,
+    playlist_id: &str,
+    uris: &[String],
+) -> Result<(), SpotifyError> {
+    let path = format!("/v1/playlists/{}/tracks", encode(playlist_id));
+    let body = serde_json::json!({ "uris": uris });
+    send(app, Method::POST, &path, Some(body)).await
+}
+
+pub async fn saved_tracks<R: Runtime>(
+    app: &AppHandle<R>,
+    limit: u32,
+    offset: u32,
+    priority: Priority,
+) -> Result<Paging<SavedTrack>, SpotifyError> {
+    let path = format!(
+        "/v1/me/tracks?limit={}&offset={}",
+        limit.clamp(1, 50),
+        offset
+    );
+    get(app, &path, priority).await
+}
+
+pub async fn save_tracks<R: Runtime>(
+    app: &AppHandle<R>,
+    ids: &[String],
+) -> Result<(), SpotifyError> {
+    let path = format!("/v1/me/tracks?ids={}", encode(&ids.join(",")));
+    send(app, Method::PUT, &path, None).await
+}
+
+#[tauri::command]
+pub async fn spotify_currently_playing(
+    app: AppHandle,
+    priority: Option<Priority>,
+) -> Result<Option<CurrentlyPlaying>, String> {
+    currently_playing(&app, priority.unwrap_or_default())
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_playback_state(
+    app: AppHandle,
+    priority: Option<Priority>,
+) -> Result<Option<PlaybackState>, String> {
+    playback_state(&app, priority.unwrap_or_default())
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_play(
+    app: AppHandle,
+    request: Option<PlayRequest>,
+    device_id: Option<String>,
+) -> Result<(), String> {
+    play(&app, &request.unwrap_or_default(), device_id.as_deref())
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_pause(app: AppHandle) -> Result<(), String> {
+    pause(&app).await.map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_next(app: AppHandle) -> Result<(), String> {
+    next(&app).await.map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_previous(app: AppHandle) -> Result<(), String> {
+    previous(&app).await.map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_seek(app: AppHandle, position_ms: u64) -> Result<(), String> {
+    seek(&app, position_ms).await.map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_set_volume(
+    app: AppHandle,
+    volume_percent: u8,
+    device_id: Option<String>,
+) -> Result<(), String> {
+    set_volume(&app, volume_percent, device_id.as_deref())
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_devices(
+    app: AppHandle,
+    priority: Option<Priority>,
+) -> Result<Vec<Device>, String> {
+    devices(&app, priority.unwrap_or_default())
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_transfer_playback(
+    app: AppHandle,
+    device_id: String,
+    play: bool,
+) -> Result<(), String> {
+    transfer_playback(&app, &device_id, play)
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_add_to_queue(app: AppHandle, uri: String) -> Result<(), String> {
+    add_to_queue(&app, &uri).await.map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_queue(
+    app: AppHandle,
+    priority: Option<Priority>,
+) -> Result<PlayerQueue, String> {
+    queue(&app, priority.unwrap_or_default())
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_recently_played(
+    app: AppHandle,
+    limit: u32,
+    priority: Option<Priority>,
+) -> Result<Vec<PlayHistory>, String> {
+    recently_played(&app, limit, priority.unwrap_or_default())
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_search_tracks(
+    app: AppHandle,
+    query: String,
+    limit: u32,
+    priority: Option<Priority>,
+) -> Result<Vec<Track>, String> {
+    search_tracks(&app, &query, limit, priority.unwrap_or_default())
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_user_playlists(
+    app: AppHandle,
+    limit: u32,
+    offset: u32,
+    priority: Option<Priority>,
+) -> Result<Paging<SimplifiedPlaylist>, String> {
+    user_playlists(&app, limit, offset, priority.unwrap_or_default())
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_playlist_tracks(
+    app: AppHandle,
+    playlist_id: String,
+    limit: u32,
+    offset: u32,
+    priority: Option<Priority>,
+) -> Result<Paging<PlaylistItem>, String> {
+    playlist_tracks(
+        &app,
+        &playlist_id,
+        limit,
+        offset,
+        priority.unwrap_or_default(),
+    )
+    .await
+    .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_add_to_playlist(
+    app: AppHandle,
+    playlist_id: String,
+    uris: Vec<String>,
+) -> Result<Outcome, String> {
+    offline::apply_or_queue(&app, Operation::AddToPlaylist { playlist_id, uris }).await
+}
+
+#[tauri::command]
+pub async fn spotify_saved_tracks(
+    app: AppHandle,
+    limit: u32,
+    offset: u32,
+    priority: Option<Priority>,
+) -> Result<Paging<SavedTrack>, String> {
+    saved_tracks(&app, limit, offset, priority.unwrap_or_default())
+        .await
+        .map_err(String::from)
+}
+
+#[tauri::command]
+pub async fn spotify_save_tracks(app: AppHandle, ids: Vec<String>) -> Result<Outcome, String> {
+    offline::apply_or_queue(&app, Operation::SaveTracks { ids }).await
+}
+
+/// Read-only access to the endpoints without a typed wrapper, like top
+/// items, audio features and recommendations. `path` starts at `/v1/`.
+#[tauri::command]
+pub async fn spotify_api_get(
+    app: AppHandle,
+    path: String,
+    priority: Option<Priority>,
+) -> Result<Option<serde_json::Value>, String> {
+    if !path.starts_with("/v1/") || path.contains("..") {
+        return Err(format!("Not a Spotify Web API path: {}", path));
+    }
+    request(&app, Method::GET, &path, None, priority.unwrap_or_default())
+        .await
+        .map_err(String::from)
+}
@@ -1,54 +1,70 @@
 use crate::credential_store;
-use base64::{engine::general_purpose, Engine as _};
-use chrono::Utc;
-use rand::RngCore;
-use reqwest::header::CONTENT_TYPE;
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-use sha2::{Digest, Sha256};
-use std::net::SocketAddr;
+use crate::endpoints::{Endpoint, SPOTIFY_ACCOUNTS, SPOTIFY_API};
+use crate::oauth::{
+    AccessToken, Account, AccountList, CallbackPorts, ClientAuth, OAuthClient, OAuthProvider,
+    SignOutReport,
+};
+use crate::offline;
 use std::sync::{Arc, Mutex};
-use tauri::async_runtime as rt;
 use tauri::AppHandle;
-use tauri::Emitter;
-use tokio::time::sleep;
-
-/// Port for the local OAuth callback server. Must match the port registered in
-/// the Spotify app's Redirect URI.
-const CALLBACK_PORT: u16 = 3000;
-/// The one and only redirect URI used for the OAuth flow. This exact string
-/// must be added to the Spotify app's "Redirect URIs" in the developer
-/// dashboard, otherwise Spotify rejects the login with
-/// "redirect_uri: Not matching configuration". Keep this as the single source
-/// of truth — the frontend fetches it via `get_spotify_redirect_uri` so the two
-/// can never drift apart.
-const REDIRECT_URI: &str = "http://127.0.0.1:3000/callback";
-const ACCESS_TOKEN_KEY: &str = "access_token";
-const REFRESH_TOKEN_KEY: &str = "refresh_token";
-const TOKEN_EXPIRY_KEY: &str = "token_expiry";
-const MUSIC_PROVIDER_KEY: &str = "music_provider";
-const SPOTIFY_CLIENT_ID_KEY: &str = "spotify_client_id";
-
-lazy_static::lazy_static! {
-    static ref CLIENT_ID_CACHE: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-    static ref TOKENS_CACHE: Arc<Mutex<Option<SpotifyTokens>>> = Arc::new(Mutex::new(None));
-    static ref MUSIC_PROVIDER_CACHE: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-}
 
-fn get_cached_tokens() -> Option<SpotifyTokens> {
-    TOKENS_CACHE.lock().ok().and_then(|g| g.clone())
-}
+const MUSIC_PROVIDER_KEY: &str = "music_provider";
 
-fn set_cached_tokens(tokens: &SpotifyTokens) {
-    if let Ok(mut cache) = TOKENS_CACHE.lock() {
-        *cache = Some(tokens.clone());
-    }
+/// Spotify uses PKCE, so only a client ID is needed. Tokens keep their
+/// unprefixed keys from before YouTube support so existing logins survive.
+pub static SPOTIFY: OAuthClient = OAuthClient::new(OAuthProvider {
+    id: "spotify",
+    name: "Spotify",
+    event_prefix: "oauth",
+    authorize_url: Endpoint::new(&SPOTIFY_ACCOUNTS, "/authorize"),
+    token_url: Endpoint::new(&SPOTIFY_ACCOUNTS, "/api/token"),
+    scopes: &[
+        "user-read-playback-state",
+        "user-modify-playback-state",
+        "user-read-currently-playing",
+        "playlist-read-private",
+        "playlist-modify-public",
+        "playlist-modify-private",
+        "user-top-read",
+        "user-read-recently-played",
+        "user-library-read",
+    ],
+    feature_scopes: &[("library-modify", &["user-library-modify"])],
+    incremental_params: &[],
+    authorize_params: &[],
+    client_auth: ClientAuth::Pkce,
+    callback_ports: CallbackPorts::Configured,
+    verify_url: Some(Endpoint::new(&SPOTIFY_API, "/v1/me")),
+    revoke_url: None,
+    parse_account: Some(spotify_account),
+    add_account_params: &[("show_dialog", "true")],
+    token_key_prefix: "",
+    client_key_prefix: "spotify_",
+    embedded_client_id: option_env!("SPOTIFY_CLIENT_ID"),
+    embedded_client_secret: None,
+    accent_color: "#1db954",
+    accent_foreground: "black",
+});
+
+/// Account from the `/v1/me` profile. Spotify only has a display name if the
+/// user set one, so the user ID is the fallback.
+fn spotify_account(me: &serde_json::Value) -> Option<Account> {
+    let id = me["id"].as_str()?.to_string();
+    let display_name = me["display_name"]
+        .as_str()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&id)
+        .to_string();
+    let avatar_url = me["images"][0]["url"].as_str().map(|url| url.to_string());
+    Some(Account {
+        id,
+        display_name,
+        avatar_url,
+    })
 }
 
-fn clear_cached_tokens() {
-    if let Ok(mut cache) = TOKENS_CACHE.lock() {
-        *cache = None;
-    }
+lazy_static::lazy_static! {
+    static ref MUSIC_PROVIDER_CACHE: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
 }
 
 fn get_cached_music_provider() -> Option<String> {
@@ -67,68 +83,14 @@ fn clear_cached_music_provider() {
     }
 }
 
-fn get_embedded_spotify_client_id() -> Option<String> {
-    option_env!("SPOTIFY_CLIENT_ID")
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
-}
-
-fn get_cached_client_id() -> Option<String> {
-    CLIENT_ID_CACHE.lock().ok().and_then(|g| g.clone())
-}
-
-fn set_cached_client_id(id: &str) {
-    if let Ok(mut cache) = CLIENT_ID_CACHE.lock() {
-        *cache = Some(id.to_string());
-    }
-}
-
-fn clear_cached_client_id() {
-    if let Ok(mut cache) = CLIENT_ID_CACHE.lock() {
-        *cache = None;
-    }
-}
-
-async fn get_stored_spotify_client_id() -> Option<String> {
-    if let Some(cached) = get_cached_client_id() {
-        return Some(cached);
-    }
-    
-    let result = tokio::task::spawn_blocking(|| {
-        credential_store::get(SPOTIFY_CLIENT_ID_KEY).ok()
-    })
-    .await
-    .ok()
-    .flatten();
-    
-    if let Some(ref id) = result {
-        set_cached_client_id(id);
-    }
-    
-    result
-}
-
 #[tauri::command]
 pub async fn has_spotify_client_id() -> bool {
-    get_embedded_spotify_client_id().is_some() || get_stored_spotify_client_id().await.is_some()
+    SPOTIFY.has_client_credentials().await
 }
 
 #[tauri::command]
 pub async fn save_spotify_client_id(client_id: String) -> Result<(), String> {
-    if client_id.trim().is_empty() {
-        return Err("Client ID is empty".to_string());
-    }
-    let client_id_trimmed = client_id.trim().to_string();
-    
-    set_cached_client_id(&client_id_trimmed);
-    
-    let client_id_clone = client_id_trimmed.clone();
-    tokio::task::spawn_blocking(move || {
-        credential_store::set(SPOTIFY_CLIENT_ID_KEY, &client_id_clone)
-            .map_err(|e| format!("Failed to save client ID: {e}"))
-    })
-    .await
-    .map_err(|e| format!("Task failed: {}", e))?
+    SPOTIFY.save_client_credentials(client_id, None).await
 }
 
 #[tauri::command]
@@ -136,52 +98,12 @@ pub async fn needs_spotify_setup() -> bool {
     !has_spotify_client_id().await
 }
 
-/// The exact redirect URI the user must register in their Spotify app.
-/// Exposed to the frontend so the setup screen can show a copy-paste value that
-/// always matches what the backend sends to Spotify.
+/// Every redirect URI the user must register in their Spotify app, one per
+/// callback port. Exposed to the frontend so the setup screen can show
+/// copy-paste values that always match what the backend sends to Spotify.
 #[tauri::command]
-pub fn get_spotify_redirect_uri() -> String {
-    REDIRECT_URI.to_string()
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SpotifyTokens {
-    pub access_token: String,
-    pub refresh_token: String,
-    pub expires_at: i64,
-}
-
-#[derive(Debug, Clone)]
-struct AuthState {
-    code_verifier: String,
-    client_id: String,
-    state_nonce: String,
-}
-
-lazy_static::lazy_static! {
-    static ref AUTH_STATE: Arc<Mutex<Option<AuthState>>> = Arc::new(Mutex::new(None));
-}
-
-fn urlsafe_b64_no_pad(bytes: &[u8]) -> String {
-    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
-}
-
-fn generate_code_verifier() -> String {
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-._~";
-    let mut rng = rand::rng();
-    let mut verifier = String::with_capacity(64);
-    for _ in 0..64 {
-        let idx = (rng.next_u32() as usize) % CHARSET.len();
-        verifier.push(CHARSET[idx] as char);
-    }
-    verifier
-}
-
-fn generate_code_challenge(verifier: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(verifier.as_bytes());
-    let hash = hasher.finalize();
-    urlsafe_b64_no_pad(&hash)
+pub fn get_spotify_redirect_uri(app: AppHandle) -> Vec<String> {
+    SPOTIFY.redirect_uris(&app)
 }
 
 #[tauri::command]
@@ -193,7 +115,7 @@ pub async fn set_music_provider(provider: String) -> Result<(), String> {
     })
     .await
     .map_err(|e| format!("Task failed: {}", e))??;
-    
+
     set_cached_music_provider(&provider);
     Ok(())
 }
@@ -203,14 +125,14 @@ pub async fn get_music_provider() -> Result<String, String> {
     if let Some(cached) = get_cached_music_provider() {
         return Ok(cached);
     }
-    
+
     let result = tokio::task::spawn_blocking(|| {
         credential_store::get(MUSIC_PROVIDER_KEY)
             .map_err(|e| format!("Failed to get music provider: {e}"))
     })
     .await
     .map_err(|e| format!("Task failed: {}", e))??;
-    
+
     set_cached_music_provider(&result);
     Ok(result)
 }
@@ -220,435 +142,101 @@ pub async fn has_music_provider() -> bool {
     get_music_provider().await.is_ok()
 }
 
-async fn save_tokens(tokens: &SpotifyTokens) -> Result<(), String> {
-    let access_token = tokens.access_token.clone();
-    let refresh_token = tokens.refresh_token.clone();
-    let expires_at = tokens.expires_at.to_string();
-    
-    tokio::task::spawn_blocking(move || {
-        credential_store::set(ACCESS_TOKEN_KEY, &access_token)
-            .map_err(|e| format!("Failed to save access token: {e}"))?;
-        credential_store::set(REFRESH_TOKEN_KEY, &refresh_token)
-            .map_err(|e| format!("Failed to save refresh token: {e}"))?;
-        credential_store::set(TOKEN_EXPIRY_KEY, &expires_at)
-            .map_err(|e| format!("Failed to save token expiry: {e}"))?;
-        Ok::<(), String>(())
-    })
-    .await
-    .map_err(|e| format!("Task failed: {}", e))??;
-    
-    set_cached_tokens(tokens);
-    Ok(())
-}
-
-pub async fn verify_spotify_access(access_token: &str) -> Result<(), String> {
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://api.spotify.com/v1/me")
-        .bearer_auth(access_token)
-        .send()
-        .await
-        .map_err(|e| format!("Verification request failed: {}", e))?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let text = response.text().await.unwrap_or_default();
-        return Err(format!("Verification failed: {} - {}", status, text));
-    }
-    Ok(())
-}
-
 #[tauri::command]
-pub async fn get_tokens() -> Result<SpotifyTokens, String> {
-    if let Some(cached) = get_cached_tokens() {
-        return Ok(cached);
-    }
-    
-    let result = tokio::task::spawn_blocking(|| -> Result<SpotifyTokens, String> {
-        let access_token = credential_store::get(ACCESS_TOKEN_KEY)
-            .map_err(|e| format!("Failed to get access token: {e}"))?;
-        let refresh_token = credential_store::get(REFRESH_TOKEN_KEY)
-            .map_err(|e| format!("Failed to get refresh token: {e}"))?;
-        let expires_at_str = credential_store::get(TOKEN_EXPIRY_KEY)
-            .map_err(|e| format!("Failed to get token expiry: {e}"))?;
-        let expires_at = expires_at_str
-            .parse::<i64>()
-            .map_err(|e| format!("Failed to parse expiry: {}", e))?;
-
-        Ok(SpotifyTokens { access_token, refresh_token, expires_at })
-    })
-    .await
-    .map_err(|e| format!("Task failed: {}", e))??;
-    
-    set_cached_tokens(&result);
-    Ok(result)
+pub async fn get_access_token(app: AppHandle) -> Result<AccessToken, String> {
+    SPOTIFY.access_token(&app).await
 }
 
 #[tauri::command]
 pub async fn has_valid_tokens() -> bool {
-    match get_tokens().await {
-        Ok(tokens) => Utc::now().timestamp() < tokens.expires_at,
-        Err(_) => false,
-    }
+    SPOTIFY.has_valid_tokens().await
 }
 
+/// Signs out of every Spotify account. Spotify has no revocation endpoint,
+/// so access stays listed under the user's Spotify apps until removed there.
 #[tauri::command]
-pub async fn clear_credentials() -> Result<(), String> {
-    if let Ok(mut s) = AUTH_STATE.lock() {
-        *s = None;
-    }
-    if let Ok(mut shutdown) = OAUTH_SHUTDOWN.lock() {
-        if let Some(tx) = shutdown.take() {
-            let _ = tx.send(());
-        }
-    }
-    clear_cached_client_id();
-    clear_cached_tokens();
+pub async fn clear_credentials(app: AppHandle) -> Result<SignOutReport, String> {
     clear_cached_music_provider();
-    
+    // Queued changes can't be sent without an account.
+    if !offline::clear_outbox(&app) {
+        eprintln!("Failed to delete the outbox");
+    }
+    let report = SPOTIFY.clear(&app).await?;
+
     tokio::task::spawn_blocking(|| {
-        let _ = credential_store::delete(ACCESS_TOKEN_KEY);
-        let _ = credential_store::delete(REFRESH_TOKEN_KEY);
-        let _ = credential_store::delete(TOKEN_EXPIRY_KEY);
         let _ = credential_store::delete(MUSIC_PROVIDER_KEY);
-        let _ = credential_store::delete(SPOTIFY_CLIENT_ID_KEY);
     })
     .await
     .map_err(|e| format!("Task failed: {}", e))?;
-    Ok(())
-}
-
-async fn exchange_code_for_tokens(state: &AuthState, code: &str) -> Result<SpotifyTokens, String> {
-    let form = [
-        ("grant_type", "authorization_code"),
-        ("code", code),
-        ("redirect_uri", REDIRECT_URI),
-        ("client_id", &state.client_id),
-        ("code_verifier", &state.code_verifier),
-    ];
-
-    let client = reqwest::Client::new();
-    let response = client
-        .post("https://accounts.spotify.com/api/token")
-        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
-        .form(&form)
-        .send()
-        .await
-        .map_err(|e| format!("Token request failed: {}", e))?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let text = response.text().await.unwrap_or_default();
-        return Err(format!("Token request failed: {} - {}", status, text));
-    }
-
-    #[derive(Deserialize)]
-    struct TokenResponse {
-        access_token: String,
-        refresh_token: Option<String>,
-        expires_in: i64,
-    }
-
-    let tr: TokenResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse token response: {}", e))?;
-
-    let expires_at = Utc::now().timestamp() + tr.expires_in - 30;
-    let refresh_token = tr
-        .refresh_token
-        .ok_or_else(|| "Missing refresh_token in response".to_string())?;
-
-    Ok(SpotifyTokens { access_token: tr.access_token, refresh_token, expires_at })
-}
-
-lazy_static::lazy_static! {
-    static ref OAUTH_SHUTDOWN: Arc<Mutex<Option<tokio::sync::oneshot::Sender<()>>>> = Arc::new(Mutex::new(None));
+    Ok(report)
 }
 
 #[tauri::command]
 pub async fn cancel_oauth_flow() -> Result<(), String> {
-    if let Ok(mut s) = AUTH_STATE.lock() {
-        *s = None;
-    }
-    if let Ok(mut shutdown) = OAUTH_SHUTDOWN.lock() {
-        if let Some(tx) = shutdown.take() {
-            let _ = tx.send(());
-        }
-    }
+    SPOTIFY.cancel_flow();
     Ok(())
 }
 
 #[tauri::command]
 pub async fn start_oauth_flow(app: AppHandle) -> Result<(), String> {
-    if let Ok(mut shutdown) = OAUTH_SHUTDOWN.lock() {
-        if let Some(tx) = shutdown.take() {
-            let _ = tx.send(());
-        }
-    }
-    
-    sleep(std::time::Duration::from_millis(100)).await;
-
-    let client_id = get_embedded_spotify_client_id()
-        .or(get_stored_spotify_client_id().await)
-        .ok_or_else(|| "No Spotify Client ID configured. Please set up your Client ID first.".to_string())?;
-
-    if let Ok(mut s) = AUTH_STATE.lock() {
-        *s = None;
-    }
+    SPOTIFY.start_flow(app).await
+}
 
-    let code_verifier = generate_code_verifier();
-    let code_challenge = generate_code_challenge(&code_verifier);
-    let mut state_bytes = [0u8; 16];
-    rand::rng().fill_bytes(&mut state_bytes);
-    let state_nonce = hex::encode(state_bytes);
-
-    if let Ok(mut s) = AUTH_STATE.lock() {
-        *s = Some(AuthState { 
-            code_verifier, 
-            client_id: client_id.clone(),
-            state_nonce: state_nonce.clone(),
-        });
-    }
+#[tauri::command]
+pub async fn complete_oauth_flow_manually(app: AppHandle, input: String) -> Result<(), String> {
+    SPOTIFY.complete_flow_manually(app, input).await
+}
 
-    let app_handle = app.clone();
-    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
-    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
-    
-    if let Ok(mut shutdown) = OAUTH_SHUTDOWN.lock() {
-        *shutdown = Some(shutdown_tx);
-    }
+#[tauri::command]
+pub async fn list_spotify_accounts() -> AccountList {
+    SPOTIFY.account_list().await
+}
 
-    rt::spawn(async move {
-        use axum::{extract::Query, routing::get, Router};
-        use std::collections::HashMap;
-
-        let (callback_done_tx, mut callback_done_rx) = tokio::sync::mpsc::channel::<()>(1);
-
-        let router = Router::new().route(
-            "/callback",
-            get({
-                let app = app_handle.clone();
-                let done_tx = callback_done_tx.clone();
-                move |query: Query<HashMap<String, String>>| {
-                    let app = app.clone();
-                    let done_tx = done_tx.clone();
-                    async move {
-                        let result = handle_oauth_callback(query, app).await;
-                        let _ = done_tx.send(()).await;
-                        result
-                    }
-                }
-            }),
-        );
-
-        let addr = SocketAddr::from(([127, 0, 0, 1], CALLBACK_PORT));
-        let listener = match tokio::net::TcpListener::bind(addr).await {
-            Ok(l) => l,
-            Err(e) => {
-                let msg = format!(
-                    "Port {} is already in use, so MiniFy can't receive the Spotify login. Close the app that is using it (or restart your computer) and try again. ({})",
-                    CALLBACK_PORT, e
-                );
-                let _ = app_handle.emit("oauth-failed", json!({ "error": msg }));
-                let _ = ready_tx.send(Err(msg));
-                return;
-            }
-        };
-        let _ = ready_tx.send(Ok(()));
-
-        let server = axum::serve(listener, router);
-        
-        tokio::select! {
-            _ = server => {}
-            _ = callback_done_rx.recv() => {
-                sleep(std::time::Duration::from_millis(500)).await;
-            }
-            _ = shutdown_rx => {}
-            _ = sleep(std::time::Duration::from_secs(300)) => {
-                let _ = app_handle.emit("oauth-failed", json!({ "error": "OAuth timeout - please try again" }));
-            }
-        }
-        
-        if let Ok(mut shutdown) = OAUTH_SHUTDOWN.lock() {
-            *shutdown = None;
-        }
-    });
-
-    let _ = ready_rx.await.map_err(|_| "server_not_ready".to_string())??;
-
-    let redirect_uri = urlencoding::encode(REDIRECT_URI);
-    let scopes = "user-read-playback-state user-modify-playback-state user-read-currently-playing playlist-read-private playlist-modify-public playlist-modify-private user-top-read user-read-recently-played user-library-read";
-    let auth_url = format!(
-        "https://accounts.spotify.com/authorize?client_id={}&response_type=code&redirect_uri={}&scope={}&code_challenge_method=S256&code_challenge={}&state={}",
-        urlencoding::encode(&client_id),
-        redirect_uri,
-        urlencoding::encode(scopes),
-        code_challenge,
-        state_nonce
-    );
-
-    webbrowser::open(&auth_url).map_err(|e| format!("Failed to open browser: {}", e))?;
+/// Opens the Spotify login with the account picker so another user can sign
+/// in. The new account becomes the active one.
+#[tauri::command]
+pub async fn add_spotify_account(app: AppHandle) -> Result<(), String> {
+    SPOTIFY.add_account(app).await
+}
 
+#[tauri::command]
+pub async fn switch_spotify_account(app: AppHandle, account_id: String) -> Result<(), String> {
+    SPOTIFY.switch_account(&app, account_id).await?;
+    offline::replay_soon(&app);
     Ok(())
 }
 
-async fn handle_oauth_callback(
-    query: axum::extract::Query<std::collections::HashMap<String, String>>,
-    app: AppHandle,
-) -> axum::response::Html<String> {
-    use axum::response::Html;
-
-    if let Some(err) = query.get("error") {
-        let _ = app.emit("oauth-failed", json!({ "error": err }));
-        return Html(error_page("Authentication was denied or failed"));
-    }
-
-    let code = match query.get("code") {
-        Some(c) => c.to_string(),
-        None => {
-            let _ = app.emit("oauth-failed", json!({ "error": "missing_code" }));
-            return Html(error_page("Missing authorization code"));
-        }
-    };
-
-    let auth_state = AUTH_STATE.lock().ok().and_then(|s| s.clone());
-
-    let Some(st) = auth_state else {
-        let _ = app.emit("oauth-failed", json!({ "error": "no_auth_state" }));
-        return Html(error_page("Session expired - please close old browser tabs and try again"));
-    };
-
-    let state_param = query.get("state").cloned().unwrap_or_default();
-    if state_param != st.state_nonce {
-        return Html(error_page("This login session has expired. Please close this tab and try again in the app."));
-    }
-
-    if let Ok(mut s) = AUTH_STATE.lock() {
-        *s = None;
-    }
-
-    match exchange_code_for_tokens(&st, &code).await {
-        Ok(tokens) => {
-            if let Err(e) = save_tokens(&tokens).await {
-                let _ = app.emit("oauth-failed", json!({ "error": e }));
-                return Html(error_page("Failed to save credentials"));
-            }
-            
-            match verify_spotify_access(&tokens.access_token).await {
-                Ok(_) => {
-                    let _ = app.emit("oauth-success", json!({}));
-                    Html(success_page())
-                }
-                Err(err_msg) => {
-                    let _ = app.emit("oauth-failed", json!({ "error": err_msg }));
-                    Html(error_page("Invalid credentials - check your Client ID"))
-                }
-            }
-        }
-        Err(e) => {
-            let _ = app.emit("oauth-failed", json!({ "error": e }));
-            Html(error_page(&format!("Token exchange failed: {}", e)))
-        }
-    }
+/// Removes an account. Its queued offline changes are dropped on the next
+/// replay, which also picks up those of the account that becomes active.
+#[tauri::command]
+pub async fn remove_spotify_account(app: AppHandle, account_id: String) -> Result<(), String> {
+    SPOTIFY.remove_account(&app, account_id).await?;
+    offline::replay_soon(&app);
+    Ok(())
 }
 
-fn success_page() -> String {
-    r##"<!DOCTYPE html>
-<html><head><meta charset="utf-8"><title>MiniFy - Success</title></head>
-<body style="font-family:system-ui,sans-serif;background:#0a0a0a;color:#fff;display:flex;justify-content:center;align-items:center;height:100vh;margin:0">
-<div style="text-align:center;padding:2rem;background:rgba(255,255,255,0.05);border-radius:16px;border:1px solid rgba(255,255,255,0.1)">
-<div style="width:64px;height:64px;background:#1db954;border-radius:12px;display:flex;align-items:center;justify-content:center;margin:0 auto 1rem">
-<svg width="32" height="32" viewBox="0 0 24 24" fill="none" stroke="black" stroke-width="3"><polyline points="20 6 9 17 4 12"></polyline></svg>
-</div>
-<h1 style="color:#1db954;margin:0 0 0.5rem;font-size:1.5rem">Connected!</h1>
-<p style="color:rgba(255,255,255,0.6);margin:0">You can close this window</p>
-<script>setTimeout(()=>window.close(),1500)</script>
-</div></body></html>"##.to_string()
+#[tauri::command]
+pub async fn has_spotify_scope(scope: String) -> bool {
+    SPOTIFY.has_scope(&scope).await
 }
 
-fn escape_html(input: &str) -> String {
-    input
-        .replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&#x27;")
+#[tauri::command]
+pub async fn has_spotify_feature(feature: String) -> Result<bool, String> {
+    SPOTIFY.has_feature(&feature).await
 }
 
-fn error_page(message: &str) -> String {
-    let escaped_message = escape_html(message);
-    format!(r##"<!DOCTYPE html>
-<html><head><meta charset="utf-8"><title>MiniFy - Error</title></head>
-<body style="font-family:system-ui,sans-serif;background:#0a0a0a;color:#fff;display:flex;justify-content:center;align-items:center;height:100vh;margin:0">
-<div style="text-align:center;padding:2rem;background:rgba(255,255,255,0.05);border-radius:16px;border:1px solid rgba(239,68,68,0.3);max-width:400px">
-<div style="width:64px;height:64px;background:#ef4444;border-radius:12px;display:flex;align-items:center;justify-content:center;margin:0 auto 1rem">
-<svg width="32" height="32" viewBox="0 0 24 24" fill="none" stroke="white" stroke-width="2"><circle cx="12" cy="12" r="10"></circle><line x1="15" y1="9" x2="9" y2="15"></line><line x1="9" y1="9" x2="15" y2="15"></line></svg>
-</div>
-<h1 style="color:#ef4444;margin:0 0 0.5rem;font-size:1.25rem">Authentication Failed</h1>
-<p style="color:rgba(255,255,255,0.6);margin:0;font-size:0.9rem">{}</p>
-<p style="color:rgba(255,255,255,0.4);margin:1rem 0 0;font-size:0.8rem">Please close this window and try again in the app</p>
-</div></body></html>"##, escaped_message)
+/// Re-opens the Spotify login asking for the scopes of one feature, keeping
+/// every scope already granted.
+#[tauri::command]
+pub async fn request_spotify_feature(app: AppHandle, feature: String) -> Result<(), String> {
+    SPOTIFY.request_feature(app, &feature).await
 }
 
 #[tauri::command]
-pub async fn refresh_access_token() -> Result<SpotifyTokens, String> {
-    let tokens = get_tokens().await?;
-    let client_id = get_embedded_spotify_client_id()
-        .or(get_stored_spotify_client_id().await)
-        .ok_or_else(|| "No Spotify Client ID configured".to_string())?;
-    let form = [
-        ("grant_type", "refresh_token"),
-        ("refresh_token", tokens.refresh_token.as_str()),
-        ("client_id", client_id.as_str()),
-    ];
-
-    let client = reqwest::Client::new();
-    let response = client
-        .post("https://accounts.spotify.com/api/token")
-        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
-        .form(&form)
-        .send()
-        .await
-        .map_err(|e| format!("Refresh request failed: {}", e))?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let text = response.text().await.unwrap_or_default();
-        return Err(format!("Refresh failed: {} - {}", status, text));
-    }
-
-    #[derive(Deserialize)]
-    struct RefreshResponse {
-        access_token: String,
-        refresh_token: Option<String>,
-        expires_in: i64,
-    }
-
-    let rr: RefreshResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse refresh response: {}", e))?;
-
-    let expires_at = Utc::now().timestamp() + rr.expires_in - 30;
-    let refresh_token = rr.refresh_token.unwrap_or(tokens.refresh_token);
-
-    let updated = SpotifyTokens { access_token: rr.access_token, refresh_token, expires_at };
-    save_tokens(&updated).await?;
-    Ok(updated)
+pub async fn refresh_access_token(app: AppHandle) -> Result<AccessToken, String> {
+    SPOTIFY.refresh(&app).await.map(AccessToken::from)
 }
 
 pub fn spawn_token_refresh_task(app: AppHandle) {
-    let _ = app;
-    rt::spawn(async move {
-        loop {
-            sleep(std::time::Duration::from_secs(300)).await;
-            if let Ok(tokens) = get_tokens().await {
-                let now = Utc::now().timestamp();
-                if now + 300 >= tokens.expires_at {
-                    let _ = refresh_access_token().await;
-                }
-            }
-        }
-    });
+    SPOTIFY.spawn_refresh_task(app);
 }
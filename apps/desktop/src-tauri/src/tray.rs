@@ -0,0 +1,279 @@
+use crate::player::{self, PlaybackSnapshot, PlayerCommand, PlayerState};
+use crate::{custom_themes, settings};
+use tauri::async_runtime as rt;
+use tauri::menu::{
+    CheckMenuItem, IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu,
+};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Wry};
+
+pub const MAIN_WINDOW_LABEL: &str = "main";
+
+const TRAY_ID: &str = "minify-tray";
+const NOW_PLAYING_ID: &str = "now_playing";
+const PLAY_PAUSE_ID: &str = "play_pause";
+const NEXT_ID: &str = "next";
+const PREVIOUS_ID: &str = "previous";
+const TOGGLE_WINDOW_ID: &str = "toggle_window";
+const QUIT_ID: &str = "quit";
+const LAYOUT_PREFIX: &str = "layout:";
+const THEME_PREFIX: &str = "theme:";
+
+const LAYOUTS: &[(&str, &str)] = &[
+    ("LayoutA", "Layout A"),
+    ("LayoutB", "Layout B"),
+    ("LayoutC", "Layout C"),
+    ("LayoutD", "Layout D"),
+    ("LayoutE", "Layout E"),
+    ("LayoutF", "Layout F"),
+];
+
+struct TrayState {
+    now_playing: MenuItem<Wry>,
+    play_pause: MenuItem<Wry>,
+    layouts: Vec<(String, CheckMenuItem<Wry>)>,
+    themes: Vec<(String, CheckMenuItem<Wry>)>,
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn now_playing_label(snapshot: &PlaybackSnapshot) -> String {
+    match (&snapshot.track, snapshot.artist_names()) {
+        (Some(track), Some(artists)) if !artists.is_empty() => {
+            format!("{} — {}", track.name, artists)
+        }
+        (Some(track), _) => track.name.clone(),
+        _ => "Nothing playing".to_string(),
+    }
+}
+
+fn check_items(
+    app: &AppHandle,
+    prefix: &str,
+    entries: &[(String, String)],
+    selected: &str,
+) -> tauri::Result<Vec<(String, CheckMenuItem<Wry>)>> {
+    entries
+        .iter()
+        .map(|(value, label)| {
+            let item = CheckMenuItem::with_id(
+                app,
+                format!("{prefix}{value}"),
+                label,
+                true,
+                value == selected,
+                None::<&str>,
+            )?;
+            Ok((value.clone(), item))
+        })
+        .collect()
+}
+
+fn as_menu_items(items: &[(String, CheckMenuItem<Wry>)]) -> Vec<&dyn IsMenuItem<Wry>> {
+    items
+        .iter()
+        .map(|(_, item)| item as &dyn IsMenuItem<Wry>)
+        .collect()
+}
+
+fn select_check_item(items: &[(String, CheckMenuItem<Wry>)], selected: &str) {
+    for (value, item) in items {
+        let _ = item.set_checked(value == selected);
+    }
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW_LABEL) {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+pub fn toggle_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW_LABEL) else {
+        return;
+    };
+    if window.is_visible().unwrap_or(false) {
+        let _ = window.hide();
+    } else {
+        show_main_window(app);
+    }
+}
+
+pub fn is_layout(name: &str) -> bool {
+    LAYOUTS.iter().any(|(value, _)| *value == name)
+}
+
+pub fn select_layout(app: &AppHandle, layout: &str) {
+    if let Some(state) = app.try_state::<TrayState>() {
+        select_check_item(&state.layouts, layout);
+    }
+    player::send_command(
+        app,
+        PlayerCommand::SetLayout {
+            layout: layout.to_string(),
+        },
+    );
+}
+
+pub fn cycle_layout(app: &AppHandle) {
+    let current = settings::read_settings(app.clone()).layout;
+    let next = LAYOUTS
+        .iter()
+        .position(|(value, _)| *value == current)
+        .map(|index| (index + 1) % LAYOUTS.len())
+        .unwrap_or(0);
+    select_layout(app, LAYOUTS[next].0);
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    match id {
+        PLAY_PAUSE_ID => player::send_command(app, PlayerCommand::PlayPause),
+        NEXT_ID => player::send_command(app, PlayerCommand::Next),
+        PREVIOUS_ID => player::send_command(app, PlayerCommand::Previous),
+        TOGGLE_WINDOW_ID => toggle_main_window(app),
+        QUIT_ID => app.exit(0),
+        _ => {
+            let Some(state) = app.try_state::<TrayState>() else {
+                return;
+            };
+            if let Some(layout) = id.strip_prefix(LAYOUT_PREFIX) {
+                select_layout(app, layout);
+            } else if let Some(theme) = id.strip_prefix(THEME_PREFIX) {
+                select_check_item(&state.themes, theme);
+                player::send_command(
+                    app,
+                    PlayerCommand::SetTheme {
+                        theme: theme.to_string(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+fn apply_snapshot(app: &AppHandle, snapshot: &PlaybackSnapshot) {
+    let label = now_playing_label(snapshot);
+
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!("MiniFy\n{label}")));
+    }
+
+    if let Some(state) = app.try_state::<TrayState>() {
+        let _ = state.now_playing.set_text(label);
+        let _ = state
+            .play_pause
+            .set_text(if snapshot.is_playing { "Pause" } else { "Play" });
+    }
+}
+
+fn spawn_now_playing_listener(app: AppHandle) {
+    let mut receiver = app.state::<PlayerState>().subscribe();
+    rt::spawn(async move {
+        let mut last_key: Option<(Option<String>, bool)> = None;
+        while receiver.changed().await.is_ok() {
+            let snapshot = receiver.borrow_and_update().clone();
+            let key = (
+                snapshot.track.as_ref().map(|track| track.id.clone()),
+                snapshot.is_playing,
+            );
+            if last_key.as_ref() == Some(&key) {
+                continue;
+            }
+            last_key = Some(key);
+            apply_snapshot(&app, &snapshot);
+        }
+    });
+}
+
+pub fn init_tray(app: &AppHandle) -> tauri::Result<()> {
+    let current = settings::read_settings(app.clone());
+
+    let layout_entries: Vec<(String, String)> = LAYOUTS
+        .iter()
+        .map(|(value, label)| (value.to_string(), label.to_string()))
+        .collect();
+    let mut theme_entries: Vec<(String, String)> = custom_themes::BUILTIN_THEMES
+        .iter()
+        .map(|(name, _)| (name.to_string(), capitalize(name)))
+        .collect();
+    theme_entries.extend(
+        custom_themes::load_custom_themes(app.clone())
+            .into_iter()
+            .map(|theme| (format!("custom:{}", theme.name), theme.name)),
+    );
+
+    let now_playing =
+        MenuItem::with_id(app, NOW_PLAYING_ID, "Nothing playing", false, None::<&str>)?;
+    let play_pause = MenuItem::with_id(app, PLAY_PAUSE_ID, "Play", true, None::<&str>)?;
+    let next = MenuItem::with_id(app, NEXT_ID, "Next", true, None::<&str>)?;
+    let previous = MenuItem::with_id(app, PREVIOUS_ID, "Previous", true, None::<&str>)?;
+    let toggle_window = MenuItem::with_id(
+        app,
+        TOGGLE_WINDOW_ID,
+        "Show/Hide Player",
+        true,
+        None::<&str>,
+    )?;
+    let quit = MenuItem::with_id(app, QUIT_ID, "Quit MiniFy", true, None::<&str>)?;
+
+    let layouts = check_items(app, LAYOUT_PREFIX, &layout_entries, &current.layout)?;
+    let themes = check_items(app, THEME_PREFIX, &theme_entries, &current.theme)?;
+    let layout_menu = Submenu::with_items(app, "Layout", true, &as_menu_items(&layouts))?;
+    let theme_menu = Submenu::with_items(app, "Theme", true, &as_menu_items(&themes))?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &now_playing,
+            &PredefinedMenuItem::separator(app)?,
+            &play_pause,
+            &next,
+            &previous,
+            &PredefinedMenuItem::separator(app)?,
+            &toggle_window,
+            &layout_menu,
+            &theme_menu,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("MiniFy")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(handle_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                toggle_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    app.manage(TrayState {
+        now_playing,
+        play_pause,
+        layouts,
+        themes,
+    });
+    spawn_now_playing_listener(app.clone());
+
+    Ok(())
+}
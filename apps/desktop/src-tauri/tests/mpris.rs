@@ -0,0 +1,160 @@
+//! Runs the MPRIS service on a private D-Bus daemon and talks to it like a
+//! desktop media applet would. Skipped when `dbus-daemon` isn't installed.
+
+#![cfg(target_os = "linux")]
+
+use minify_lib::mpris::{self, MprisAction};
+use minify_lib::player::{PlaybackSnapshot, PlayerCommand};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use zbus::proxy::{Builder, CacheProperties};
+use zbus::zvariant::OwnedValue;
+use zbus::{connection, Proxy};
+
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// A `dbus-daemon` that only lives as long as the test.
+struct PrivateBus {
+    daemon: Child,
+    address: String,
+    dir: PathBuf,
+}
+
+impl PrivateBus {
+    fn start() -> Option<PrivateBus> {
+        let dir = std::env::temp_dir().join(format!("minify-mpris-tests-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok()?;
+        let config = dir.join("bus.conf");
+        std::fs::write(
+            &config,
+            format!(
+                r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:dir={}</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#,
+                dir.display()
+            ),
+        )
+        .ok()?;
+
+        let mut daemon = match Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .args(["--nofork", "--print-address=1"])
+            .stdout(Stdio::piped())
+            .spawn()
+        {
+            Ok(daemon) => daemon,
+            Err(e) => {
+                eprintln!("Skipping MPRIS test, can't start dbus-daemon: {}", e);
+                return None;
+            }
+        };
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+        Some(PrivateBus {
+            daemon,
+            address: address.trim().to_string(),
+            dir,
+        })
+    }
+
+    fn connection(&self) -> connection::Builder<'static> {
+        connection::Builder::address(self.address.as_str()).expect("bus address")
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn playing_snapshot() -> PlaybackSnapshot {
+    let track = serde_json::from_value(serde_json::json!({
+        "id": "track1",
+        "name": "Test Song",
+        "duration_ms": 180000,
+        "artists": [{ "id": "artist1", "name": "Test Artist" }],
+        "album": { "id": "album1", "name": "Test Album", "images": [] },
+        "uri": "spotify:track:track1",
+        "provider": "spotify",
+    }))
+    .expect("track");
+    PlaybackSnapshot {
+        track: Some(track),
+        is_playing: true,
+        progress_ms: 1000,
+        volume_percent: Some(40),
+        observed_at: None,
+    }
+}
+
+#[tokio::test]
+async fn serves_metadata_status_and_commands_on_a_private_bus() {
+    let Some(bus) = PrivateBus::start() else {
+        return;
+    };
+    let (snapshots_tx, snapshots_rx) = watch::channel(playing_snapshot());
+    let (actions_tx, mut actions_rx) = mpsc::unbounded_channel();
+    let _service = mpris::serve(bus.connection(), snapshots_rx, actions_tx)
+        .await
+        .expect("serve MPRIS");
+
+    let client = bus.connection().build().await.expect("client connection");
+    // Without the cache every read is a call, so it sees the latest snapshot.
+    let player: Proxy = Builder::new(&client)
+        .destination(mpris::BUS_NAME)
+        .and_then(|b| b.path(OBJECT_PATH))
+        .and_then(|b| b.interface(PLAYER_INTERFACE))
+        .expect("player proxy")
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+        .expect("player proxy");
+
+    let status: String = player.get_property("PlaybackStatus").await.expect("status");
+    assert_eq!(status, "Playing");
+
+    let metadata: HashMap<String, OwnedValue> =
+        player.get_property("Metadata").await.expect("metadata");
+    let title = String::try_from(metadata["xesam:title"].clone()).expect("title");
+    assert_eq!(title, "Test Song");
+    let artists = Vec::<String>::try_from(metadata["xesam:artist"].clone()).expect("artists");
+    assert_eq!(artists, ["Test Artist"]);
+    let length = i64::try_from(metadata["mpris:length"].clone()).expect("length");
+    assert_eq!(length, 180_000_000);
+    let url = String::try_from(metadata["xesam:url"].clone()).expect("url");
+    assert_eq!(url, "https://open.spotify.com/track/track1");
+
+    let _: () = player.call("PlayPause", &()).await.expect("PlayPause call");
+    let action = tokio::time::timeout(Duration::from_secs(5), actions_rx.recv())
+        .await
+        .expect("action in time")
+        .expect("action");
+    assert!(
+        matches!(action, MprisAction::Command(PlayerCommand::PlayPause)),
+        "unexpected action: {action:?}"
+    );
+
+    snapshots_tx.send_modify(|snapshot| snapshot.is_playing = false);
+    let status: String = player.get_property("PlaybackStatus").await.expect("status");
+    assert_eq!(status, "Paused");
+}
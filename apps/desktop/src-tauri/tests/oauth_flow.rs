@@ -0,0 +1,275 @@
+//! Drives the OAuth engine end to end against the bundled mock server: the
+//! authorization redirect, the loopback callback, the token exchange with PKCE,
+//! refreshes and sign-out. Each test has its own provider and mock server so
+//! they can run in parallel. Credentials stay in memory and endpoints are
+//! pointed at the mock without touching the process environment.
+
+#![cfg(debug_assertions)]
+
+use minify_lib::endpoints::{Endpoint, Service};
+use minify_lib::mock_oauth::{self, MockOAuthServer, MOCK_USER_ID};
+use minify_lib::oauth::{Account, CallbackPorts, ClientAuth, OAuthClient, OAuthProvider};
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::test::{mock_app, MockRuntime};
+use tauri::{AppHandle, Listener};
+
+fn mock_account(me: &serde_json::Value) -> Option<Account> {
+    Some(Account {
+        id: me["id"].as_str()?.to_string(),
+        display_name: me["display_name"].as_str()?.to_string(),
+        avatar_url: None,
+    })
+}
+
+const fn test_provider(
+    id: &'static str,
+    service: &'static Service,
+    client_auth: ClientAuth,
+) -> OAuthProvider {
+    let embedded_client_secret = match client_auth {
+        ClientAuth::Pkce => None,
+        ClientAuth::ClientSecret => Some("test-secret"),
+    };
+    OAuthProvider {
+        id,
+        name: "Test",
+        event_prefix: id,
+        authorize_url: Endpoint::new(service, "/authorize"),
+        token_url: Endpoint::new(service, "/api/token"),
+        scopes: &["read", "write"],
+        feature_scopes: &[("extra", &["extra"])],
+        incremental_params: &[],
+        authorize_params: &[],
+        client_auth,
+        callback_ports: CallbackPorts::Ephemeral,
+        verify_url: Some(Endpoint::new(service, "/v1/me")),
+        revoke_url: Some(Endpoint::new(service, "/revoke")),
+        parse_account: Some(mock_account),
+        add_account_params: &[],
+        token_key_prefix: id,
+        client_key_prefix: id,
+        embedded_client_id: Some("test-client"),
+        embedded_client_secret,
+        accent_color: "#000",
+        accent_foreground: "white",
+    }
+}
+
+/// Starts the mock server and points `service` at it.
+async fn start_mock(service: &Service) -> MockOAuthServer {
+    mock_oauth::use_memory_credentials();
+    let server = MockOAuthServer::start(0).await.expect("mock server");
+    service.set_override(&server.base_url());
+    server
+}
+
+/// Runs a login like a browser would: open the authorization URL, follow the
+/// redirect to the callback server and return the page it shows.
+async fn log_in(client: &'static OAuthClient, app: &AppHandle<MockRuntime>) -> String {
+    let auth_url = client.begin_flow(app.clone()).await.expect("begin flow");
+    reqwest::get(auth_url)
+        .await
+        .expect("callback reachable")
+        .text()
+        .await
+        .expect("callback page")
+}
+
+fn wait_for_event(app: &AppHandle<MockRuntime>, event: &str) -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    app.listen_any(event.to_string(), move |e| {
+        let _ = tx.send(e.payload().to_string());
+    });
+    rx
+}
+
+static PKCE_SERVICE: Service = Service {
+    env: "MINIFY_TEST_PKCE_URL",
+    base_url: "http://127.0.0.1:9",
+};
+static PKCE: OAuthClient =
+    OAuthClient::new(test_provider("test_pkce_", &PKCE_SERVICE, ClientAuth::Pkce));
+
+#[tokio::test]
+async fn pkce_login_completes_through_the_callback() {
+    let mock = start_mock(&PKCE_SERVICE).await;
+    let app = mock_app();
+    let success = wait_for_event(app.handle(), "test_pkce_-success");
+
+    let page = log_in(&PKCE, app.handle()).await;
+    assert!(page.contains("Test Connected!"), "unexpected page: {page}");
+    success
+        .recv_timeout(Duration::from_secs(5))
+        .expect("success event");
+
+    let tokens = PKCE.tokens().await.expect("stored tokens");
+    assert!(tokens.access_token.starts_with("access-"));
+    assert!(mock.is_refresh_token_active(&tokens.refresh_token));
+    assert_eq!(tokens.scopes, ["read", "write"]);
+    assert_eq!(mock.token_requests(), 1);
+
+    let accounts = PKCE.account_list().await;
+    assert_eq!(accounts.active_account.as_deref(), Some(MOCK_USER_ID));
+    assert!(!PKCE.has_feature("extra").await.unwrap());
+
+    PKCE.clear(app.handle()).await.expect("sign out");
+}
+
+static STATE_SERVICE: Service = Service {
+    env: "MINIFY_TEST_STATE_URL",
+    base_url: "http://127.0.0.1:9",
+};
+static STATE: OAuthClient =
+    OAuthClient::new(test_provider("test_state_", &STATE_SERVICE, ClientAuth::Pkce));
+
+#[tokio::test]
+async fn callback_with_a_foreign_state_is_rejected() {
+    let mock = start_mock(&STATE_SERVICE).await;
+    let app = mock_app();
+
+    let auth_url = STATE.begin_flow(app.handle().clone()).await.expect("begin flow");
+    let mut forged = url::Url::parse(&auth_url).unwrap();
+    let pairs: Vec<(String, String)> = forged
+        .query_pairs()
+        .map(|(k, v)| {
+            let v = if k == "state" { "forged".into() } else { v.into_owned() };
+            (k.into_owned(), v)
+        })
+        .collect();
+    forged.query_pairs_mut().clear().extend_pairs(pairs);
+
+    let page = reqwest::get(forged).await.unwrap().text().await.unwrap();
+    assert!(page.contains("login session has expired"), "unexpected page: {page}");
+    assert_eq!(mock.token_requests(), 0);
+    assert!(STATE.tokens().await.is_err());
+
+    STATE.cancel_flow();
+}
+
+static PASTE_SERVICE: Service = Service {
+    env: "MINIFY_TEST_PASTE_URL",
+    base_url: "http://127.0.0.1:9",
+};
+static PASTE: OAuthClient = OAuthClient::new(test_provider(
+    "test_paste_",
+    &PASTE_SERVICE,
+    ClientAuth::ClientSecret,
+));
+
+#[tokio::test]
+async fn pasted_redirect_needs_the_state_without_pkce() {
+    let mock = start_mock(&PASTE_SERVICE).await;
+    let app = mock_app();
+
+    // Stop at the redirect, as if the browser couldn't reach the callback.
+    let auth_url = PASTE.begin_flow(app.handle().clone()).await.expect("begin flow");
+    let browser = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let response = browser.get(auth_url).send().await.unwrap();
+    let redirect = response.headers()["location"].to_str().unwrap().to_string();
+    let redirect = url::Url::parse(&redirect).unwrap();
+    let code = redirect
+        .query_pairs()
+        .find(|(k, _)| k == "code")
+        .map(|(_, v)| v.into_owned())
+        .expect("code in redirect");
+
+    let mut forged = redirect.clone();
+    forged
+        .query_pairs_mut()
+        .clear()
+        .append_pair("code", &code)
+        .append_pair("state", "forged");
+    let err = PASTE
+        .complete_flow_manually(app.handle().clone(), forged.to_string())
+        .await
+        .unwrap_err();
+    assert!(err.contains("older login attempt"), "unexpected error: {err}");
+
+    let err = PASTE
+        .complete_flow_manually(app.handle().clone(), code)
+        .await
+        .unwrap_err();
+    assert!(err.contains("full URL"), "unexpected error: {err}");
+    assert_eq!(mock.token_requests(), 0);
+
+    PASTE
+        .complete_flow_manually(app.handle().clone(), redirect.to_string())
+        .await
+        .expect("pasted login");
+    assert_eq!(mock.token_requests(), 1);
+    assert!(PASTE.tokens().await.is_ok());
+
+    PASTE.clear(app.handle()).await.expect("sign out");
+}
+
+static REFRESH_SERVICE: Service = Service {
+    env: "MINIFY_TEST_REFRESH_URL",
+    base_url: "http://127.0.0.1:9",
+};
+static REFRESH: OAuthClient =
+    OAuthClient::new(test_provider("test_refresh_", &REFRESH_SERVICE, ClientAuth::Pkce));
+
+#[tokio::test]
+async fn refresh_rotates_access_and_expires_revoked_logins() {
+    let mock = start_mock(&REFRESH_SERVICE).await;
+    let app = mock_app();
+    log_in(&REFRESH, app.handle()).await;
+    let before = REFRESH.tokens().await.unwrap();
+
+    let after = REFRESH.refresh(app.handle()).await.expect("refresh");
+    assert_ne!(after.access_token, before.access_token);
+    assert_eq!(after.refresh_token, before.refresh_token);
+    assert_eq!(after.scopes, before.scopes);
+
+    let expired = wait_for_event(app.handle(), "auth-expired");
+    mock.revoke_refresh_token(&after.refresh_token);
+    assert!(REFRESH.refresh(app.handle()).await.is_err());
+    expired
+        .recv_timeout(Duration::from_secs(5))
+        .expect("auth-expired event");
+    assert!(REFRESH.tokens().await.is_err());
+
+    REFRESH.clear(app.handle()).await.expect("sign out");
+}
+
+static SECRET_SERVICE: Service = Service {
+    env: "MINIFY_TEST_SECRET_URL",
+    base_url: "http://127.0.0.1:9",
+};
+static SECRET: OAuthClient = OAuthClient::new(test_provider(
+    "test_secret_",
+    &SECRET_SERVICE,
+    ClientAuth::ClientSecret,
+));
+
+#[tokio::test]
+async fn sign_out_revokes_the_grant_and_wipes_tokens() {
+    let mock = start_mock(&SECRET_SERVICE).await;
+    let app = mock_app();
+    log_in(&SECRET, app.handle()).await;
+    let tokens = SECRET.tokens().await.expect("client secret login");
+
+    let report = SECRET.clear(app.handle()).await.expect("sign out");
+    assert!(report.revocation_supported);
+    assert_eq!(report.revoked, 1);
+    assert!(report.failed.is_empty());
+    assert!(!mock.is_refresh_token_active(&tokens.refresh_token));
+    assert!(SECRET.tokens().await.is_err());
+    assert!(SECRET.account_list().await.accounts.is_empty());
+}
+
+#[test]
+fn endpoints_follow_overrides_in_debug_builds() {
+    static SERVICE: Service = Service {
+        env: "MINIFY_TEST_ENDPOINT_URL",
+        base_url: "https://example.com",
+    };
+    let endpoint = Endpoint::new(&SERVICE, "/token");
+    assert_eq!(endpoint.url(), "https://example.com/token");
+    SERVICE.set_override("http://127.0.0.1:4000/");
+    assert_eq!(endpoint.url(), "http://127.0.0.1:4000/token");
+}
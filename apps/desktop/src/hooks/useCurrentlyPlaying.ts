@@ -1,9 +1,10 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useEffect, useRef, useState } from "react";
 import { useAIQueueStore } from "../lib/aiQueueStore";
 import { startAutoplayMonitor, stopAutoplayMonitor } from "../lib/playback/autoplayService";
-import { startKeepAlive, stopKeepAlive } from "../lib/playback/spotifyKeepAlive";
 import {
+  type CachedTrack,
   type LastPlayedTrack,
   type MusicProviderType,
   type ProviderPlaybackCache,
@@ -33,7 +34,38 @@ function updateDiscordPresence(
   });
 }
 
-function unifiedTrackToCache(track: UnifiedTrack, progressMs: number): LastPlayedTrack {
+/** What the backend knows about playback, sent with its playback events. */
+type PlaybackSnapshot = {
+  track: CachedTrack | null;
+  is_playing: boolean;
+  progress_ms: number;
+  volume_percent: number | null;
+};
+
+type ProgressUpdate = {
+  track_id: string | null;
+  progress_ms: number;
+  duration_ms: number | null;
+};
+
+/** The backend polls Spotify itself; YouTube plays in this webview and is reported from here. */
+const YOUTUBE_REPORT_MS = 3000;
+
+function reportPlaybackState(playbackState: PlaybackState | null): void {
+  invoke("report_playback_state", {
+    track: playbackState?.track ? unifiedTrackToCache(playbackState.track, 0).track : null,
+    isPlaying: playbackState?.isPlaying ?? false,
+    progressMs: Math.max(0, Math.floor(playbackState?.progressMs ?? 0)),
+  }).catch(() => {
+    // Native integrations are optional, never break polling because of them
+  });
+}
+
+function unifiedTrackToCache(
+  track: UnifiedTrack,
+  progressMs: number,
+  cachedAt = Date.now()
+): LastPlayedTrack {
   return {
     track: {
       id: track.id,
@@ -53,50 +85,66 @@ function unifiedTrackToCache(track: UnifiedTrack, progressMs: number): LastPlaye
       provider: track.provider,
     },
     progress_ms: progressMs,
-    cached_at: Date.now(),
+    cached_at: cachedAt,
   };
 }
 
-function cacheToPlaybackState(cache: LastPlayedTrack): PlaybackState {
-  const cachedProvider = cache.track.provider ?? "spotify";
-  const cachedUri = cache.track.uri ?? `spotify:track:${cache.track.id}`;
+function cachedTrackToUnified(track: CachedTrack): UnifiedTrack {
+  return {
+    id: track.id,
+    name: track.name,
+    durationMs: track.duration_ms,
+    artists: track.artists,
+    album: track.album,
+    uri: track.uri ?? `spotify:track:${track.id}`,
+    provider: track.provider ?? "spotify",
+  };
+}
 
+function cacheToPlaybackState(cache: LastPlayedTrack): PlaybackState {
   return {
     isPlaying: false,
     progressMs: cache.progress_ms,
-    track: {
-      id: cache.track.id,
-      name: cache.track.name,
-      durationMs: cache.track.duration_ms,
-      artists: cache.track.artists,
-      album: cache.track.album,
-      uri: cachedUri,
-      provider: cachedProvider,
-    },
+    track: cachedTrackToUnified(cache.track),
+  };
+}
+
+function snapshotToPlaybackState(snapshot: PlaybackSnapshot): PlaybackState {
+  return {
+    isPlaying: snapshot.is_playing,
+    progressMs: snapshot.progress_ms,
+    track: snapshot.track ? cachedTrackToUnified(snapshot.track) : null,
   };
 }
 
 let saveTimeout: ReturnType<typeof setTimeout> | null = null;
 let currentProviderCache: ProviderPlaybackCache = { spotify: null, youtube: null };
 
-async function saveTrackToProviderCache(
+let lastCached: LastPlayedTrack | null = null;
+
+// Progress events arrive every second, so this writes at most every two seconds
+// instead of postponing the write for as long as they keep coming.
+// cached_at only moves while the provider reports playback, so showing the
+// cached track at launch doesn't make it look recently played to resume.
+function saveTrackToProviderCache(
   provider: MusicProviderType,
   track: UnifiedTrack,
-  progressMs: number
-): Promise<void> {
-  const cached = unifiedTrackToCache(track, progressMs);
-  currentProviderCache[provider] = cached;
+  progressMs: number,
+  isPlaying: boolean
+): void {
+  const cachedAt = isPlaying ? Date.now() : currentProviderCache[provider]?.cached_at;
+  if (cachedAt === undefined) return;
+  lastCached = unifiedTrackToCache(track, progressMs, cachedAt);
+  currentProviderCache[provider] = lastCached;
 
-  if (saveTimeout) {
-    clearTimeout(saveTimeout);
-  }
+  if (saveTimeout) return;
 
   saveTimeout = setTimeout(async () => {
+    saveTimeout = null;
     await writeSettings({
       provider_playback_cache: currentProviderCache,
-      last_played_track: cached,
+      last_played_track: lastCached,
     });
-    saveTimeout = null;
   }, 2000);
 }
 
@@ -126,10 +174,11 @@ export interface CurrentPlayingState {
   provider: MusicProviderType | null;
 }
 
-export function useCurrentlyPlaying(pollMs = 3000) {
+export function useCurrentlyPlaying(reportMs = YOUTUBE_REPORT_MS) {
   const [state, setState] = useState<PlaybackState | null>(null);
   const [activeProvider, setActiveProvider] = useState<MusicProviderType | null>(null);
   const lastTrackRef = useRef<string | null>(null);
+  const currentTrackRef = useRef<UnifiedTrack | null>(null);
   const lastPlayingRef = useRef<boolean>(false);
   const lastAIQueueRef = useRef<boolean>(false);
   const initialLoadDone = useRef<boolean>(false);
@@ -161,9 +210,6 @@ export function useCurrentlyPlaying(pollMs = 3000) {
 
         // Start playback services
         startAutoplayMonitor();
-        if (provider === "spotify") {
-          startKeepAlive();
-        }
       } catch (err) {
         console.error("Failed to load cached track:", err);
       }
@@ -171,109 +217,146 @@ export function useCurrentlyPlaying(pollMs = 3000) {
 
     return () => {
       stopAutoplayMonitor();
-      stopKeepAlive();
     };
   }, []);
 
-  // Poll for current playback state
+  // Follow the playback events from the backend
   useEffect(() => {
     let mounted = true;
 
-    const load = async () => {
-      try {
-        const providerType = await getActiveProviderType();
-        const provider = await getActiveProvider();
+    const apply = (playbackState: PlaybackState) => {
+      const track = playbackState.track;
+      const hasActiveTrack = track !== null;
+
+      if (track) {
+        currentTrackRef.current = track;
+        saveTrackToProviderCache(
+          track.provider,
+          track,
+          playbackState.progressMs,
+          playbackState.isPlaying
+        );
+      }
 
-        if (!mounted) return;
+      const trackId = playbackState.track?.id ?? null;
+      const isPlaying = playbackState.isPlaying;
+      const aiQueueActive = useAIQueueStore.getState().isActive;
+
+      if (
+        trackId !== lastTrackRef.current ||
+        isPlaying !== lastPlayingRef.current ||
+        aiQueueActive !== lastAIQueueRef.current
+      ) {
+        lastTrackRef.current = trackId;
+        lastPlayingRef.current = isPlaying;
+        lastAIQueueRef.current = aiQueueActive;
+
+        const trackName = playbackState.track?.name ?? null;
+        const artistName = playbackState.track?.artists?.map((a) => a.name).join(", ") ?? null;
+        updateDiscordPresence(trackName, artistName, isPlaying, aiQueueActive);
+      }
 
-        setActiveProvider(providerType);
+      setState((prev) => {
+        if (!hasActiveTrack && !initialLoadDone.current) {
+          initialLoadDone.current = true;
+          return prev;
+        }
 
-        let playbackState: PlaybackState | null = null;
+        initialLoadDone.current = true;
 
-        try {
-          playbackState = await provider.getPlaybackState();
-        } catch (apiError) {
-          console.warn("API call failed, using cache:", apiError);
-          // API failed, try to use cached state for this provider
-          const cached = await getLastPlayedForProvider(providerType);
-          if (cached) {
-            playbackState = cacheToPlaybackState(cached);
+        if (hasActiveTrack) {
+          if (
+            prev?.track?.id === playbackState.track?.id &&
+            prev?.isPlaying === playbackState.isPlaying &&
+            prev?.progressMs === playbackState.progressMs
+          ) {
+            return prev;
           }
+          return playbackState;
         }
 
-        if (!mounted) return;
+        if (prev?.isPlaying) {
+          return { ...prev, isPlaying: false };
+        }
 
-        const hasActiveTrack = playbackState?.track !== null && playbackState?.track !== undefined;
+        return prev;
+      });
+    };
 
-        // Save to provider-specific cache
-        if (hasActiveTrack && playbackState?.track) {
-          saveTrackToProviderCache(
-            providerType,
-            playbackState.track,
-            playbackState.progressMs ?? 0
-          );
-        }
+    const applySnapshot = (snapshot: PlaybackSnapshot) => {
+      if (mounted) apply(snapshotToPlaybackState(snapshot));
+    };
 
-        const trackId = playbackState?.track?.id ?? null;
-        const isPlaying = playbackState?.isPlaying ?? false;
-        const aiQueueActive = useAIQueueStore.getState().isActive;
-
-        if (
-          trackId !== lastTrackRef.current ||
-          isPlaying !== lastPlayingRef.current ||
-          aiQueueActive !== lastAIQueueRef.current
-        ) {
-          lastTrackRef.current = trackId;
-          lastPlayingRef.current = isPlaying;
-          lastAIQueueRef.current = aiQueueActive;
-
-          const trackName = playbackState?.track?.name ?? null;
-          const artistName = playbackState?.track?.artists?.map((a) => a.name).join(", ") ?? null;
-          updateDiscordPresence(trackName, artistName, isPlaying, aiQueueActive);
-        }
+    const applyProgress = (update: ProgressUpdate) => {
+      const track = currentTrackRef.current;
+      if (!mounted || !track || track.id !== update.track_id) return;
+      saveTrackToProviderCache(track.provider, track, update.progress_ms, lastPlayingRef.current);
+      setState((prev) =>
+        prev?.track?.id === update.track_id ? { ...prev, progressMs: update.progress_ms } : prev
+      );
+    };
 
-        setState((prev) => {
-          if (!hasActiveTrack && !initialLoadDone.current) {
-            initialLoadDone.current = true;
-            return prev;
-          }
+    const setupListeners = async () => {
+      const unlistenTrack = await listen<PlaybackSnapshot>("track-changed", (event) =>
+        applySnapshot(event.payload)
+      );
+      const unlistenState = await listen<PlaybackSnapshot>("playback-state-changed", (event) =>
+        applySnapshot(event.payload)
+      );
+      const unlistenProgress = await listen<ProgressUpdate>("progress", (event) =>
+        applyProgress(event.payload)
+      );
+
+      // Catch up on what happened before the listeners were registered
+      invoke<PlaybackSnapshot>("get_playback_snapshot")
+        .then(applySnapshot)
+        .catch((e) => console.error("Error fetching playback state:", e));
+
+      return () => {
+        unlistenTrack();
+        unlistenState();
+        unlistenProgress();
+      };
+    };
 
-          initialLoadDone.current = true;
+    const cleanup = setupListeners();
 
-          if (hasActiveTrack && playbackState) {
-            if (
-              prev?.track?.id === playbackState?.track?.id &&
-              prev?.isPlaying === playbackState?.isPlaying &&
-              prev?.progressMs === playbackState?.progressMs
-            ) {
-              return prev;
-            }
-            return playbackState;
-          }
+    return () => {
+      mounted = false;
+      cleanup.then((fn) => fn());
+    };
+  }, []);
 
-          if (prev?.track && !prev?.isPlaying) {
-            return prev;
-          }
+  // Track the active provider, and report YouTube playback to the backend
+  useEffect(() => {
+    let mounted = true;
 
-          if (prev?.isPlaying) {
-            return { ...prev, isPlaying: false };
-          }
+    const load = async () => {
+      try {
+        const providerType = await getActiveProviderType();
+        if (!mounted) return;
+        setActiveProvider(providerType);
 
-          return prev;
-        });
+        if (providerType !== "youtube") return;
+
+        const provider = await getActiveProvider();
+        const playbackState = await provider.getPlaybackState();
+        if (mounted) {
+          reportPlaybackState(playbackState);
+        }
       } catch (e) {
         console.error("Error fetching playback state:", e);
       }
     };
 
     load();
-    const id = setInterval(load, pollMs);
+    const id = setInterval(load, reportMs);
 
     return () => {
       mounted = false;
       clearInterval(id);
     };
-  }, [pollMs]);
+  }, [reportMs]);
 
   return {
     track: state?.track ?? null,
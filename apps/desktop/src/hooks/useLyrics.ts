@@ -0,0 +1,119 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useRef, useState } from "react";
+import { getLyrics, type LyricLineUpdate, type Lyrics, type TrackLyrics } from "../lib/lyrics";
+
+/** The part of the backend's playback snapshot the lyrics need. */
+type PlaybackPosition = {
+  is_playing: boolean;
+  progress_ms: number;
+};
+
+type ProgressUpdate = {
+  track_id: string | null;
+  progress_ms: number;
+};
+
+/** How often the position moves on while a line with timed words is sung. */
+const WORD_TICK_MS = 50;
+
+/** Same as `Lyrics::line_at` in the backend, for views opened in the middle of a line. */
+function lineAt(lyrics: Lyrics | null, progressMs: number): number | null {
+  if (!lyrics?.synced) return null;
+  let index: number | null = null;
+  for (const [i, line] of lyrics.lines.entries()) {
+    if (line.start_ms > progressMs) break;
+    index = i;
+  }
+  return index;
+}
+
+/**
+ * Lyrics of the playing track and the line being sung. The backend sends a `lyric-line` event
+ * when the line changes; between events the position is interpolated here, so words can be
+ * highlighted while a line with timed words is sung.
+ */
+export function useLyrics() {
+  const [lyrics, setLyrics] = useState<TrackLyrics | null>(null);
+  const [lineIndex, setLineIndex] = useState<number | null>(null);
+  const [progressMs, setProgressMs] = useState<number>(0);
+  const [isPlaying, setIsPlaying] = useState<boolean>(false);
+  const trackIdRef = useRef<string | null>(null);
+  const anchorRef = useRef({ progressMs: 0, at: performance.now() });
+
+  useEffect(() => {
+    let mounted = true;
+
+    const anchor = (progress: number, playing: boolean) => {
+      if (!mounted) return;
+      anchorRef.current = { progressMs: progress, at: performance.now() };
+      setProgressMs(progress);
+      setIsPlaying(playing);
+    };
+
+    const applyLyrics = (current: TrackLyrics | null, progress: number) => {
+      if (!mounted) return;
+      trackIdRef.current = current?.track_id ?? null;
+      setLyrics(current);
+      setLineIndex(lineAt(current?.lyrics ?? null, progress));
+    };
+
+    const setupListeners = async () => {
+      const unlistenLyrics = await listen<TrackLyrics>("lyrics-changed", (event) =>
+        applyLyrics(event.payload, anchorRef.current.progressMs)
+      );
+      const unlistenLine = await listen<LyricLineUpdate>("lyric-line", (event) => {
+        if (!mounted || event.payload.track_id !== trackIdRef.current) return;
+        setLineIndex(event.payload.index);
+        anchorRef.current = { progressMs: event.payload.progress_ms, at: performance.now() };
+        setProgressMs(event.payload.progress_ms);
+      });
+      const unlistenTrack = await listen<PlaybackPosition>("track-changed", (event) =>
+        anchor(event.payload.progress_ms, event.payload.is_playing)
+      );
+      const unlistenState = await listen<PlaybackPosition>("playback-state-changed", (event) =>
+        anchor(event.payload.progress_ms, event.payload.is_playing)
+      );
+      const unlistenProgress = await listen<ProgressUpdate>("progress", (event) =>
+        anchor(event.payload.progress_ms, true)
+      );
+
+      // Catch up on what happened before the listeners were registered
+      Promise.all([getLyrics(), invoke<PlaybackPosition>("get_playback_snapshot")])
+        .then(([current, snapshot]) => {
+          anchor(snapshot.progress_ms, snapshot.is_playing);
+          applyLyrics(current, snapshot.progress_ms);
+        })
+        .catch((e) => console.error("Error fetching lyrics:", e));
+
+      return () => {
+        unlistenLyrics();
+        unlistenLine();
+        unlistenTrack();
+        unlistenState();
+        unlistenProgress();
+      };
+    };
+
+    const cleanup = setupListeners();
+
+    return () => {
+      mounted = false;
+      cleanup.then((fn) => fn());
+    };
+  }, []);
+
+  const hasWords = lineIndex !== null && (lyrics?.lyrics?.lines[lineIndex]?.words.length ?? 0) > 0;
+
+  // Interpolate the position only when the words need it
+  useEffect(() => {
+    if (!isPlaying || !hasWords) return;
+    const timer = setInterval(() => {
+      const { progressMs: anchored, at } = anchorRef.current;
+      setProgressMs(anchored + Math.round(performance.now() - at));
+    }, WORD_TICK_MS);
+    return () => clearInterval(timer);
+  }, [isPlaying, hasWords]);
+
+  return { lyrics, lineIndex, progressMs };
+}
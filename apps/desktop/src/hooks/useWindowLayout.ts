@@ -11,7 +11,8 @@ export type Layout =
   | "Settings"
   | "SearchSongs"
   | "AIDJ"
-  | "Volume";
+  | "Volume"
+  | "Lyrics";
 
 export default function useWindowLayout() {
   const setLayout = useCallback(async (layout: Layout) => {
@@ -1,8 +1,9 @@
 import { createAnthropic } from "@ai-sdk/anthropic";
 import { createGoogleGenerativeAI } from "@ai-sdk/google";
 import { createOpenAI } from "@ai-sdk/openai";
+import { invoke } from "@tauri-apps/api/core";
 import type { LanguageModelV1 } from "ai";
-import { type AIProviderConfig, type AIProviderType, getAIApiKey } from "./settingLib";
+import { type AIProviderConfig, type AIProviderType, hasAIApiKey } from "./settingLib";
 
 export const AI_DJ_SYSTEM_PROMPT = `You are an AI DJ assistant for MiniFy, a desktop music player app.
 
@@ -79,24 +80,67 @@ Only use AI Queue when continuous playback is EXPLICITLY requested.
 - When user asks to "play X", just play it immediately
 - Keep responses short and action-focused`;
 
-export function createAIModel(providerType: AIProviderType, apiKey: string): LanguageModelV1 {
+type AIProxyResponse = {
+  status: number;
+  headers: Record<string, string>;
+  body: string;
+};
+
+// The SDKs require a key; the real one is added by the backend
+const PROXIED_API_KEY = "stored-in-keyring";
+
+/**
+ * Fetch for the AI SDK that sends every request through the backend, which adds
+ * the API key from the keyring. The key itself never reaches the webview.
+ */
+function createProxiedFetch(provider: AIProviderType): typeof fetch {
+  return async (input, init) => {
+    const request = new Request(input, init);
+    const headers: Record<string, string> = {};
+    request.headers.forEach((value, key) => {
+      headers[key] = value;
+    });
+    const hasBody = request.method !== "GET" && request.method !== "HEAD";
+
+    const response = await invoke<AIProxyResponse>("ai_proxy_request", {
+      provider,
+      request: {
+        url: request.url,
+        method: request.method,
+        headers,
+        body: hasBody ? await request.text() : null,
+      },
+    });
+
+    const nullBody = response.status === 204 || response.status === 304;
+    return new Response(nullBody ? null : response.body, {
+      status: response.status,
+      headers: response.headers,
+    });
+  };
+}
+
+export function createAIModel(providerType: AIProviderType): LanguageModelV1 {
+  const apiKey = PROXIED_API_KEY;
+  const fetch = createProxiedFetch(providerType);
   switch (providerType) {
     case "openai": {
-      const openai = createOpenAI({ apiKey });
+      const openai = createOpenAI({ apiKey, fetch });
       return openai("gpt-4o-mini");
     }
     case "anthropic": {
-      const anthropic = createAnthropic({ apiKey });
+      const anthropic = createAnthropic({ apiKey, fetch });
       return anthropic("claude-3-haiku-20240307");
     }
     case "google": {
-      const google = createGoogleGenerativeAI({ apiKey });
+      const google = createGoogleGenerativeAI({ apiKey, fetch });
       return google("gemini-1.5-flash");
     }
     case "groq": {
       const groq = createOpenAI({
         apiKey,
         baseURL: "https://api.groq.com/openai/v1",
+        fetch,
       });
       return groq("llama-3.1-8b-instant");
     }
@@ -111,17 +155,16 @@ export function getActiveProvider(
   return providers.find((p) => p.provider === activeProvider && p.enabled) ?? null;
 }
 
-export async function getActiveProviderWithKey(
+export async function getConfiguredAIProvider(
   providers: AIProviderConfig[],
   activeProvider: AIProviderType | null
-): Promise<{ provider: AIProviderType; apiKey: string } | null> {
+): Promise<{ provider: AIProviderType } | null> {
   const config = getActiveProvider(providers, activeProvider);
   if (!config) return null;
 
   try {
-    const apiKey = await getAIApiKey(config.provider);
-    if (!apiKey) return null;
-    return { provider: config.provider, apiKey };
+    if (!(await hasAIApiKey(config.provider))) return null;
+    return { provider: config.provider };
   } catch {
     return null;
   }
@@ -17,7 +17,7 @@ import {
   playTracks as spotifyPlayTracks,
   searchTracks as spotifySearchTracks,
 } from "../ui/spotifyClient";
-import { createAIModel, getActiveProviderWithKey } from "./aiClient";
+import { createAIModel, getConfiguredAIProvider } from "./aiClient";
 import { type QueuedTrack, useAIQueueStore } from "./aiQueueStore";
 import { readSettings } from "./settingLib";
 
@@ -179,7 +179,7 @@ export async function fetchNextBatch(): Promise<QueuedTrack[]> {
   const store = useAIQueueStore.getState();
 
   const settings = await readSettings();
-  const aiProvider = await getActiveProviderWithKey(
+  const aiProvider = await getConfiguredAIProvider(
     settings.ai_providers,
     settings.active_ai_provider
   );
@@ -222,7 +222,7 @@ export async function fetchNextBatch(): Promise<QueuedTrack[]> {
       recentUris = new Set(recentTracks.map((t) => t.uri));
     }
 
-    const model = createAIModel(aiProvider.provider, aiProvider.apiKey);
+    const model = createAIModel(aiProvider.provider);
 
     const randomSeed = Math.random().toString(36).substring(2, 8);
 
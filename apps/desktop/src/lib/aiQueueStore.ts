@@ -1,3 +1,4 @@
+import { invoke } from "@tauri-apps/api/core";
 import { create } from "zustand";
 
 export interface QueuedTrack {
@@ -116,3 +117,26 @@ export const useAIQueueStore = create<AIQueueState>((set, get) => ({
       // Keep playedUris - don't reset history on queue stop
     }),
 }));
+
+// Mirror the queue to the backend so the local control API can push it to clients.
+useAIQueueStore.subscribe((state, previous) => {
+  if (
+    state.isActive === previous.isActive &&
+    state.isLoading === previous.isLoading &&
+    state.queue === previous.queue &&
+    state.currentIndex === previous.currentIndex &&
+    state.error === previous.error
+  ) {
+    return;
+  }
+
+  invoke("report_ai_queue", {
+    queue: {
+      is_active: state.isActive,
+      is_loading: state.isLoading,
+      queue: state.queue,
+      current_index: state.currentIndex,
+      error: state.error,
+    },
+  }).catch(() => {});
+});
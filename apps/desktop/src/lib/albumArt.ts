@@ -0,0 +1,14 @@
+import { convertFileSrc } from "@tauri-apps/api/core";
+
+/** Size of the album art thumbnails in track lists, in CSS pixels. */
+export const LIST_ART_SIZE = 44;
+
+/**
+ * Address of a cover in the backend's album art cache, scaled for `size` CSS pixels on this
+ * display. The backend downloads it once and serves it from disk afterwards, also offline.
+ */
+export function albumArtUrl(url: string | null | undefined, size: number): string | undefined {
+  if (!url) return undefined;
+  const pixels = Math.ceil(size * (window.devicePixelRatio || 1));
+  return convertFileSrc(`${pixels}/${url}`, "minify-art");
+}
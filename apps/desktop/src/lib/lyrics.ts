@@ -0,0 +1,47 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// The backend looks the lyrics up for every track and follows the playback position; see
+// lyrics.rs. These mirror the payloads of its `lyrics-changed` and `lyric-line` events.
+
+export type LyricWord = {
+  start_ms: number;
+  /** Includes the space after the word. */
+  text: string;
+};
+
+export type LyricLine = {
+  start_ms: number;
+  text: string;
+  /** Empty unless the lyrics time every word. */
+  words: LyricWord[];
+};
+
+export type Lyrics = {
+  synced: boolean;
+  lines: LyricLine[];
+};
+
+export type TrackLyrics = {
+  track_id: string;
+  /** "folder", or the provider the lyrics came from. */
+  source: string | null;
+  lyrics: Lyrics | null;
+};
+
+export type LyricLineUpdate = {
+  track_id: string;
+  index: number | null;
+  line: LyricLine | null;
+  progress_ms: number;
+};
+
+/** Online providers the backend knows, by the name used in `lyrics_providers`. */
+export const LYRICS_PROVIDERS = [{ id: "lrclib", name: "LRCLIB" }] as const;
+
+export function getLyrics(): Promise<TrackLyrics | null> {
+  return invoke<TrackLyrics | null>("get_lyrics");
+}
+
+export function clearLyricsCache(): Promise<void> {
+  return invoke("clear_lyrics_cache");
+}
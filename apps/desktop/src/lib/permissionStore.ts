@@ -0,0 +1,69 @@
+import { invoke } from "@tauri-apps/api/core";
+import { create } from "zustand";
+
+// Optional Spotify features whose scopes are only requested when first used.
+// Keys match the feature names in the backend's provider description.
+export type SpotifyFeature = "library-modify";
+
+const FEATURE_DESCRIPTIONS: Record<SpotifyFeature, string> = {
+  "library-modify": "MiniFy needs permission to save tracks to your Liked Songs.",
+};
+
+type PromptPhase = "hidden" | "prompt" | "waiting" | "error";
+
+interface PermissionState {
+  phase: PromptPhase;
+  feature: SpotifyFeature | null;
+  errorMsg: string;
+
+  prompt: (feature: SpotifyFeature) => void;
+  grant: () => Promise<void>;
+  dismiss: () => void;
+}
+
+export const usePermissionStore = create<PermissionState>((set, get) => ({
+  phase: "hidden",
+  feature: null,
+  errorMsg: "",
+
+  prompt: (feature) => {
+    // Keep an in-flight consent flow instead of restarting it.
+    if (get().phase === "waiting") return;
+    set({ phase: "prompt", feature, errorMsg: "" });
+  },
+
+  grant: async () => {
+    const { feature } = get();
+    if (!feature) return;
+    set({ phase: "waiting", errorMsg: "" });
+    try {
+      // Resolves once the browser login is open; the prompt closes on oauth-success.
+      await invoke("request_spotify_feature", { feature });
+    } catch (err) {
+      set({ phase: "error", errorMsg: String(err) });
+    }
+  },
+
+  dismiss: () => {
+    if (get().phase === "waiting") {
+      void invoke("cancel_oauth_flow");
+    }
+    set({ phase: "hidden", feature: null, errorMsg: "" });
+  },
+}));
+
+export function describeFeature(feature: SpotifyFeature): string {
+  return FEATURE_DESCRIPTIONS[feature];
+}
+
+/**
+ * Resolves true when every scope of the feature is granted. Otherwise shows the
+ * grant prompt and resolves false so callers can skip the action.
+ */
+export async function ensureSpotifyFeature(feature: SpotifyFeature): Promise<boolean> {
+  const granted = await invoke<boolean>("has_spotify_feature", { feature }).catch(() => false);
+  if (!granted) {
+    usePermissionStore.getState().prompt(feature);
+  }
+  return granted;
+}
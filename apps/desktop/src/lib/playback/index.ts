@@ -1,4 +1,4 @@
 export * from "./autoplayService";
 export * from "./playbackQueueService";
 export * from "./playbackQueueStore";
-export * from "./spotifyKeepAlive";
+export * from "./spotifyDevices";
@@ -0,0 +1,57 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { getActiveProviderType } from "../../providers";
+import { type PlayerDevice, play as spotifyPlay } from "../../ui/spotifyClient";
+
+// The backend keeps a Spotify Connect device active, moves playback to the
+// preferred device when it is switched on and restores each device's volume.
+
+export interface PreferredDevice {
+  id: string;
+  name: string;
+}
+
+export interface DeviceStatus {
+  active: PlayerDevice | null;
+  devices: PlayerDevice[];
+  preferred: PreferredDevice | null;
+  last_successful_ping: number;
+  consecutive_failures: number;
+}
+
+export function getDeviceStatus(): Promise<DeviceStatus> {
+  return invoke<DeviceStatus>("get_device_status");
+}
+
+export function listDevices(): Promise<PlayerDevice[]> {
+  return invoke<PlayerDevice[]>("list_devices");
+}
+
+// null forgets the preferred device
+export function setPreferredDevice(deviceId: string | null): Promise<void> {
+  return invoke("set_preferred_device", { deviceId });
+}
+
+export function onDeviceChanged(handler: (status: DeviceStatus) => void): Promise<UnlistenFn> {
+  return listen<DeviceStatus>("device-changed", (event) => handler(event.payload));
+}
+
+export async function ensureActiveDevice(): Promise<boolean> {
+  const providerType = await getActiveProviderType();
+  if (providerType !== "spotify") return true;
+
+  try {
+    return await invoke<boolean>("ensure_active_device");
+  } catch (err) {
+    console.error("Failed to ensure active device:", err);
+    return false;
+  }
+}
+
+export async function recoverAndPlay(): Promise<boolean> {
+  const hasDevice = await ensureActiveDevice();
+  if (!hasDevice) return false;
+
+  spotifyPlay();
+  return true;
+}
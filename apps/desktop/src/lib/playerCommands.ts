@@ -0,0 +1,120 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { getLastPlayedForProvider } from "../hooks/useCurrentlyPlaying";
+import { getActiveProvider, getActiveProviderType } from "../providers";
+import { saveTrackToLibrary } from "../ui/spotifyClient";
+import { writeSettings } from "./settingLib";
+
+export type PlayerCommand =
+  | { action: "play_pause" }
+  | { action: "play" }
+  | { action: "pause" }
+  | { action: "next" }
+  | { action: "previous" }
+  | { action: "seek"; position_ms: number }
+  | { action: "set_volume"; volume: number }
+  | { action: "like_track" }
+  | { action: "set_layout"; layout: string }
+  | { action: "set_theme"; theme: string };
+
+export interface PlayerCommandHandlers {
+  onSetLayout: (layout: string) => void;
+  onSetTheme: (theme: string) => void;
+}
+
+async function startPlayback(): Promise<void> {
+  const provider = await getActiveProvider();
+  const playbackState = await provider.getPlaybackState();
+
+  if (!playbackState?.track) {
+    const cached = await getLastPlayedForProvider(await getActiveProviderType());
+    if (cached) {
+      await provider.playTrack(cached.track.uri, cached.progress_ms);
+      return;
+    }
+  }
+
+  provider.play();
+}
+
+async function togglePlayback(): Promise<void> {
+  const provider = await getActiveProvider();
+  const playbackState = await provider.getPlaybackState();
+
+  if (playbackState?.isPlaying) {
+    provider.pause();
+    return;
+  }
+
+  await startPlayback();
+}
+
+async function setVolume(volume: number): Promise<void> {
+  const provider = await getActiveProvider();
+  provider.setVolume(volume);
+
+  if (provider.type === "youtube") {
+    await writeSettings({ youtube_volume: volume });
+  }
+}
+
+async function likeCurrentTrack(): Promise<void> {
+  const provider = await getActiveProvider();
+  if (!provider.getCapabilities().hasLikedSongs) {
+    console.warn(`Liking tracks is not supported by ${provider.type}`);
+    return;
+  }
+
+  const track = await provider.getCurrentTrack();
+  if (track) {
+    await saveTrackToLibrary(track.id);
+  }
+}
+
+export async function executePlayerCommand(
+  command: PlayerCommand,
+  handlers: PlayerCommandHandlers
+): Promise<void> {
+  switch (command.action) {
+    case "play_pause":
+      await togglePlayback();
+      break;
+    case "play":
+      await startPlayback();
+      break;
+    case "pause":
+      (await getActiveProvider()).pause();
+      break;
+    case "next":
+      (await getActiveProvider()).nextTrack();
+      break;
+    case "previous":
+      (await getActiveProvider()).previousTrack();
+      break;
+    case "seek":
+      (await getActiveProvider()).seek(command.position_ms);
+      break;
+    case "set_volume":
+      await setVolume(command.volume);
+      break;
+    case "like_track":
+      await likeCurrentTrack();
+      break;
+    case "set_layout":
+      handlers.onSetLayout(command.layout);
+      break;
+    case "set_theme":
+      handlers.onSetTheme(command.theme);
+      break;
+  }
+}
+
+/**
+ * Executes transport and UI commands sent by native integrations (tray menu, media keys, ...).
+ */
+export function listenForPlayerCommands(handlers: PlayerCommandHandlers): Promise<UnlistenFn> {
+  return listen<PlayerCommand>("player-command", (event) => {
+    executePlayerCommand(event.payload, handlers).catch((err) => {
+      console.warn("Player command failed:", err);
+    });
+  });
+}
@@ -12,24 +12,6 @@ export async function saveAIApiKey(provider: AIProviderType, apiKey: string): Pr
   await invoke("save_ai_api_key", { provider, apiKey });
 }
 
-/**
- * Retrieves an AI API key from the secure keyring.
- * @param provider - The AI provider type
- * @returns The API key if found, null if not found
- * @throws Error for keyring access failures (not for missing keys)
- */
-export async function getAIApiKey(provider: AIProviderType): Promise<string | null> {
-  try {
-    return await invoke("get_ai_api_key", { provider });
-  } catch (error) {
-    const message = error instanceof Error ? error.message : String(error);
-    if (message.includes("not found") || message.includes("No password found")) {
-      return null;
-    }
-    throw error;
-  }
-}
-
 export async function hasAIApiKey(provider: AIProviderType): Promise<boolean> {
   return await invoke("has_ai_api_key", { provider });
 }
@@ -42,6 +24,56 @@ export async function clearAllAIKeys(): Promise<void> {
   await invoke("clear_all_ai_keys");
 }
 
+export type SpotifyAccount = {
+  id: string;
+  display_name: string;
+  avatar_url: string | null;
+};
+
+export type SpotifyAccountList = {
+  accounts: SpotifyAccount[];
+  active_account: string | null;
+};
+
+export async function listSpotifyAccounts(): Promise<SpotifyAccountList> {
+  return await invoke("list_spotify_accounts");
+}
+
+/** Opens the Spotify login with the account picker; the new account becomes active. */
+export async function addSpotifyAccount(): Promise<void> {
+  await invoke("add_spotify_account");
+}
+
+export async function switchSpotifyAccount(accountId: string): Promise<void> {
+  await invoke("switch_spotify_account", { accountId });
+}
+
+export async function removeSpotifyAccount(accountId: string): Promise<void> {
+  await invoke("remove_spotify_account", { accountId });
+}
+
+/** Result of a sign-out; local credentials are gone even when `failed` isn't empty. */
+export type SignOutReport = {
+  revocation_supported: boolean;
+  revoked: number;
+  failed: string[];
+};
+
+/** Signs out of a provider, revoking its grants where the provider supports it. */
+export async function signOut(provider: "spotify" | "youtube"): Promise<SignOutReport> {
+  return await invoke(provider === "spotify" ? "clear_credentials" : "clear_youtube_credentials");
+}
+
+/** Message for grants that may still be active at the provider after a sign-out. */
+export function describeSignOutFailures(
+  provider: string,
+  report: SignOutReport
+): string | null {
+  if (report.failed.length === 0) return null;
+  const reasons = report.failed.join("; ");
+  return `Signed out, but ${provider} couldn't be told to revoke access: ${reasons}`;
+}
+
 export type CachedTrackArtist = {
   id: string;
   name: string;
@@ -67,6 +99,7 @@ export type CachedTrack = {
   album: CachedTrackAlbum;
   uri: string;
   provider: MusicProviderType;
+  isrc?: string | null;
 };
 
 export type LastPlayedTrack = {
@@ -96,6 +129,25 @@ export type Settings = {
   last_played_track: LastPlayedTrack | null;
   provider_playback_cache: ProviderPlaybackCache | null;
   youtube_volume: number | null;
+  api_server_enabled: boolean;
+  api_server_port: number;
+  api_server_allow_lan: boolean;
+  now_playing_files_enabled: boolean;
+  now_playing_text_path: string | null;
+  now_playing_template: string;
+  now_playing_json_path: string | null;
+  now_playing_cover_path: string | null;
+  oauth_callback_ports: number[];
+  http_connect_timeout_secs: number;
+  http_read_timeout_secs: number;
+  http_proxy: string | null;
+  http_ca_certificate_path: string | null;
+  album_art_cache_mb: number;
+  resume_on_launch: boolean;
+  resume_rewind_secs: number;
+  resume_max_age_hours: number;
+  lyrics_folder: string | null;
+  lyrics_providers: string[];
 };
 
 export type CustomTheme = {
@@ -159,6 +211,25 @@ export async function readSettings(): Promise<Settings> {
       last_played_track: settings.last_played_track ?? null,
       provider_playback_cache: settings.provider_playback_cache ?? null,
       youtube_volume: settings.youtube_volume ?? null,
+      api_server_enabled: settings.api_server_enabled ?? false,
+      api_server_port: settings.api_server_port ?? 8975,
+      api_server_allow_lan: settings.api_server_allow_lan ?? false,
+      now_playing_files_enabled: settings.now_playing_files_enabled ?? false,
+      now_playing_text_path: settings.now_playing_text_path ?? null,
+      now_playing_template: settings.now_playing_template ?? "{artist} - {title}",
+      now_playing_json_path: settings.now_playing_json_path ?? null,
+      now_playing_cover_path: settings.now_playing_cover_path ?? null,
+      oauth_callback_ports: settings.oauth_callback_ports ?? [3000, 38917, 48231],
+      http_connect_timeout_secs: settings.http_connect_timeout_secs ?? 10,
+      http_read_timeout_secs: settings.http_read_timeout_secs ?? 30,
+      http_proxy: settings.http_proxy ?? null,
+      http_ca_certificate_path: settings.http_ca_certificate_path ?? null,
+      album_art_cache_mb: settings.album_art_cache_mb ?? 200,
+      resume_on_launch: settings.resume_on_launch ?? false,
+      resume_rewind_secs: settings.resume_rewind_secs ?? 5,
+      resume_max_age_hours: settings.resume_max_age_hours ?? 24,
+      lyrics_folder: settings.lyrics_folder ?? null,
+      lyrics_providers: settings.lyrics_providers ?? ["lrclib"],
     };
   } catch (err) {
     console.warn("Failed to read settings via Tauri, using defaults:", err);
@@ -178,6 +249,25 @@ export async function readSettings(): Promise<Settings> {
       last_played_track: null,
       provider_playback_cache: null,
       youtube_volume: null,
+      api_server_enabled: false,
+      api_server_port: 8975,
+      api_server_allow_lan: false,
+      now_playing_files_enabled: false,
+      now_playing_text_path: null,
+      now_playing_template: "{artist} - {title}",
+      now_playing_json_path: null,
+      now_playing_cover_path: null,
+      oauth_callback_ports: [3000, 38917, 48231],
+      http_connect_timeout_secs: 10,
+      http_read_timeout_secs: 30,
+      http_proxy: null,
+      http_ca_certificate_path: null,
+      album_art_cache_mb: 200,
+      resume_on_launch: false,
+      resume_rewind_secs: 5,
+      resume_max_age_hours: 24,
+      lyrics_folder: null,
+      lyrics_providers: ["lrclib"],
     };
   }
 }
@@ -48,7 +48,7 @@ class SpotifyProviderImpl implements MusicProvider {
 
   async isAuthenticated(): Promise<boolean> {
     try {
-      await invoke("get_tokens");
+      await invoke("get_access_token");
       return true;
     } catch {
       return false;
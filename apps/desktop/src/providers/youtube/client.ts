@@ -4,9 +4,9 @@ let cachedToken: string | null = null;
 let tokenExpiresAt = 0;
 const TOKEN_BUFFER_MS = 60_000;
 
-export interface YouTubeTokens {
+// Only the short-lived access token is exposed by the backend
+export interface YouTubeAccessToken {
   access_token: string;
-  refresh_token: string;
   expires_at: number;
 }
 
@@ -16,14 +16,14 @@ export async function getYouTubeAccessToken(): Promise<string> {
     return cachedToken;
   }
 
-  const tokens = await invoke<YouTubeTokens>("get_youtube_tokens");
+  const tokens = await invoke<YouTubeAccessToken>("get_youtube_access_token");
   cachedToken = tokens.access_token;
   tokenExpiresAt = tokens.expires_at * 1000;
   return cachedToken;
 }
 
 export async function refreshYouTubeToken(): Promise<string> {
-  const tokens = await invoke<YouTubeTokens>("refresh_youtube_access_token");
+  const tokens = await invoke<YouTubeAccessToken>("refresh_youtube_access_token");
   cachedToken = tokens.access_token;
   tokenExpiresAt = tokens.expires_at * 1000;
   return cachedToken;
@@ -1,3 +1,4 @@
+import { albumArtUrl } from "../../../lib/albumArt";
 import type { TrackInfoProps } from "../../../lib/trackInfoProps";
 import { getLargestImageUrl, saveTrackToLibrary } from "../../spotifyClient";
 import { TrackActions } from "../TrackDataComponent/TrackAction";
@@ -18,7 +19,7 @@ export function TrackInfoLayout({ track, variant, size, maxLength }: Props) {
     case "cover":
       return (
         <TrackCover
-          src={track ? (getLargestImageUrl(track.album.images) ?? undefined) : undefined}
+          src={track ? albumArtUrl(getLargestImageUrl(track.album.images), size || 64) : undefined}
           size={size || 64}
           isEmpty={!track}
         />
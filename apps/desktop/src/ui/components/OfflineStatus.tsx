@@ -0,0 +1,89 @@
+import { CloudSlash, Warning, X } from "@phosphor-icons/react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+
+type PendingOperation = {
+  id: number;
+  operation: { kind: "add_to_playlist" | "save_tracks" };
+  account: string | null;
+  queued_at: number;
+  attempts: number;
+};
+
+type OfflineStatusPayload = {
+  online: boolean;
+  pending: PendingOperation[];
+  conflicts: { operation: PendingOperation; error: string }[];
+};
+
+function plural(count: number, word: string): string {
+  return `${count} ${word}${count === 1 ? "" : "s"}`;
+}
+
+// Shows that the backend lost Spotify, how many changes wait in its outbox, and
+// the changes Spotify rejected when they were replayed.
+export default function OfflineStatus() {
+  const [status, setStatus] = useState<OfflineStatusPayload | null>(null);
+
+  useEffect(() => {
+    const setup = async () => {
+      const unlisten = await listen<OfflineStatusPayload>("offline-status", (event) => {
+        setStatus(event.payload);
+      });
+      invoke<OfflineStatusPayload>("get_offline_status")
+        .then(setStatus)
+        .catch(() => {});
+      return unlisten;
+    };
+
+    const cleanup = setup();
+    return () => {
+      cleanup.then((c) => c());
+    };
+  }, []);
+
+  if (!status) return null;
+
+  const pending = status.pending.length;
+  const conflicts = status.conflicts.length;
+  if (status.online && pending === 0 && conflicts === 0) return null;
+
+  const dismissConflicts = () => {
+    invoke("dismiss_outbox_conflicts").catch(() => {});
+  };
+
+  return (
+    <div
+      className="absolute bottom-2 left-1/2 z-[55] flex -translate-x-1/2 items-center gap-2 rounded-full border px-3 py-1 text-[11px] text-[--settings-text] shadow-lg"
+      style={{
+        background: "var(--settings-panel-bg)",
+        borderColor: "var(--settings-panel-border)",
+      }}
+    >
+      {conflicts > 0 ? (
+        <>
+          <Warning size={12} weight="fill" className="text-yellow-400" />
+          <span title={status.conflicts.map((c) => c.error).join("\n")}>
+            {plural(conflicts, "change")} couldn't be applied
+          </span>
+          <button
+            type="button"
+            onClick={dismissConflicts}
+            className="rounded-full p-0.5 hover:bg-[--settings-item-hover] cursor-pointer"
+          >
+            <X size={10} />
+          </button>
+        </>
+      ) : (
+        <>
+          {!status.online && <CloudSlash size={12} weight="fill" />}
+          <span>
+            {status.online ? "Syncing" : "Offline"}
+            {pending > 0 ? ` · ${plural(pending, "action")} pending` : ""}
+          </span>
+        </>
+      )}
+    </div>
+  );
+}
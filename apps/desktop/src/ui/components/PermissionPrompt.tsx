@@ -0,0 +1,104 @@
+import { LockKeyOpen, X } from "@phosphor-icons/react";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect } from "react";
+import { describeFeature, usePermissionStore } from "../../lib/permissionStore";
+import { clearSpotifyRequestCache } from "../spotifyClient";
+
+// Asks the user to grant a Spotify permission that an optional feature needs.
+// Granting re-opens the Spotify login for just the missing scopes; the overlay
+// closes when the login finishes and shows the error if it fails.
+export default function PermissionPrompt() {
+  const phase = usePermissionStore((s) => s.phase);
+  const feature = usePermissionStore((s) => s.feature);
+  const errorMsg = usePermissionStore((s) => s.errorMsg);
+  const grant = usePermissionStore((s) => s.grant);
+  const dismiss = usePermissionStore((s) => s.dismiss);
+
+  useEffect(() => {
+    const setup = async () => {
+      const unlistenSuccess = await listen("oauth-success", () => {
+        if (usePermissionStore.getState().phase !== "waiting") return;
+        clearSpotifyRequestCache();
+        usePermissionStore.setState({ phase: "hidden", feature: null });
+      });
+      const unlistenFailed = await listen<{ error: string }>("oauth-failed", (event) => {
+        if (usePermissionStore.getState().phase !== "waiting") return;
+        usePermissionStore.setState({ phase: "error", errorMsg: event.payload.error });
+      });
+      return () => {
+        unlistenSuccess();
+        unlistenFailed();
+      };
+    };
+
+    const cleanup = setup();
+    return () => {
+      cleanup.then((c) => c());
+    };
+  }, []);
+
+  if (phase === "hidden" || !feature) return null;
+
+  return (
+    <div
+      className="absolute inset-0 z-[60] flex items-center justify-center p-3"
+      style={{
+        background: "rgba(0, 0, 0, 0.55)",
+        backdropFilter: "blur(6px)",
+        borderRadius: "12px",
+      }}
+    >
+      <div
+        className="max-h-full w-full max-w-[92%] overflow-y-auto rounded-xl border p-4 text-[--settings-text] shadow-2xl"
+        style={{
+          background: "var(--settings-panel-bg)",
+          borderColor: "var(--settings-panel-border)",
+        }}
+      >
+        <div className="flex items-start gap-3">
+          <div
+            className="mt-0.5 flex h-9 w-9 shrink-0 items-center justify-center rounded-lg"
+            style={{ background: "color-mix(in srgb, var(--settings-accent) 22%, transparent)" }}
+          >
+            <LockKeyOpen size={20} weight="fill" color="var(--settings-accent)" />
+          </div>
+
+          <div className="min-w-0 flex-1">
+            <div className="text-sm font-semibold">
+              {phase === "error" ? "Permission not granted" : "Permission needed"}
+            </div>
+            <p className="mt-0.5 max-h-12 overflow-y-auto text-xs text-[--settings-text-muted]">
+              {phase === "error"
+                ? errorMsg || "The Spotify login didn't finish."
+                : phase === "waiting"
+                  ? "Finish the Spotify login in your browser."
+                  : describeFeature(feature)}
+            </p>
+
+            <div className="mt-3 flex items-center justify-end gap-2">
+              <button
+                type="button"
+                onClick={dismiss}
+                className="flex items-center gap-1 rounded-lg px-3 py-1.5 text-xs transition-colors hover:bg-[--settings-item-hover] cursor-pointer"
+              >
+                <X size={14} />
+                {phase === "waiting" ? "Cancel" : "Not now"}
+              </button>
+              {phase !== "waiting" && (
+                <button
+                  type="button"
+                  onClick={grant}
+                  className="flex items-center gap-1.5 rounded-lg px-3 py-1.5 text-xs font-medium text-black transition-transform hover:scale-[1.03] active:scale-[0.97] cursor-pointer"
+                  style={{ background: "var(--settings-accent)" }}
+                >
+                  <LockKeyOpen size={14} weight="bold" />
+                  {phase === "error" ? "Try again" : "Grant permission"}
+                </button>
+              )}
+            </div>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
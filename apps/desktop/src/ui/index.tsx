@@ -2,17 +2,22 @@ import { useEffect, useRef, useState } from "react";
 import "./global.css";
 
 import { LogicalPosition } from "@tauri-apps/api/dpi";
+import { listen } from "@tauri-apps/api/event";
 import { Menu, MenuItem, PredefinedMenuItem } from "@tauri-apps/api/menu";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 
 import { getActiveProvider as getActiveAIProvider } from "../lib/aiClient";
 import { useAIQueueStore } from "../lib/aiQueueStore";
+import { listenForPlayerCommands } from "../lib/playerCommands";
 import { loadCustomThemes, readSettings, writeSettings } from "../lib/settingLib";
 import { applyCustomThemeFromJson, applyThemeByName } from "../loader/themeLoader";
 import { getActiveProvider, getActiveProviderType } from "../providers";
 import { setYouTubePlayerRef, updateCurrentYouTubeTrack } from "../providers/youtube";
+import { clearYouTubeTokenCache } from "../providers/youtube/client";
 import AppUpdater from "./components/AppUpdater";
 import MusicVisualizer from "./components/MusicVisualizer";
+import OfflineStatus from "./components/OfflineStatus";
+import PermissionPrompt from "./components/PermissionPrompt";
 import { YouTubePlayer, type YouTubePlayerRef } from "./components/YouTubePlayer";
 import LayoutA from "./layouts/LayoutA";
 import LayoutB from "./layouts/LayoutB";
@@ -23,13 +28,23 @@ import LayoutF from "./layouts/LayoutF";
 
 import AddToPlaylistView from "./views/AddToPlaylistView";
 import AIDJView from "./views/AIDJView";
+import { clearSpotifyRequestCache } from "./spotifyClient";
 import Boot from "./views/Boot";
+import LyricsView from "./views/LyricsView";
 import PlaylistView from "./views/PlaylistView";
 import SearchBar from "./views/SearchBar";
 import Settings from "./views/Settings";
 import VolumeView from "./views/VolumeView";
 
-type AppView = "app" | "settings" | "search" | "aidj" | "playlist" | "addToPlaylist" | "volume";
+type AppView =
+  | "app"
+  | "settings"
+  | "search"
+  | "aidj"
+  | "playlist"
+  | "addToPlaylist"
+  | "volume"
+  | "lyrics";
 
 type AddToPlaylistTrack = {
   id: string;
@@ -118,6 +133,66 @@ export default function App() {
     document.body.style.opacity = String(windowOpacity / 100);
   }, [windowOpacity]);
 
+  // ---- Commands from the tray menu and other native integrations
+  useEffect(() => {
+    const unlisten = listenForPlayerCommands({
+      onSetLayout: (nextLayout) => {
+        setView("app");
+        setLayout(nextLayout);
+        void writeSettings({ layout: nextLayout });
+      },
+      onSetTheme: (nextTheme) => {
+        setTheme(nextTheme);
+        void writeSettings({ theme: nextTheme });
+      },
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // ---- Re-login when the backend reports a revoked refresh token
+  useEffect(() => {
+    const unlisten = listen<{ provider: "spotify" | "youtube"; error: string }>(
+      "auth-expired",
+      async (event) => {
+        const { provider } = event.payload;
+        if (provider === "youtube") {
+          clearYouTubeTokenCache();
+        } else {
+          clearSpotifyRequestCache();
+        }
+        if ((await getActiveProviderType()) !== provider) return;
+        setIsReconnect(true);
+        setBootStep(provider === "youtube" ? "youtube-setup" : "spotify-setup");
+        setFirstBootDone(false);
+        setView("app");
+      }
+    );
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // ---- Follow Spotify account switches; removing the last account logs out
+  useEffect(() => {
+    const unlisten = listen<{ provider: string; account: { id: string } | null }>(
+      "account-changed",
+      async (event) => {
+        if (event.payload.provider !== "spotify") return;
+        clearSpotifyRequestCache();
+        if (event.payload.account || (await getActiveProviderType()) !== "spotify") return;
+        setIsReconnect(true);
+        setBootStep("spotify-setup");
+        setFirstBootDone(false);
+        setView("app");
+      }
+    );
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // ---- Keyboard shortcuts
   useEffect(() => {
     if (!firstBootDone) return;
@@ -154,6 +229,10 @@ export default function App() {
             e.preventDefault();
             setView("volume");
             break;
+          case "y": // Ctrl+Y: Lyrics
+            e.preventDefault();
+            setView("lyrics");
+            break;
           case "d": {
             // Ctrl+D: AI DJ (if available)
             e.preventDefault();
@@ -203,6 +282,11 @@ export default function App() {
           action: () => setView("volume"),
         });
 
+        const lyricsItem = await MenuItem.new({
+          text: "Lyrics\t\t\t\tCtrl+Y",
+          action: () => setView("lyrics"),
+        });
+
         const separator = await PredefinedMenuItem.new({ item: "Separator" });
         const minimizeItem = await PredefinedMenuItem.new({ item: "Minimize" });
         const closeItem = await PredefinedMenuItem.new({ item: "CloseWindow" });
@@ -219,6 +303,7 @@ export default function App() {
               searchItem,
               playlistItem,
               volumeItem,
+              lyricsItem,
               aiDjItem,
               separator,
               minimizeItem,
@@ -232,6 +317,7 @@ export default function App() {
               searchItem,
               playlistItem,
               volumeItem,
+              lyricsItem,
               separator,
               minimizeItem,
               closeItem,
@@ -343,6 +429,9 @@ export default function App() {
     if (view === "volume") {
       return <VolumeView onBack={() => setView("app")} />;
     }
+    if (view === "lyrics") {
+      return <LyricsView onBack={() => setView("app")} />;
+    }
     if (view === "addToPlaylist") {
       return (
         <AddToPlaylistView
@@ -416,7 +505,9 @@ export default function App() {
         onVideoChange={handleYouTubeVideoChange}
         onVideoEnded={handleYouTubeVideoEnded}
       />
+      <OfflineStatus />
       <AppUpdater />
+      <PermissionPrompt />
     </div>
   );
 }
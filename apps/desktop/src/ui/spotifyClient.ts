@@ -1,8 +1,7 @@
 import { invoke } from "@tauri-apps/api/core";
+import { ensureSpotifyFeature, usePermissionStore } from "../lib/permissionStore";
 
-type FetchOptions = Omit<RequestInit, "headers"> & {
-  headers?: Record<string, string>;
-};
+type SpotifyPriority = "background" | "normal" | "interactive";
 
 export interface SimplifiedArtist {
   id: string;
@@ -29,126 +28,59 @@ export interface CurrentlyPlaying {
   item: SimplifiedTrack | null;
 }
 
-// Token cache to avoid repeated Tauri invocations
-let cachedToken: string | null = null;
-let tokenExpiresAt = 0;
-const TOKEN_BUFFER_MS = 60_000; // Refresh 1 min before expiry
-
-// Request deduplication for concurrent identical requests
+// Concurrent identical reads share one backend call
 const pendingRequests = new Map<string, Promise<unknown>>();
 
-export function clearSpotifyTokenCache(): void {
-  cachedToken = null;
-  tokenExpiresAt = 0;
+export function clearSpotifyRequestCache(): void {
   pendingRequests.clear();
 }
 
-async function getAccessToken(): Promise<string> {
-  const now = Date.now();
-  if (cachedToken && now < tokenExpiresAt - TOKEN_BUFFER_MS) {
-    return cachedToken;
+// Spotify is only called from the backend, which refreshes the token, waits
+// out rate limits and sends queued requests by priority.
+function read<T>(command: string, args: Record<string, unknown> = {}): Promise<T> {
+  const key = `${command}:${JSON.stringify(args)}`;
+  const pending = pendingRequests.get(key);
+  if (pending) {
+    return pending as Promise<T>;
   }
 
-  const tokens = await invoke<{ access_token: string; expires_in?: number }>("get_tokens");
-  cachedToken = tokens.access_token;
-  tokenExpiresAt = now + (tokens.expires_in ?? 3600) * 1000;
-  return cachedToken;
-}
-
-async function refreshToken(): Promise<string> {
-  const tokens = await invoke<{ access_token: string; expires_in?: number }>(
-    "refresh_access_token"
-  );
-  cachedToken = tokens.access_token;
-  tokenExpiresAt = Date.now() + (tokens.expires_in ?? 3600) * 1000;
-  return cachedToken;
+  const promise = invoke<T>(command, args);
+  pendingRequests.set(key, promise);
+  promise.finally(() => pendingRequests.delete(key));
+  return promise;
 }
 
-async function request<T>(url: string, init?: FetchOptions): Promise<T> {
-  const cacheKey = `${init?.method ?? "GET"}:${url}:${init?.body ?? ""}`;
-
-  // Only dedupe GET requests
-  if (!init?.method || init.method === "GET") {
-    const pending = pendingRequests.get(cacheKey);
-    if (pending) {
-      return pending as Promise<T>;
-    }
-  }
-
-  const doRequest = async (): Promise<T> => {
-    let token = await getAccessToken();
-    let res = await fetch(url, {
-      ...init,
-      headers: {
-        Authorization: `Bearer ${token}`,
-        "Content-Type": "application/json",
-        ...(init?.headers ?? {}),
-      },
-    });
-
-    if (res.status === 401) {
-      cachedToken = null;
-      token = await refreshToken();
-      res = await fetch(url, {
-        ...init,
-        headers: {
-          Authorization: `Bearer ${token}`,
-          "Content-Type": "application/json",
-          ...(init?.headers ?? {}),
-        },
-      });
-    }
-
-    if (!res.ok) {
-      const text = await res.text();
-      throw new Error(`${res.status} ${res.statusText}: ${text}`);
-    }
-
-    if (res.status === 204) {
-      return undefined as unknown as T;
-    }
-
-    return (await res.json()) as T;
-  };
-
-  const promise = doRequest();
-
-  if (!init?.method || init.method === "GET") {
-    pendingRequests.set(cacheKey, promise);
-    promise.finally(() => pendingRequests.delete(cacheKey));
-  }
-
-  return promise;
+// Web API endpoints without a typed backend command, e.g. `/v1/me/top/tracks`
+function apiGet<T>(path: string, priority?: SpotifyPriority): Promise<T> {
+  return read<T>("spotify_api_get", { path, priority });
 }
 
 // Fire-and-forget for player commands (non-blocking)
-function fireAndForget(url: string, init?: FetchOptions): void {
-  request<void>(url, init).catch((err) => {
+function fireAndForget(command: string, args?: Record<string, unknown>): void {
+  invoke(command, args).catch((err) => {
     console.warn("Player command failed:", err);
   });
 }
 
-export async function fetchCurrentlyPlaying(): Promise<CurrentlyPlaying> {
-  const data = await request<CurrentlyPlaying>(
-    "https://api.spotify.com/v1/me/player/currently-playing?additional_types=track"
-  );
-  return data;
+// null when nothing is playing
+export async function fetchCurrentlyPlaying(): Promise<CurrentlyPlaying | null> {
+  return read<CurrentlyPlaying | null>("spotify_currently_playing");
 }
 
 export function play(): void {
-  fireAndForget("https://api.spotify.com/v1/me/player/play", { method: "PUT" });
+  fireAndForget("spotify_play");
 }
 
 export function pause(): void {
-  fireAndForget("https://api.spotify.com/v1/me/player/pause", { method: "PUT" });
+  fireAndForget("spotify_pause");
 }
 
 export function nextTrack(): void {
-  fireAndForget("https://api.spotify.com/v1/me/player/next", { method: "POST" });
+  fireAndForget("spotify_next");
 }
 
 export function previousTrack(): void {
-  fireAndForget("https://api.spotify.com/v1/me/player/previous", { method: "POST" });
+  fireAndForget("spotify_previous");
 }
 
 // Debounced seek to avoid flooding API during scrubbing
@@ -160,7 +92,8 @@ let volumeTimeout: ReturnType<typeof setTimeout> | null = null;
 let lastVolumeValue = 0;
 
 export interface PlayerDevice {
-  id: string;
+  // null for devices the Web API can't control
+  id: string | null;
   name: string;
   type: string;
   volume_percent: number;
@@ -176,8 +109,7 @@ export interface PlayerState {
 
 export async function getPlayerState(): Promise<PlayerState | null> {
   try {
-    const data = await request<PlayerState>("https://api.spotify.com/v1/me/player");
-    return data;
+    return await read<PlayerState | null>("spotify_playback_state");
   } catch {
     return null;
   }
@@ -191,9 +123,7 @@ export function setVolume(volumePercent: number): void {
   }
 
   volumeTimeout = setTimeout(() => {
-    fireAndForget(`https://api.spotify.com/v1/me/player/volume?volume_percent=${lastVolumeValue}`, {
-      method: "PUT",
-    });
+    fireAndForget("spotify_set_volume", { volumePercent: lastVolumeValue });
     volumeTimeout = null;
   }, 50);
 }
@@ -206,15 +136,30 @@ export function seek(positionMs: number): void {
   }
 
   seekTimeout = setTimeout(() => {
-    const url = `https://api.spotify.com/v1/me/player/seek?position_ms=${lastSeekPosition}`;
-    fireAndForget(url, { method: "PUT" });
+    fireAndForget("spotify_seek", { positionMs: lastSeekPosition });
     seekTimeout = null;
   }, 100);
 }
 
-export async function saveTrackToLibrary(trackId: string): Promise<void> {
-  const url = `https://api.spotify.com/v1/me/tracks?ids=${encodeURIComponent(trackId)}`;
-  await request<void>(url, { method: "PUT" });
+// Library and playlist changes either reach Spotify right away or wait in the
+// backend's outbox, behind older changes or until Spotify is reachable again.
+export type ChangeOutcome = "applied" | "queued";
+
+// Saving needs user-library-modify, which older logins never granted. Without
+// it the user gets a grant prompt instead of a failed request, and null back.
+export async function saveTrackToLibrary(trackId: string): Promise<ChangeOutcome | null> {
+  if (!(await ensureSpotifyFeature("library-modify"))) return null;
+
+  try {
+    return await invoke<ChangeOutcome>("spotify_save_tracks", { ids: [trackId] });
+  } catch (err) {
+    // The grant can be withdrawn on Spotify's side after we recorded it.
+    if (String(err).includes("Insufficient client scope")) {
+      usePermissionStore.getState().prompt("library-modify");
+      return null;
+    }
+    throw err;
+  }
 }
 
 export function getLargestImageUrl(images: SimplifiedAlbum["images"]): string | null {
@@ -223,47 +168,32 @@ export function getLargestImageUrl(images: SimplifiedAlbum["images"]): string |
   return sorted[0]?.url ?? null;
 }
 
-interface SpotifySearchResponse {
-  tracks: {
-    items: SimplifiedTrack[];
-    total: number;
-  };
-}
-
 export async function searchTracks(query: string, limit: number): Promise<SimplifiedTrack[]> {
   if (!query.trim()) return [];
 
-  const url = `https://api.spotify.com/v1/search?q=${encodeURIComponent(query)}&type=track&limit=${limit}`;
-  const data = await request<SpotifySearchResponse>(url);
-  return data.tracks.items;
+  return read<SimplifiedTrack[]>("spotify_search_tracks", { query, limit });
 }
 
 export async function playTrack(trackUri: string, positionMs?: number): Promise<void> {
-  const body: { uris: string[]; position_ms?: number } = { uris: [trackUri] };
+  const request: { uris: string[]; position_ms?: number } = { uris: [trackUri] };
   if (positionMs !== undefined && positionMs > 0) {
-    body.position_ms = positionMs;
+    request.position_ms = Math.floor(positionMs);
   }
-  await request<void>("https://api.spotify.com/v1/me/player/play", {
-    method: "PUT",
-    body: JSON.stringify(body),
-  });
+  await invoke("spotify_play", { request });
 }
 
-interface RecentlyPlayedResponse {
-  items: Array<{
-    track: SimplifiedTrack;
-    played_at: string;
-  }>;
+interface PlayHistory {
+  track: SimplifiedTrack;
+  played_at: string;
 }
 
 export async function fetchRecentlyPlayed(limit: number): Promise<SimplifiedTrack[]> {
-  const url = `https://api.spotify.com/v1/me/player/recently-played?limit=${limit}`;
-  const data = await request<RecentlyPlayedResponse>(url);
+  const items = await read<PlayHistory[]>("spotify_recently_played", { limit });
 
   const seen = new Set<string>();
   const uniqueTracks: SimplifiedTrack[] = [];
 
-  for (const item of data.items) {
+  for (const item of items) {
     if (!seen.has(item.track.id)) {
       seen.add(item.track.id);
       uniqueTracks.push(item.track);
@@ -297,14 +227,14 @@ export async function fetchTopTracks(
   timeRange: TimeRange,
   limit: number
 ): Promise<SimplifiedTrack[]> {
-  const url = `https://api.spotify.com/v1/me/top/tracks?time_range=${timeRange}&limit=${limit}`;
-  const data = await request<TopTracksResponse>(url);
+  const path = `/v1/me/top/tracks?time_range=${timeRange}&limit=${limit}`;
+  const data = await apiGet<TopTracksResponse>(path);
   return data.items;
 }
 
 export async function fetchTopArtists(timeRange: TimeRange, limit: number): Promise<FullArtist[]> {
-  const url = `https://api.spotify.com/v1/me/top/artists?time_range=${timeRange}&limit=${limit}`;
-  const data = await request<TopArtistsResponse>(url);
+  const path = `/v1/me/top/artists?time_range=${timeRange}&limit=${limit}`;
+  const data = await apiGet<TopArtistsResponse>(path);
   return data.items;
 }
 
@@ -368,15 +298,13 @@ function extractTrackId(trackIdOrUri: string): string {
 export async function fetchAudioFeatures(trackIds: string[]): Promise<AudioFeatures[]> {
   if (trackIds.length === 0) return [];
   const ids = trackIds.slice(0, 100).join(",");
-  const url = `https://api.spotify.com/v1/audio-features?ids=${ids}`;
-  const data = await request<AudioFeaturesResponse>(url);
+  const data = await apiGet<AudioFeaturesResponse>(`/v1/audio-features?ids=${ids}`);
   return data.audio_features.filter((f): f is AudioFeatures => f !== null);
 }
 
 export async function fetchAudioAnalysis(trackIdOrUri: string): Promise<SpotifyAudioAnalysis> {
   const trackId = extractTrackId(trackIdOrUri);
-  const url = `https://api.spotify.com/v1/audio-analysis/${trackId}`;
-  return request<SpotifyAudioAnalysis>(url);
+  return apiGet<SpotifyAudioAnalysis>(`/v1/audio-analysis/${trackId}`);
 }
 
 export interface SpotifyRecommendation {
@@ -419,8 +347,9 @@ export async function fetchRecommendations(
   }
   urlParams.set("limit", (params.limit ?? 10).toString());
 
-  const url = `https://api.spotify.com/v1/recommendations?${urlParams.toString()}`;
-  const data = await request<SpotifyRecommendation>(url);
+  // Only autoplay asks for recommendations, so they never hold up the UI
+  const path = `/v1/recommendations?${urlParams.toString()}`;
+  const data = await apiGet<SpotifyRecommendation>(path, "background");
   return data.tracks ?? [];
 }
 
@@ -433,8 +362,7 @@ export interface UserProfile {
 }
 
 export async function fetchUserProfile(): Promise<UserProfile> {
-  const url = "https://api.spotify.com/v1/me";
-  return request<UserProfile>(url);
+  return apiGet<UserProfile>("/v1/me");
 }
 
 export interface SavedTracksResponse {
@@ -443,21 +371,16 @@ export interface SavedTracksResponse {
 }
 
 export async function fetchSavedTracksCount(): Promise<number> {
-  const url = "https://api.spotify.com/v1/me/tracks?limit=1";
-  const data = await request<SavedTracksResponse>(url);
+  const data = await read<SavedTracksResponse>("spotify_saved_tracks", { limit: 1, offset: 0 });
   return data.total;
 }
 
 export async function addToQueue(trackUri: string): Promise<void> {
-  const url = `https://api.spotify.com/v1/me/player/queue?uri=${encodeURIComponent(trackUri)}`;
-  await request<void>(url, { method: "POST" });
+  await invoke("spotify_add_to_queue", { uri: trackUri });
 }
 
 export async function playTracks(trackUris: string[]): Promise<void> {
-  await request<void>("https://api.spotify.com/v1/me/player/play", {
-    method: "PUT",
-    body: JSON.stringify({ uris: trackUris }),
-  });
+  await invoke("spotify_play", { request: { uris: trackUris } });
 }
 
 export interface SimplifiedPlaylist {
@@ -485,8 +408,7 @@ export async function fetchUserPlaylists(
   limit: number,
   offset: number
 ): Promise<{ playlists: SimplifiedPlaylist[]; total: number }> {
-  const url = `https://api.spotify.com/v1/me/playlists?limit=${limit}&offset=${offset}`;
-  const data = await request<UserPlaylistsResponse>(url);
+  const data = await read<UserPlaylistsResponse>("spotify_user_playlists", { limit, offset });
   return { playlists: data.items, total: data.total };
 }
 
@@ -505,20 +427,22 @@ export async function fetchPlaylistTracks(
   limit: number,
   offset: number
 ): Promise<{ tracks: SimplifiedTrack[]; total: number }> {
-  const url = `https://api.spotify.com/v1/playlists/${playlistId}/tracks?limit=${limit}&offset=${offset}`;
-  const data = await request<PlaylistTracksResponse>(url);
+  const data = await read<PlaylistTracksResponse>("spotify_playlist_tracks", {
+    playlistId,
+    limit,
+    offset,
+  });
   const tracks = data.items
     .filter((item) => item.track !== null)
     .map((item) => item.track as SimplifiedTrack);
   return { tracks, total: data.total };
 }
 
-export async function addTrackToPlaylist(playlistId: string, trackUri: string): Promise<void> {
-  const url = `https://api.spotify.com/v1/playlists/${playlistId}/tracks`;
-  await request<{ snapshot_id: string }>(url, {
-    method: "POST",
-    body: JSON.stringify({ uris: [trackUri] }),
-  });
+export async function addTrackToPlaylist(
+  playlistId: string,
+  trackUri: string
+): Promise<ChangeOutcome> {
+  return invoke<ChangeOutcome>("spotify_add_to_playlist", { playlistId, uris: [trackUri] });
 }
 
 export async function playPlaylistContext(
@@ -526,44 +450,34 @@ export async function playPlaylistContext(
   offset: number,
   trackUri?: string
 ): Promise<void> {
-  await request<void>("https://api.spotify.com/v1/me/player/play", {
-    method: "PUT",
-    body: JSON.stringify({
+  await invoke("spotify_play", {
+    request: {
       context_uri: `spotify:playlist:${playlistId}`,
       offset: trackUri ? { uri: trackUri } : { position: offset },
-    }),
+    },
   });
 }
 
 export async function playAlbumContext(albumId: string, offset: number): Promise<void> {
-  await request<void>("https://api.spotify.com/v1/me/player/play", {
-    method: "PUT",
-    body: JSON.stringify({
+  await invoke("spotify_play", {
+    request: {
       context_uri: `spotify:album:${albumId}`,
       offset: { position: offset },
-    }),
+    },
   });
 }
 
-interface DevicesResponse {
-  devices: PlayerDevice[];
-}
-
 export async function getDevices(): Promise<PlayerDevice[]> {
-  const data = await request<DevicesResponse>("https://api.spotify.com/v1/me/player/devices");
-  return data.devices ?? [];
+  return read<PlayerDevice[]>("spotify_devices");
 }
 
 export async function transferPlayback(deviceId: string, play: boolean): Promise<void> {
-  await request<void>("https://api.spotify.com/v1/me/player", {
-    method: "PUT",
-    body: JSON.stringify({ device_ids: [deviceId], play }),
-  });
+  await invoke("spotify_transfer_playback", { deviceId, play });
 }
 
 export async function getQueue(): Promise<{ currently_playing: SimplifiedTrack | null; queue: SimplifiedTrack[] }> {
-  const data = await request<{ currently_playing: SimplifiedTrack | null; queue: SimplifiedTrack[] }>(
-    "https://api.spotify.com/v1/me/player/queue"
+  return read<{ currently_playing: SimplifiedTrack | null; queue: SimplifiedTrack[] }>(
+    "spotify_queue",
+    { priority: "background" }
   );
-  return data;
 }
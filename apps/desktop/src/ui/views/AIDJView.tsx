@@ -13,7 +13,7 @@ import { encode } from "@toon-format/toon";
 import { type CoreMessage, generateText } from "ai";
 import { useCallback, useEffect, useRef, useState } from "react";
 import useWindowLayout from "../../hooks/useWindowLayout";
-import { AI_DJ_SYSTEM_PROMPT, createAIModel, getActiveProviderWithKey } from "../../lib/aiClient";
+import { AI_DJ_SYSTEM_PROMPT, createAIModel, getConfiguredAIProvider } from "../../lib/aiClient";
 import { startAIQueue, stopAIQueue } from "../../lib/aiQueueService";
 import { useAIQueueStore } from "../../lib/aiQueueStore";
 import { musicTools } from "../../lib/musicTools";
@@ -125,7 +125,7 @@ export default function AIDJView({ onBack }: AIDJViewProps) {
       setProviderType(type);
 
       const settings = await readSettings();
-      const provider = await getActiveProviderWithKey(
+      const provider = await getConfiguredAIProvider(
         settings.ai_providers,
         settings.active_ai_provider
       );
@@ -151,7 +151,7 @@ export default function AIDJView({ onBack }: AIDJViewProps) {
     if (!input.trim() || isLoading) return;
 
     const settings = await readSettings();
-    const provider = await getActiveProviderWithKey(
+    const provider = await getConfiguredAIProvider(
       settings.ai_providers,
       settings.active_ai_provider
     );
@@ -175,7 +175,7 @@ export default function AIDJView({ onBack }: AIDJViewProps) {
     setShouldAutoScroll(true);
 
     try {
-      const model = createAIModel(provider.provider, provider.apiKey);
+      const model = createAIModel(provider.provider);
 
       const userContext = await buildUserContext();
 
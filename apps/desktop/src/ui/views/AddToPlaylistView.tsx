@@ -50,7 +50,11 @@ export default function AddToPlaylistView({ trackId, trackName, onBack }: AddToP
 
     setAddingTo(playlist.id);
     try {
-      await addTrackToPlaylist(playlist.id, `spotify:track:${trackId}`);
+      const outcome = await addTrackToPlaylist(playlist.id, `spotify:track:${trackId}`);
+      // The offline status pill shows the change as pending until it goes through.
+      if (outcome === "queued") {
+        console.info(`Queued adding the track to ${playlist.name}`);
+      }
       onBack();
     } catch (err) {
       console.error("Failed to add track to playlist:", err);
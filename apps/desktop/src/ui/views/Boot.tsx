@@ -28,31 +28,59 @@ export default function Boot({
   const [clientId, setClientId] = useState("");
   const [clientSecret, setClientSecret] = useState("");
   const [error, setError] = useState<string | null>(null);
-  const [redirectUri, setRedirectUri] = useState("http://127.0.0.1:3000/callback");
-  const [copied, setCopied] = useState(false);
-
-  // Fetch the exact redirect URI from the backend so the setup instructions
-  // always match what MiniFy actually sends to Spotify.
+  const [redirectUris, setRedirectUris] = useState<string[]>([
+    "http://127.0.0.1:3000/callback",
+  ]);
+  const [copied, setCopied] = useState<string | null>(null);
+  const [pastedRedirect, setPastedRedirect] = useState("");
+  const [pasteError, setPasteError] = useState<string | null>(null);
+  const [submittingPaste, setSubmittingPaste] = useState(false);
+
+  // Fetch the exact redirect URIs from the backend so the setup instructions
+  // always match what MiniFy actually sends to Spotify. MiniFy falls back to
+  // the next port when one is taken, so every URI has to be registered.
   useEffect(() => {
-    invoke<string>("get_spotify_redirect_uri")
-      .then(setRedirectUri)
+    invoke<string[]>("get_spotify_redirect_uri")
+      .then(setRedirectUris)
       .catch(() => {});
   }, []);
 
-  const copyRedirectUri = async () => {
+  const copyRedirectUri = async (redirectUri: string) => {
     try {
       await navigator.clipboard.writeText(redirectUri);
-      setCopied(true);
-      setTimeout(() => setCopied(false), 1500);
+      setCopied(redirectUri);
+      setTimeout(() => setCopied(null), 1500);
     } catch {
       // Clipboard may be unavailable; the value is still visible to copy manually.
     }
   };
 
+  // Fallback for when the browser can't reach the local callback server, e.g.
+  // in Flatpak or over remote desktop: the user pastes the redirect URL.
+  const submitPastedRedirect = async () => {
+    if (!pastedRedirect.trim() || submittingPaste) return;
+    setSubmittingPaste(true);
+    setPasteError(null);
+    const cmd =
+      selectedProvider === "youtube"
+        ? "complete_youtube_oauth_flow_manually"
+        : "complete_oauth_flow_manually";
+    try {
+      await invoke(cmd, { input: pastedRedirect });
+      setPastedRedirect("");
+    } catch (e) {
+      setPasteError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setSubmittingPaste(false);
+    }
+  };
+
   const cancelConnecting = async () => {
     const cmd = selectedProvider === "youtube" ? "cancel_youtube_oauth_flow" : "cancel_oauth_flow";
     await invoke(cmd).catch(() => {});
     setError(null);
+    setPasteError(null);
+    setPastedRedirect("");
     setStep("provider");
   };
 
@@ -373,21 +401,23 @@ export default function Boot({
                 </span>
                 <div className="flex-1">
                   <span>
-                    In the app settings, add this exact{" "}
-                    <span className="text-white">Redirect URI</span> and save:
+                    In the app settings, add these exact{" "}
+                    <span className="text-white">Redirect URIs</span> and save:
                   </span>
-                  <div className="mt-2 flex items-stretch gap-2">
-                    <code className="flex-1 min-w-0 truncate bg-black/40 border border-white/10 rounded-lg px-3 py-2 text-xs text-white/90 font-mono">
-                      {redirectUri}
-                    </code>
-                    <button
-                      type="button"
-                      onClick={copyRedirectUri}
-                      className="flex-none px-3 rounded-lg text-xs font-medium border border-white/10 hover:bg-white/10 transition-colors"
-                    >
-                      {copied ? "Copied!" : "Copy"}
-                    </button>
-                  </div>
+                  {redirectUris.map((redirectUri) => (
+                    <div key={redirectUri} className="mt-2 flex items-stretch gap-2">
+                      <code className="flex-1 min-w-0 truncate bg-black/40 border border-white/10 rounded-lg px-3 py-2 text-xs text-white/90 font-mono">
+                        {redirectUri}
+                      </code>
+                      <button
+                        type="button"
+                        onClick={() => copyRedirectUri(redirectUri)}
+                        className="flex-none px-3 rounded-lg text-xs font-medium border border-white/10 hover:bg-white/10 transition-colors"
+                      >
+                        {copied === redirectUri ? "Copied!" : "Copy"}
+                      </button>
+                    </div>
+                  ))}
                   <p className="mt-1 text-xs text-white/40">
                     It must match character for character, or Spotify shows a "redirect_uri not
                     matching" error.
@@ -547,6 +577,31 @@ export default function Boot({
               style={{ animationDelay: "0.3s" }}
             />
           </div>
+          <div className="w-full max-w-xs flex flex-col gap-2">
+            <label htmlFor="pasted-redirect" className="text-xs text-white/40 text-center">
+              Browser shows "can't connect" after logging in? Paste its URL here:
+            </label>
+            <div className="flex items-stretch gap-2">
+              <input
+                id="pasted-redirect"
+                type="text"
+                value={pastedRedirect}
+                onChange={(e) => setPastedRedirect(e.target.value)}
+                onKeyDown={(e) => e.key === "Enter" && submitPastedRedirect()}
+                placeholder="http://127.0.0.1:…/callback?code=…"
+                className="flex-1 min-w-0 bg-black/40 border border-white/10 rounded-lg px-3 py-2 text-xs text-white/90 font-mono focus:outline-none focus:border-white/30"
+              />
+              <button
+                type="button"
+                onClick={submitPastedRedirect}
+                disabled={!pastedRedirect.trim() || submittingPaste}
+                className="flex-none px-3 rounded-lg text-xs font-medium border border-white/10 hover:bg-white/10 transition-colors disabled:opacity-40"
+              >
+                {submittingPaste ? "…" : "Submit"}
+              </button>
+            </div>
+            {pasteError && <p className="text-xs text-red-400 text-center">{pasteError}</p>}
+          </div>
           <button
             type="button"
             onClick={cancelConnecting}
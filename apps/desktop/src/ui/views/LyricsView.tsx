@@ -0,0 +1,118 @@
+import { ArrowLeft } from "@phosphor-icons/react";
+import { useEffect, useRef } from "react";
+import { useLyrics } from "../../hooks/useLyrics";
+import useWindowLayout from "../../hooks/useWindowLayout";
+import type { LyricLine } from "../../lib/lyrics";
+
+type LyricsViewProps = {
+  onBack: () => void;
+};
+
+function SungLine({ line, progressMs }: { line: LyricLine; progressMs: number }) {
+  if (line.words.length === 0) return <>{line.text || "♪"}</>;
+  return (
+    <>
+      {line.words.map((word, i) => (
+        <span
+          key={`${word.start_ms}-${i}`}
+          className="transition-colors duration-150"
+          style={{
+            color:
+              word.start_ms <= progressMs ? "var(--settings-accent)" : "var(--settings-text)",
+          }}
+        >
+          {word.text}
+        </span>
+      ))}
+    </>
+  );
+}
+
+export default function LyricsView({ onBack }: LyricsViewProps) {
+  const { setLayout } = useWindowLayout();
+  const { lyrics, lineIndex, progressMs } = useLyrics();
+  const currentLineRef = useRef<HTMLParagraphElement | null>(null);
+
+  useEffect(() => {
+    setLayout("Lyrics");
+  }, [setLayout]);
+
+  // Keep the line being sung in the middle
+  useEffect(() => {
+    if (lineIndex === null) return;
+    currentLineRef.current?.scrollIntoView({ behavior: "smooth", block: "center" });
+  }, [lineIndex]);
+
+  const lines = lyrics?.lyrics?.lines ?? [];
+  const synced = lyrics?.lyrics?.synced ?? false;
+
+  return (
+    <div className="h-full w-full p-3 flex flex-col" style={{ color: "var(--settings-text)" }}>
+      <div
+        className="flex items-center justify-between mb-2"
+        style={{ color: "var(--settings-header-text)" }}
+      >
+        <h1 className="text-sm font-semibold">Lyrics</h1>
+        <button
+          type="button"
+          onClick={onBack}
+          aria-label="Back"
+          className="rounded-full w-7 h-7 flex items-center justify-center active:scale-[0.95] transition-transform duration-150 hover:bg-[rgba(255,255,255,0.08)]"
+        >
+          <ArrowLeft size={18} weight="bold" />
+        </button>
+      </div>
+
+      <div
+        className="rounded-xl border text-sm p-3 flex-1 overflow-y-auto"
+        style={{
+          background: "var(--settings-panel-bg)",
+          borderColor: "var(--settings-panel-border)",
+        }}
+      >
+        {lines.length === 0 ? (
+          <div
+            className="flex items-center justify-center h-full"
+            style={{ color: "var(--settings-text-muted)" }}
+          >
+            {lyrics ? "No lyrics for this track" : "Looking for lyrics..."}
+          </div>
+        ) : (
+          <div className="flex flex-col gap-2 py-16 text-center">
+            {lines.map((line, i) => {
+              const current = synced && i === lineIndex;
+              return (
+                <p
+                  key={`${line.start_ms}-${i}`}
+                  ref={current ? currentLineRef : undefined}
+                  className={`transition-all duration-200 ${
+                    current ? "text-base font-semibold" : "text-sm"
+                  }`}
+                  style={{
+                    color:
+                      synced && !current ? "var(--settings-text-muted)" : "var(--settings-text)",
+                  }}
+                >
+                  {current ? (
+                    <SungLine line={line} progressMs={progressMs} />
+                  ) : (
+                    line.text || (synced ? "♪" : "\u00a0")
+                  )}
+                </p>
+              );
+            })}
+          </div>
+        )}
+      </div>
+
+      {lyrics?.source && (
+        <div
+          className="text-[10px] text-center mt-1"
+          style={{ color: "var(--settings-text-muted)" }}
+        >
+          Lyrics from {lyrics.source === "folder" ? "your lyrics folder" : lyrics.source}
+        </div>
+      )}
+    </div>
+  );
+}
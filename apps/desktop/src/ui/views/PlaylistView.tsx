@@ -1,6 +1,7 @@
 import { ArrowLeft, MusicNotes, Play, SpinnerGap, Warning } from "@phosphor-icons/react";
 import { useCallback, useEffect, useRef, useState } from "react";
 import useWindowLayout from "../../hooks/useWindowLayout";
+import { albumArtUrl, LIST_ART_SIZE } from "../../lib/albumArt";
 import { getActiveProvider, getActiveProviderType } from "../../providers";
 import type { MusicProviderType, UnifiedPlaylist, UnifiedTrack } from "../../providers/types";
 
@@ -351,7 +352,10 @@ export default function PlaylistView({ onBack }: PlaylistViewProps) {
                 >
                   <ul className="py-2">
                     {tracks.map((track, index) => {
-                      const albumArt = track.album.images[0]?.url;
+                      const albumArt = albumArtUrl(
+                        track.album.images[0]?.url,
+                        LIST_ART_SIZE
+                      );
                       const artistNames = track.artists.map((a) => a.name).join(", ");
                       const isPlaying = playingId === track.id;
 
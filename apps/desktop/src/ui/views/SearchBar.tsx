@@ -7,6 +7,7 @@ import {
 } from "@phosphor-icons/react";
 import { useCallback, useEffect, useRef, useState } from "react";
 import useWindowLayout from "../../hooks/useWindowLayout";
+import { albumArtUrl, LIST_ART_SIZE } from "../../lib/albumArt";
 import { getActiveProvider } from "../../providers";
 import type { UnifiedTrack } from "../../providers/types";
 
@@ -211,7 +212,7 @@ export default function SearchBar({ onBack }: SearchBarProps) {
           {!isLoading && displayTracks.length > 0 && (
             <ul className="py-2">
               {displayTracks.map((track) => {
-                const albumArt = track.album.images[0]?.url;
+                const albumArt = albumArtUrl(track.album.images[0]?.url, LIST_ART_SIZE);
                 const artistNames = track.artists.map((a) => a.name).join(", ");
                 const isPlaying = playingId === track.id;
 
@@ -5,20 +5,28 @@ import {
   Brain,
   Check,
   CircleNotch,
+  ClockCounterClockwise,
+  Copy,
   DiscordLogo,
   Download,
   Eye,
+  FileText,
   FloppyDisk,
   GearSix,
   GithubLogo,
+  Globe,
   Link,
+  MicrophoneStage,
   MusicNote,
   PaintBrush,
+  Plugs,
+  Plus,
   ShieldCheck,
   SignOut,
   SpotifyLogo,
   SquaresFour,
   Trash,
+  User,
   Warning,
   X,
   YoutubeLogo,
@@ -29,26 +37,42 @@ import { listen } from "@tauri-apps/api/event";
 import { openUrl } from "@tauri-apps/plugin-opener";
 import { useCallback, useEffect, useState } from "react";
 import useWindowLayout from "../../hooks/useWindowLayout";
+import { clearLyricsCache, LYRICS_PROVIDERS } from "../../lib/lyrics";
+import {
+  type DeviceStatus,
+  getDeviceStatus,
+  listDevices,
+  onDeviceChanged,
+  setPreferredDevice,
+} from "../../lib/playback/spotifyDevices";
 import {
   type AIProviderConfig,
   type AIProviderType,
+  addSpotifyAccount,
   type CustomTheme,
   deleteAIApiKey,
   deleteCustomTheme,
+  describeSignOutFailures,
   exportCustomTheme,
   hasAIApiKey,
+  listSpotifyAccounts,
   loadCustomThemes,
   type MusicProviderType,
   readSettings,
+  removeSpotifyAccount,
+  type SpotifyAccountList,
   saveAIApiKey,
   saveCustomTheme,
+  type Settings,
+  signOut,
+  switchSpotifyAccount,
   writeSettings,
 } from "../../lib/settingLib";
 import { useUpdaterStore } from "../../lib/updaterStore";
 import { applyCustomThemeFromJson, validateThemeJsonFormat } from "../../loader/themeLoader";
 import { clearProviderCache } from "../../providers";
 import { clearYouTubeState } from "../../providers/youtube";
-import { clearSpotifyTokenCache } from "../spotifyClient";
+import { clearSpotifyRequestCache } from "../spotifyClient";
 
 const AI_PROVIDERS: { id: AIProviderType; name: string; model: string; color: string }[] = [
   { id: "openai", name: "OpenAI", model: "GPT-4o Mini", color: "#10A37F" },
@@ -114,6 +138,12 @@ async function validateAIApiKey(provider: AIProviderType, apiKey: string): Promi
   }
 }
 
+type ApiServerStatus = {
+  running: boolean;
+  address: string | null;
+  error: string | null;
+};
+
 type SettingsProps = {
   onBack: () => void;
   onUpdateLayout?: (layout: string) => void;
@@ -240,6 +270,11 @@ export default function Settings({
 
   const [spotifyConnected, setSpotifyConnected] = useState<boolean>(false);
   const [spotifyLoading, setSpotifyLoading] = useState<boolean>(false);
+  const [spotifyAccounts, setSpotifyAccounts] = useState<SpotifyAccountList>({
+    accounts: [],
+    active_account: null,
+  });
+  const [deviceStatus, setDeviceStatus] = useState<DeviceStatus | null>(null);
   const [youtubeConnected, setYoutubeConnected] = useState<boolean>(false);
   const [youtubeLoading, setYoutubeLoading] = useState<boolean>(false);
 
@@ -266,8 +301,31 @@ export default function Settings({
   const [musicVisualizerColor, setMusicVisualizerColor] = useState<string>("theme");
   const [musicVisualizerIntensity, setMusicVisualizerIntensity] = useState<number>(100);
   const [discordRpcEnabled, setDiscordRpcEnabled] = useState<boolean>(true);
+  const [resumeOnLaunch, setResumeOnLaunch] = useState<boolean>(false);
+  const [resumeRewindSecs, setResumeRewindSecs] = useState<number>(5);
+  const [resumeMaxAgeHours, setResumeMaxAgeHours] = useState<number>(24);
+  const [lyricsFolder, setLyricsFolder] = useState<string>("");
+  const [lyricsProviders, setLyricsProviders] = useState<string[]>(["lrclib"]);
+  const [lyricsCleared, setLyricsCleared] = useState<boolean>(false);
+  const [lyricsError, setLyricsError] = useState<string | null>(null);
+  const [apiServerEnabled, setApiServerEnabled] = useState<boolean>(false);
+  const [apiServerStatus, setApiServerStatus] = useState<ApiServerStatus | null>(null);
+  const [apiServerToken, setApiServerToken] = useState<string | null>(null);
+  const [nowPlayingFilesEnabled, setNowPlayingFilesEnabled] = useState<boolean>(false);
+  const [nowPlayingTextPath, setNowPlayingTextPath] = useState<string>("");
+  const [nowPlayingTemplate, setNowPlayingTemplate] = useState<string>("{artist} - {title}");
+  const [nowPlayingJsonPath, setNowPlayingJsonPath] = useState<string>("");
+  const [nowPlayingCoverPath, setNowPlayingCoverPath] = useState<string>("");
+  const [httpConnectTimeout, setHttpConnectTimeout] = useState<number>(10);
+  const [httpReadTimeout, setHttpReadTimeout] = useState<number>(30);
+  const [httpProxy, setHttpProxy] = useState<string>("");
+  const [httpCaCertificatePath, setHttpCaCertificatePath] = useState<string>("");
+  const [networkError, setNetworkError] = useState<string | null>(null);
+  const [albumArtCacheMb, setAlbumArtCacheMb] = useState<number>(200);
+  const [albumArtCleared, setAlbumArtCleared] = useState<boolean>(false);
   const [windowOpacity, setWindowOpacity] = useState<number>(100);
   const [showClearDialog, setShowClearDialog] = useState<boolean>(false);
+  const [signOutWarning, setSignOutWarning] = useState<string | null>(null);
   const [clearingData, setClearingData] = useState<boolean>(false);
 
   const refreshCustomThemes = useCallback(async () => {
@@ -278,6 +336,7 @@ export default function Settings({
   const checkSpotifyConnection = useCallback(async () => {
     const hasTokens = await invoke<boolean>("has_valid_tokens");
     setSpotifyConnected(hasTokens);
+    setSpotifyAccounts(await listSpotifyAccounts());
     return hasTokens;
   }, []);
 
@@ -293,14 +352,14 @@ export default function Settings({
 
     if (spotifyOk && !youtubeOk && activeMusicProvider !== "spotify") {
       clearYouTubeState();
-      clearSpotifyTokenCache();
+      clearSpotifyRequestCache();
       clearProviderCache();
       setActiveMusicProvider("spotify");
       await writeSettings({ active_music_provider: "spotify" });
       onMusicProviderChange?.("spotify");
     } else if (!spotifyOk && youtubeOk && activeMusicProvider !== "youtube") {
       clearYouTubeState();
-      clearSpotifyTokenCache();
+      clearSpotifyRequestCache();
       clearProviderCache();
       setActiveMusicProvider("youtube");
       await writeSettings({ active_music_provider: "youtube" });
@@ -348,6 +407,23 @@ export default function Settings({
       setMusicVisualizerColor(settings.music_visualizer_color ?? "theme");
       setMusicVisualizerIntensity(settings.music_visualizer_intensity ?? 100);
       setDiscordRpcEnabled(settings.discord_rpc_enabled ?? true);
+      setResumeOnLaunch(settings.resume_on_launch ?? false);
+      setResumeRewindSecs(settings.resume_rewind_secs ?? 5);
+      setResumeMaxAgeHours(settings.resume_max_age_hours ?? 24);
+      setLyricsFolder(settings.lyrics_folder ?? "");
+      setLyricsProviders(settings.lyrics_providers ?? ["lrclib"]);
+      setApiServerEnabled(settings.api_server_enabled ?? false);
+      setApiServerStatus(await invoke<ApiServerStatus>("get_api_server_status"));
+      setNowPlayingFilesEnabled(settings.now_playing_files_enabled ?? false);
+      setNowPlayingTextPath(settings.now_playing_text_path ?? "");
+      setNowPlayingTemplate(settings.now_playing_template ?? "{artist} - {title}");
+      setNowPlayingJsonPath(settings.now_playing_json_path ?? "");
+      setNowPlayingCoverPath(settings.now_playing_cover_path ?? "");
+      setHttpConnectTimeout(settings.http_connect_timeout_secs ?? 10);
+      setHttpReadTimeout(settings.http_read_timeout_secs ?? 30);
+      setHttpProxy(settings.http_proxy ?? "");
+      setHttpCaCertificatePath(settings.http_ca_certificate_path ?? "");
+      setAlbumArtCacheMb(settings.album_art_cache_mb ?? 200);
       setWindowOpacity(settings.window_opacity ?? 100);
       await refreshCustomThemes();
       await checkSpotifyConnection();
@@ -362,6 +438,20 @@ export default function Settings({
     autoActivateSingleProvider,
   ]);
 
+  useEffect(() => {
+    if (!spotifyConnected) return;
+
+    const unlisten = onDeviceChanged(setDeviceStatus);
+    listDevices()
+      .catch((err) => console.warn("Failed to list Spotify devices:", err))
+      .then(getDeviceStatus)
+      .then(setDeviceStatus)
+      .catch(() => {});
+    return () => {
+      unlisten.then((u) => u());
+    };
+  }, [spotifyConnected]);
+
   useEffect(() => {
     const setupOAuthListener = async () => {
       const unlistenSuccess = await listen("oauth-success", async () => {
@@ -378,12 +468,17 @@ export default function Settings({
       const unlistenYTFailed = await listen("youtube-oauth-failed", () => {
         setYoutubeLoading(false);
       });
+      const unlistenAccountChanged = await listen("account-changed", async () => {
+        clearSpotifyRequestCache();
+        await checkSpotifyConnection();
+      });
 
       return () => {
         unlistenSuccess();
         unlistenFailed();
         unlistenYTSuccess();
         unlistenYTFailed();
+        unlistenAccountChanged();
       };
     };
 
@@ -395,7 +490,8 @@ export default function Settings({
 
   const handleSpotifyLogout = async () => {
     setSpotifyLoading(true);
-    await invoke("clear_credentials");
+    const report = await signOut("spotify");
+    setSignOutWarning(describeSignOutFailures("Spotify", report));
     setSpotifyConnected(false);
     setSpotifyLoading(false);
     onResetAuth?.();
@@ -405,13 +501,48 @@ export default function Settings({
     onResetAuth?.("spotify");
   };
 
+  const handleAddSpotifyAccount = async () => {
+    setSpotifyLoading(true);
+    try {
+      await addSpotifyAccount();
+    } catch (err) {
+      console.error("Failed to add Spotify account:", err);
+      setSpotifyLoading(false);
+    }
+  };
+
+  const handleSwitchSpotifyAccount = async (accountId: string) => {
+    try {
+      await switchSpotifyAccount(accountId);
+    } catch (err) {
+      console.error("Failed to switch Spotify account:", err);
+    }
+  };
+
+  const handleSetPreferredDevice = async (deviceId: string | null) => {
+    try {
+      await setPreferredDevice(deviceId);
+    } catch (err) {
+      console.error("Failed to set preferred device:", err);
+    }
+  };
+
+  const handleRemoveSpotifyAccount = async (accountId: string) => {
+    try {
+      await removeSpotifyAccount(accountId);
+    } catch (err) {
+      console.error("Failed to remove Spotify account:", err);
+    }
+  };
+
   const handleYouTubeConnect = async () => {
     onResetAuth?.("youtube");
   };
 
   const handleYouTubeLogout = async () => {
     setYoutubeLoading(true);
-    await invoke("clear_youtube_credentials");
+    const report = await signOut("youtube");
+    setSignOutWarning(describeSignOutFailures("YouTube", report));
     setYoutubeConnected(false);
     setYoutubeLoading(false);
   };
@@ -488,7 +619,7 @@ export default function Settings({
 
   const handleSetActiveMusicProvider = async (provider: MusicProviderType) => {
     clearYouTubeState();
-    clearSpotifyTokenCache();
+    clearSpotifyRequestCache();
     clearProviderCache();
     setActiveMusicProvider(provider);
     await writeSettings({ active_music_provider: provider });
@@ -537,6 +668,90 @@ export default function Settings({
     }
   };
 
+  const handleToggleApiServer = async () => {
+    const newValue = !apiServerEnabled;
+    setApiServerEnabled(newValue);
+    await writeSettings({ api_server_enabled: newValue });
+    try {
+      setApiServerStatus(await invoke<ApiServerStatus>("restart_api_server"));
+    } catch (err) {
+      console.warn("API server toggle failed:", err);
+    }
+  };
+
+  const handleRevealApiToken = async () => {
+    try {
+      setApiServerToken(await invoke<string>("get_api_server_token"));
+    } catch (err) {
+      console.warn("Failed to read API token:", err);
+    }
+  };
+
+  const handleRegenerateApiToken = async () => {
+    try {
+      setApiServerToken(await invoke<string>("regenerate_api_server_token"));
+    } catch (err) {
+      console.warn("Failed to regenerate API token:", err);
+    }
+  };
+
+  const handleToggleResumeOnLaunch = async () => {
+    const newValue = !resumeOnLaunch;
+    setResumeOnLaunch(newValue);
+    await writeSettings({ resume_on_launch: newValue });
+  };
+
+  const handleToggleLyricsProvider = async (provider: string) => {
+    const newValue = lyricsProviders.includes(provider)
+      ? lyricsProviders.filter((p) => p !== provider)
+      : [...lyricsProviders, provider];
+    setLyricsProviders(newValue);
+    await writeSettings({ lyrics_providers: newValue });
+  };
+
+  const handleClearLyricsCache = async () => {
+    try {
+      await clearLyricsCache();
+      setLyricsCleared(true);
+      setLyricsError(null);
+    } catch (err) {
+      setLyricsError(String(err));
+    }
+  };
+
+  const handleToggleNowPlayingFiles = async () => {
+    const newValue = !nowPlayingFilesEnabled;
+    setNowPlayingFilesEnabled(newValue);
+    await writeSettings({ now_playing_files_enabled: newValue });
+  };
+
+  const saveNowPlayingPath = async (
+    key: "now_playing_text_path" | "now_playing_json_path" | "now_playing_cover_path",
+    value: string,
+  ) => {
+    await writeSettings({ [key]: value.trim() || null });
+  };
+
+  const saveNetworkSettings = async (update: Partial<Settings>) => {
+    await writeSettings(update);
+    try {
+      await invoke("apply_network_settings");
+      setNetworkError(null);
+    } catch (err) {
+      setNetworkError(String(err));
+    }
+  };
+
+  const handleClearAlbumArtCache = async () => {
+    try {
+      await invoke("clear_album_art_cache");
+      setAlbumArtCleared(true);
+      setNetworkError(null);
+    } catch (err) {
+      setNetworkError(String(err));
+    }
+  };
+
   const handleWindowOpacityChange = async (value: number) => {
     const nextOpacity = Math.min(100, Math.max(35, value));
     setWindowOpacity(nextOpacity);
@@ -835,6 +1050,295 @@ export default function Settings({
                 );
               })}
 
+              {spotifyConnected && (
+                <div className="flex flex-col gap-2">
+                  <div className="text-xs font-medium text-[--settings-text-muted]">
+                    Spotify accounts
+                  </div>
+                  {spotifyAccounts.accounts.map((account) => {
+                    const isCurrent = account.id === spotifyAccounts.active_account;
+                    return (
+                      <div
+                        key={account.id}
+                        className="flex items-center justify-between p-3 rounded-lg border"
+                        style={{
+                          background: "rgba(0, 0, 0, 0.2)",
+                          borderColor: isCurrent ? "#1DB95450" : "rgba(255, 255, 255, 0.1)",
+                        }}
+                      >
+                        <div className="flex items-center gap-3 min-w-0">
+                          {account.avatar_url ? (
+                            <img
+                              src={account.avatar_url}
+                              alt=""
+                              className="w-8 h-8 rounded-full object-cover"
+                            />
+                          ) : (
+                            <div className="w-8 h-8 rounded-full flex items-center justify-center bg-white/10">
+                              <User size={16} />
+                            </div>
+                          )}
+                          <div className="flex flex-col min-w-0">
+                            <span className="text-sm truncate">{account.display_name}</span>
+                            {isCurrent && (
+                              <span className="text-xs" style={{ color: "#1DB954" }}>
+                                Current
+                              </span>
+                            )}
+                          </div>
+                        </div>
+                        <div className="flex items-center gap-2">
+                          {!isCurrent && (
+                            <button
+                              type="button"
+                              onClick={() => handleSwitchSpotifyAccount(account.id)}
+                              className="text-xs px-2 py-1.5 rounded-lg border border-white/20 hover:bg-white/10 transition-colors cursor-pointer"
+                            >
+                              Switch
+                            </button>
+                          )}
+                          <button
+                            type="button"
+                            onClick={() => handleRemoveSpotifyAccount(account.id)}
+                            title="Remove account"
+                            className="p-1.5 rounded-lg text-red-400 hover:bg-red-500/20 transition-colors cursor-pointer"
+                          >
+                            <Trash size={14} />
+                          </button>
+                        </div>
+                      </div>
+                    );
+                  })}
+                  <button
+                    type="button"
+                    onClick={handleAddSpotifyAccount}
+                    disabled={spotifyLoading}
+                    className="flex items-center justify-center gap-2 px-3 py-2 rounded-lg border border-white/20 hover:bg-white/10 transition-colors cursor-pointer disabled:opacity-50 disabled:cursor-not-allowed"
+                  >
+                    <Plus size={14} />
+                    <span className="text-sm">
+                      {spotifyLoading ? "Waiting for login..." : "Add account"}
+                    </span>
+                  </button>
+                </div>
+              )}
+
+              {spotifyConnected && deviceStatus && deviceStatus.devices.length > 0 && (
+                <div className="flex flex-col gap-2">
+                  <div className="text-xs font-medium text-[--settings-text-muted]">
+                    Spotify devices
+                  </div>
+                  {deviceStatus.devices.map((device) => {
+                    const isPreferred = device.id === deviceStatus.preferred?.id;
+                    return (
+                      <div
+                        key={device.id ?? device.name}
+                        className="flex items-center justify-between p-3 rounded-lg border"
+                        style={{
+                          background: "rgba(0, 0, 0, 0.2)",
+                          borderColor: isPreferred ? "#1DB95450" : "rgba(255, 255, 255, 0.1)",
+                        }}
+                      >
+                        <div className="flex flex-col min-w-0">
+                          <span className="text-sm truncate">{device.name}</span>
+                          <span className="text-xs text-[--settings-text-muted]">
+                            {device.type}
+                            {device.is_active ? " · Playing here" : ""}
+                          </span>
+                        </div>
+                        {isPreferred ? (
+                          <button
+                            type="button"
+                            onClick={() => handleSetPreferredDevice(null)}
+                            title="Stop preferring this device"
+                            className="flex items-center gap-1 text-xs px-2 py-1.5 rounded-lg border border-white/20 hover:bg-white/10 transition-colors cursor-pointer"
+                            style={{ color: "#1DB954" }}
+                          >
+                            <Check size={12} />
+                            Preferred
+                          </button>
+                        ) : (
+                          device.id && (
+                            <button
+                              type="button"
+                              onClick={() => handleSetPreferredDevice(device.id)}
+                              className="text-xs px-2 py-1.5 rounded-lg border border-white/20 hover:bg-white/10 transition-colors cursor-pointer"
+                            >
+                              Prefer
+                            </button>
+                          )
+                        )}
+                      </div>
+                    );
+                  })}
+                  <span className="text-xs text-[--settings-text-muted]">
+                    Playback moves to the preferred device when it comes online, unless music is
+                    already playing elsewhere.
+                  </span>
+                </div>
+              )}
+
+              {signOutWarning && (
+                <div className="flex items-start gap-1 text-xs text-yellow-400">
+                  <Warning size={12} className="mt-0.5 shrink-0" />
+                  {signOutWarning}
+                </div>
+              )}
+
+              <div className="border-t border-white/10 my-2" />
+
+              <div className="font-medium flex items-center gap-2">
+                <ClockCounterClockwise size={18} weight="fill" />
+                Resume on Launch
+              </div>
+              <p className="text-xs text-[--settings-text-muted]">
+                Start the last Spotify track where you left off when MiniFy opens
+              </p>
+
+              <div
+                className="flex flex-col gap-3 p-4 rounded-xl border"
+                style={{
+                  background: "rgba(0, 0, 0, 0.2)",
+                  borderColor: resumeOnLaunch
+                    ? "var(--settings-accent)"
+                    : "rgba(255, 255, 255, 0.1)",
+                }}
+              >
+                <div className="flex items-center justify-between">
+                  <div className="flex flex-col">
+                    <span className="font-medium">Resume playback</span>
+                    <span className="text-xs text-[--settings-text-muted]">
+                      Skipped when something else is already playing
+                    </span>
+                  </div>
+
+                  <button
+                    type="button"
+                    onClick={handleToggleResumeOnLaunch}
+                    className={`relative w-10 h-5 rounded-full transition-colors duration-200 flex-shrink-0 ${
+                      resumeOnLaunch ? "bg-[--settings-accent]" : "bg-white/20"
+                    }`}
+                  >
+                    <span
+                      className={`absolute top-0.5 left-0.5 w-4 h-4 rounded-full bg-white transition-all duration-200 ${
+                        resumeOnLaunch ? "translate-x-5" : "translate-x-0"
+                      }`}
+                    />
+                  </button>
+                </div>
+
+                {resumeOnLaunch && (
+                  <>
+                    <div className="flex items-center justify-between gap-3">
+                      <span className="text-xs">Rewind (seconds)</span>
+                      <input
+                        type="number"
+                        min={0}
+                        value={resumeRewindSecs}
+                        onChange={(e) => setResumeRewindSecs(Number(e.target.value))}
+                        onBlur={() =>
+                          writeSettings({
+                            resume_rewind_secs: Math.max(0, Math.round(resumeRewindSecs)),
+                          })
+                        }
+                        className="w-20 px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                        style={{ color: "var(--settings-text)" }}
+                      />
+                    </div>
+                    <div className="flex items-center justify-between gap-3">
+                      <span className="text-xs">Only if played within (hours)</span>
+                      <input
+                        type="number"
+                        min={1}
+                        value={resumeMaxAgeHours}
+                        onChange={(e) => setResumeMaxAgeHours(Number(e.target.value))}
+                        onBlur={() =>
+                          writeSettings({
+                            resume_max_age_hours: Math.max(1, Math.round(resumeMaxAgeHours)),
+                          })
+                        }
+                        className="w-20 px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                        style={{ color: "var(--settings-text)" }}
+                      />
+                    </div>
+                  </>
+                )}
+              </div>
+
+              <div className="border-t border-white/10 my-2" />
+
+              <div className="font-medium flex items-center gap-2">
+                <MicrophoneStage size={18} weight="fill" />
+                Lyrics
+              </div>
+              <p className="text-xs text-[--settings-text-muted]">
+                Shown with Ctrl+Y. Files in your lyrics folder are used first
+              </p>
+
+              <div
+                className="flex flex-col gap-3 p-4 rounded-xl border"
+                style={{
+                  background: "rgba(0, 0, 0, 0.2)",
+                  borderColor: "rgba(255, 255, 255, 0.1)",
+                }}
+              >
+                <input
+                  type="text"
+                  placeholder="Lyrics folder, e.g. Artist - Title.lrc or Artist/Title.lrc"
+                  value={lyricsFolder}
+                  onChange={(e) => setLyricsFolder(e.target.value)}
+                  onBlur={() => writeSettings({ lyrics_folder: lyricsFolder.trim() || null })}
+                  className="px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                  style={{ color: "var(--settings-text)" }}
+                />
+
+                {LYRICS_PROVIDERS.map(({ id, name }) => {
+                  const enabled = lyricsProviders.includes(id);
+                  return (
+                    <div key={id} className="flex items-center justify-between">
+                      <div className="flex flex-col">
+                        <span className="font-medium">Fetch lyrics from {name}</span>
+                        <span className="text-xs text-[--settings-text-muted]">
+                          Sends the title, artist and album of each track
+                        </span>
+                      </div>
+
+                      <button
+                        type="button"
+                        onClick={() => handleToggleLyricsProvider(id)}
+                        className={`relative w-10 h-5 rounded-full transition-colors duration-200 flex-shrink-0 ${
+                          enabled ? "bg-[--settings-accent]" : "bg-white/20"
+                        }`}
+                      >
+                        <span
+                          className={`absolute top-0.5 left-0.5 w-4 h-4 rounded-full bg-white transition-all duration-200 ${
+                            enabled ? "translate-x-5" : "translate-x-0"
+                          }`}
+                        />
+                      </button>
+                    </div>
+                  );
+                })}
+
+                <div className="flex items-center justify-between gap-3">
+                  <span className="text-xs">Downloaded lyrics</span>
+                  <button
+                    type="button"
+                    onClick={handleClearLyricsCache}
+                    disabled={lyricsCleared}
+                    className="text-xs px-2 py-1.5 rounded-lg border border-white/20 hover:bg-white/10 transition-colors cursor-pointer disabled:opacity-50 disabled:cursor-default"
+                  >
+                    {lyricsCleared ? "Cleared" : "Clear"}
+                  </button>
+                </div>
+                {lyricsError && (
+                  <div className="flex items-start gap-1 text-xs text-yellow-400">
+                    <Warning size={12} className="mt-0.5 shrink-0" />
+                    {lyricsError}
+                  </div>
+                )}
+              </div>
+
               <div className="border-t border-white/10 my-2" />
 
               <div className="font-medium flex items-center gap-2">
@@ -899,6 +1403,279 @@ export default function Settings({
 
               <div className="border-t border-white/10 my-2" />
 
+              <div className="font-medium flex items-center gap-2">
+                <Plugs size={18} weight="fill" />
+                Local Control API
+              </div>
+              <p className="text-xs text-[--settings-text-muted]">
+                Let Stream Deck, Touch Portal and overlays control MiniFy over HTTP and WebSocket
+              </p>
+
+              <div
+                className="flex flex-col gap-3 p-4 rounded-xl border"
+                style={{
+                  background: "rgba(0, 0, 0, 0.2)",
+                  borderColor: apiServerStatus?.running
+                    ? "var(--settings-accent)"
+                    : "rgba(255, 255, 255, 0.1)",
+                }}
+              >
+                <div className="flex items-center justify-between">
+                  <div className="flex flex-col">
+                    <span className="font-medium">Control API</span>
+                    <span className="text-xs text-[--settings-text-muted]">
+                      {apiServerStatus?.running
+                        ? `Listening on ${apiServerStatus.address}`
+                        : (apiServerStatus?.error ?? "Disabled")}
+                    </span>
+                  </div>
+
+                  <button
+                    type="button"
+                    onClick={handleToggleApiServer}
+                    className={`relative w-10 h-5 rounded-full transition-colors duration-200 flex-shrink-0 ${
+                      apiServerEnabled ? "bg-[--settings-accent]" : "bg-white/20"
+                    }`}
+                  >
+                    <span
+                      className={`absolute top-0.5 left-0.5 w-4 h-4 rounded-full bg-white transition-all duration-200 ${
+                        apiServerEnabled ? "translate-x-5" : "translate-x-0"
+                      }`}
+                    />
+                  </button>
+                </div>
+
+                {apiServerEnabled && (
+                  <div className="flex items-center gap-2">
+                    {apiServerToken ? (
+                      <>
+                        <code className="flex-1 text-xs truncate px-2 py-1 rounded bg-black/30">
+                          {apiServerToken}
+                        </code>
+                        <button
+                          type="button"
+                          onClick={() => navigator.clipboard.writeText(apiServerToken)}
+                          className="p-1.5 rounded-lg hover:bg-white/10 transition-colors cursor-pointer"
+                          title="Copy token"
+                        >
+                          <Copy size={14} />
+                        </button>
+                        <button
+                          type="button"
+                          onClick={handleRegenerateApiToken}
+                          className="p-1.5 rounded-lg hover:bg-white/10 transition-colors cursor-pointer"
+                          title="Regenerate token"
+                        >
+                          <ArrowClockwise size={14} />
+                        </button>
+                      </>
+                    ) : (
+                      <button
+                        type="button"
+                        onClick={handleRevealApiToken}
+                        className="text-xs px-3 py-1.5 rounded-lg bg-white/10 hover:bg-white/20 transition-colors cursor-pointer"
+                      >
+                        Show access token
+                      </button>
+                    )}
+                  </div>
+                )}
+              </div>
+
+              <div className="border-t border-white/10 my-2" />
+
+              <div className="font-medium flex items-center gap-2">
+                <FileText size={18} weight="fill" />
+                Now Playing Files
+              </div>
+              <p className="text-xs text-[--settings-text-muted]">
+                Write the current track to files for OBS text sources and other streaming tools
+              </p>
+
+              <div
+                className="flex flex-col gap-3 p-4 rounded-xl border"
+                style={{
+                  background: "rgba(0, 0, 0, 0.2)",
+                  borderColor: nowPlayingFilesEnabled
+                    ? "var(--settings-accent)"
+                    : "rgba(255, 255, 255, 0.1)",
+                }}
+              >
+                <div className="flex items-center justify-between">
+                  <div className="flex flex-col">
+                    <span className="font-medium">Write files</span>
+                    <span className="text-xs text-[--settings-text-muted]">
+                      Leave a path empty to skip that file
+                    </span>
+                  </div>
+
+                  <button
+                    type="button"
+                    onClick={handleToggleNowPlayingFiles}
+                    className={`relative w-10 h-5 rounded-full transition-colors duration-200 flex-shrink-0 ${
+                      nowPlayingFilesEnabled ? "bg-[--settings-accent]" : "bg-white/20"
+                    }`}
+                  >
+                    <span
+                      className={`absolute top-0.5 left-0.5 w-4 h-4 rounded-full bg-white transition-all duration-200 ${
+                        nowPlayingFilesEnabled ? "translate-x-5" : "translate-x-0"
+                      }`}
+                    />
+                  </button>
+                </div>
+
+                {nowPlayingFilesEnabled && (
+                  <div className="flex flex-col gap-2">
+                    <input
+                      type="text"
+                      placeholder="Full path of the text file"
+                      value={nowPlayingTextPath}
+                      onChange={(e) => setNowPlayingTextPath(e.target.value)}
+                      onBlur={() => saveNowPlayingPath("now_playing_text_path", nowPlayingTextPath)}
+                      className="px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                      style={{ color: "var(--settings-text)" }}
+                    />
+                    <input
+                      type="text"
+                      placeholder="{artist} - {title}"
+                      value={nowPlayingTemplate}
+                      onChange={(e) => setNowPlayingTemplate(e.target.value)}
+                      onBlur={() => writeSettings({ now_playing_template: nowPlayingTemplate })}
+                      className="px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                      style={{ color: "var(--settings-text)" }}
+                    />
+                    <input
+                      type="text"
+                      placeholder="Full path of the JSON file"
+                      value={nowPlayingJsonPath}
+                      onChange={(e) => setNowPlayingJsonPath(e.target.value)}
+                      onBlur={() => saveNowPlayingPath("now_playing_json_path", nowPlayingJsonPath)}
+                      className="px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                      style={{ color: "var(--settings-text)" }}
+                    />
+                    <input
+                      type="text"
+                      placeholder="Full path of the cover image, e.g. …/cover.jpg"
+                      value={nowPlayingCoverPath}
+                      onChange={(e) => setNowPlayingCoverPath(e.target.value)}
+                      onBlur={() => saveNowPlayingPath("now_playing_cover_path", nowPlayingCoverPath)}
+                      className="px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                      style={{ color: "var(--settings-text)" }}
+                    />
+                    <span className="text-xs text-[--settings-text-muted]">
+                      Placeholders: {"{title} {artist} {album} {status} {position} {duration}"}
+                    </span>
+                  </div>
+                )}
+              </div>
+
+              <div className="border-t border-white/10 my-2" />
+
+              <div className="font-medium flex items-center gap-2">
+                <Globe size={18} weight="fill" />
+                Network
+              </div>
+              <p className="text-xs text-[--settings-text-muted]">
+                Timeouts, proxy, certificates and the album art cache
+              </p>
+
+              <div
+                className="flex flex-col gap-3 p-4 rounded-xl border"
+                style={{
+                  background: "rgba(0, 0, 0, 0.2)",
+                  borderColor: "rgba(255, 255, 255, 0.1)",
+                }}
+              >
+                <div className="flex items-center justify-between gap-3">
+                  <span className="text-xs">Connect timeout (seconds)</span>
+                  <input
+                    type="number"
+                    min={1}
+                    value={httpConnectTimeout}
+                    onChange={(e) => setHttpConnectTimeout(Number(e.target.value))}
+                    onBlur={() =>
+                      saveNetworkSettings({
+                        http_connect_timeout_secs: Math.max(1, Math.round(httpConnectTimeout)),
+                      })
+                    }
+                    className="w-20 px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                    style={{ color: "var(--settings-text)" }}
+                  />
+                </div>
+                <div className="flex items-center justify-between gap-3">
+                  <span className="text-xs">Read timeout (seconds)</span>
+                  <input
+                    type="number"
+                    min={1}
+                    value={httpReadTimeout}
+                    onChange={(e) => setHttpReadTimeout(Number(e.target.value))}
+                    onBlur={() =>
+                      saveNetworkSettings({
+                        http_read_timeout_secs: Math.max(1, Math.round(httpReadTimeout)),
+                      })
+                    }
+                    className="w-20 px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                    style={{ color: "var(--settings-text)" }}
+                  />
+                </div>
+                <input
+                  type="text"
+                  placeholder="Proxy, e.g. http://proxy:8080 or socks5://127.0.0.1:1080"
+                  value={httpProxy}
+                  onChange={(e) => setHttpProxy(e.target.value)}
+                  onBlur={() => saveNetworkSettings({ http_proxy: httpProxy.trim() || null })}
+                  className="px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                  style={{ color: "var(--settings-text)" }}
+                />
+                <input
+                  type="text"
+                  placeholder="Full path of an extra CA certificate (PEM)"
+                  value={httpCaCertificatePath}
+                  onChange={(e) => setHttpCaCertificatePath(e.target.value)}
+                  onBlur={() =>
+                    saveNetworkSettings({
+                      http_ca_certificate_path: httpCaCertificatePath.trim() || null,
+                    })
+                  }
+                  className="px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                  style={{ color: "var(--settings-text)" }}
+                />
+                <div className="flex items-center justify-between gap-3">
+                  <span className="text-xs">Album art cache (MB)</span>
+                  <div className="flex items-center gap-2">
+                    <input
+                      type="number"
+                      min={0}
+                      value={albumArtCacheMb}
+                      onChange={(e) => setAlbumArtCacheMb(Number(e.target.value))}
+                      onBlur={() =>
+                        writeSettings({
+                          album_art_cache_mb: Math.max(0, Math.round(albumArtCacheMb)),
+                        })
+                      }
+                      className="w-20 px-3 py-2 rounded-lg border border-white/10 bg-black/30 text-xs focus:outline-none focus:border-[--settings-accent]"
+                      style={{ color: "var(--settings-text)" }}
+                    />
+                    <button
+                      type="button"
+                      onClick={handleClearAlbumArtCache}
+                      disabled={albumArtCleared}
+                      className="text-xs px-2 py-1.5 rounded-lg border border-white/20 hover:bg-white/10 transition-colors cursor-pointer disabled:opacity-50 disabled:cursor-default"
+                    >
+                      {albumArtCleared ? "Cleared" : "Clear"}
+                    </button>
+                  </div>
+                </div>
+                {networkError && (
+                  <div className="flex items-start gap-1 text-xs text-yellow-400">
+                    <Warning size={12} className="mt-0.5 shrink-0" />
+                    {networkError}
+                  </div>
+                )}
+              </div>
+
+              <div className="border-t border-white/10 my-2" />
+
               <div className="font-medium flex items-center gap-2">
                 <Brain size={18} weight="fill" />
                 AI Provider
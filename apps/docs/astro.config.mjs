@@ -62,6 +62,8 @@ export default defineConfig({
             { label: "Layouts", slug: "desktop/layouts" },
             { label: "Themes", slug: "desktop/themes" },
             { label: "Keyboard Shortcuts", slug: "desktop/shortcuts" },
+            { label: "Command Line", slug: "desktop/command-line" },
+            { label: "Control API", slug: "desktop/control-api" },
           ],
         },
         {